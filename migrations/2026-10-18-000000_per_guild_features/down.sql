@@ -0,0 +1,10 @@
+DELETE FROM features WHERE guild_id <> 0;
+
+ALTER TABLE features
+  DROP CONSTRAINT features_name_guild_id_key;
+
+ALTER TABLE features
+  ADD CONSTRAINT features_name_key UNIQUE (name);
+
+ALTER TABLE features
+  DROP COLUMN guild_id;
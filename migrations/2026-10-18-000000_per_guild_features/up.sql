@@ -0,0 +1,11 @@
+-- Per-guild feature flags.
+-- Rows with guild_id = 0 are the global defaults; a row for a specific guild
+-- overrides the default for that guild only. See src/features/mod.rs.
+ALTER TABLE features
+  ADD COLUMN guild_id BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE features
+  DROP CONSTRAINT features_name_key;
+
+ALTER TABLE features
+  ADD CONSTRAINT features_name_guild_id_key UNIQUE (name, guild_id);
@@ -0,0 +1,16 @@
+-- Per-guild configuration, one row per guild in `servers`.
+-- NULL channels / empty role lists fall back to the legacy defaults in
+-- src/tugbot/guild_config.rs. Managed with the /config command.
+CREATE TABLE guild_settings (
+    guild_id                BIGINT      PRIMARY KEY,
+    ask_channel_id          BIGINT,
+    mod_log_channel_id      BIGINT,
+    gulag_channel_id        BIGINT,
+    moderator_role_ids      BIGINT[]    NOT NULL DEFAULT '{}',
+    cull_whitelist_role_ids BIGINT[]    NOT NULL DEFAULT '{}',
+    updated_at              TIMESTAMP   NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO guild_settings (guild_id)
+SELECT DISTINCT guild_id FROM servers
+ON CONFLICT (guild_id) DO NOTHING;
@@ -0,0 +1,14 @@
+-- Per-guild grants of bot capabilities to roles or individual users.
+-- A capability with no grants in a guild falls back to the guild's
+-- moderator roles (see guild_settings).
+CREATE TABLE permission_grants (
+    id SERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    capability VARCHAR NOT NULL,
+    grantee_type VARCHAR NOT NULL CHECK (grantee_type IN ('role', 'user')),
+    grantee_id BIGINT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE(guild_id, capability, grantee_type, grantee_id)
+);
+
+CREATE INDEX idx_permission_grants_guild_capability ON permission_grants (guild_id, capability);
@@ -0,0 +1,17 @@
+-- Append-only audit log of gulag actions. Rows are never updated or deleted.
+-- action: 'gulag' or 'release'
+-- source: how the action was triggered (slash_command, reaction_vote, ai_slop,
+--         goku_poll, elon, slow_user, rejoin, timer)
+CREATE TABLE moderation_events (
+    id BIGSERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    action VARCHAR NOT NULL,
+    source VARCHAR NOT NULL,
+    moderator_id BIGINT,
+    reason TEXT,
+    duration_seconds INTEGER,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_moderation_events_guild_user ON moderation_events (guild_id, user_id, created_at);
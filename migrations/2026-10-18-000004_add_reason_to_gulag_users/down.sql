@@ -0,0 +1 @@
+ALTER TABLE gulag_users DROP COLUMN reason;
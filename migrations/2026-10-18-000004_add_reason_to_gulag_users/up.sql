@@ -0,0 +1,2 @@
+-- Why the user was sent to the gulag. NULL for rows created before this column.
+ALTER TABLE gulag_users ADD COLUMN reason TEXT;
@@ -0,0 +1,32 @@
+CREATE TABLE "ai_slop_usage" (
+  "id" SERIAL PRIMARY KEY,
+  "user_id" bigint NOT NULL,
+  "guild_id" bigint NOT NULL,
+  "usage_count" int NOT NULL DEFAULT 0,
+  "last_slop_at" timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  "created_at" timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  UNIQUE(user_id, guild_id)
+);
+
+CREATE TABLE goku_poll_usage (
+    id SERIAL PRIMARY KEY,
+    user_id BIGINT NOT NULL,
+    guild_id BIGINT NOT NULL,
+    usage_count INTEGER NOT NULL DEFAULT 0,
+    last_goku_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    UNIQUE(user_id, guild_id)
+);
+
+INSERT INTO ai_slop_usage (user_id, guild_id, usage_count, last_slop_at, created_at)
+SELECT user_id, guild_id, count, last_offense_at, created_at
+FROM offenses
+WHERE offense_type = 'ai_slop';
+
+INSERT INTO goku_poll_usage (user_id, guild_id, usage_count, last_goku_at, created_at)
+SELECT user_id, guild_id, count, last_offense_at, created_at
+FROM offenses
+WHERE offense_type = 'goku_poll';
+
+DROP TABLE offenses;
+DROP TABLE offense_policies;
@@ -0,0 +1,47 @@
+-- Escalation policy per offense type. guild_id 0 holds the global default;
+-- a row for a specific guild overrides it.
+-- Duration of the Nth offense = min(cap_seconds, base_seconds * multiplier^(N-1)).
+-- If decay_window_seconds is set and the last offense is older than the
+-- window, the count starts over. NULL never decays.
+CREATE TABLE offense_policies (
+    guild_id BIGINT NOT NULL DEFAULT 0,
+    offense_type VARCHAR NOT NULL,
+    base_seconds INTEGER NOT NULL CHECK (base_seconds > 0),
+    multiplier DOUBLE PRECISION NOT NULL DEFAULT 2 CHECK (multiplier >= 1),
+    cap_seconds INTEGER NOT NULL CHECK (cap_seconds > 0),
+    decay_window_seconds INTEGER CHECK (decay_window_seconds > 0),
+    PRIMARY KEY (guild_id, offense_type)
+);
+
+INSERT INTO offense_policies (guild_id, offense_type, base_seconds, multiplier, cap_seconds, decay_window_seconds) VALUES
+    (0, 'slash_command', 1800, 2, 604800, NULL),
+    (0, 'reaction_vote', 300, 2, 86400, 2592000),
+    (0, 'ai_slop', 1800, 2, 2592000, NULL),
+    (0, 'goku_poll', 1800, 2, 2592000, NULL),
+    (0, 'elon', 300, 2, 86400, 2592000),
+    (0, 'slow_user', 300, 1, 300, NULL)
+ON CONFLICT (guild_id, offense_type) DO NOTHING;
+
+-- One row per user, guild and offense type.
+CREATE TABLE offenses (
+    user_id BIGINT NOT NULL,
+    guild_id BIGINT NOT NULL,
+    offense_type VARCHAR NOT NULL,
+    count INTEGER NOT NULL DEFAULT 0,
+    last_offense_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, guild_id, offense_type)
+);
+
+INSERT INTO offenses (user_id, guild_id, offense_type, count, last_offense_at, created_at)
+SELECT user_id, guild_id, 'ai_slop', usage_count, last_slop_at, created_at
+FROM ai_slop_usage
+WHERE usage_count > 0;
+
+INSERT INTO offenses (user_id, guild_id, offense_type, count, last_offense_at, created_at)
+SELECT user_id, guild_id, 'goku_poll', usage_count, last_goku_at, created_at
+FROM goku_poll_usage
+WHERE usage_count > 0;
+
+DROP TABLE ai_slop_usage;
+DROP TABLE goku_poll_usage;
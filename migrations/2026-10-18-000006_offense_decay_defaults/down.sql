@@ -0,0 +1,4 @@
+UPDATE offense_policies
+SET decay_window_seconds = NULL
+WHERE guild_id = 0
+  AND offense_type IN ('slash_command', 'ai_slop', 'goku_poll');
@@ -0,0 +1,7 @@
+-- Counts now halve per decay window instead of resetting, so give the
+-- escalating defaults a window: one halving per 30 days without an offense.
+UPDATE offense_policies
+SET decay_window_seconds = 2592000
+WHERE guild_id = 0
+  AND decay_window_seconds IS NULL
+  AND offense_type IN ('slash_command', 'ai_slop', 'goku_poll');
@@ -0,0 +1 @@
+DROP TABLE gulag_appeals;
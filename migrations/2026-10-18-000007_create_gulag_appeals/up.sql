@@ -0,0 +1,17 @@
+-- Appeals filed with /gulag-appeal and how moderators resolved them.
+-- status: 'pending', 'approved', 'denied', or 'expired' (the user was
+--         released some other way before a moderator answered)
+CREATE TABLE gulag_appeals (
+    id BIGSERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    message TEXT NOT NULL,
+    status VARCHAR NOT NULL DEFAULT 'pending',
+    moderator_id BIGINT,
+    channel_id BIGINT,
+    review_message_id BIGINT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    resolved_at TIMESTAMP
+);
+
+CREATE INDEX idx_gulag_appeals_guild_user ON gulag_appeals (guild_id, user_id, created_at);
@@ -0,0 +1 @@
+DELETE FROM features WHERE name IN ('activity_reactions', 'activity_voice');
@@ -0,0 +1,7 @@
+-- Optional extra activity sources for /cull (disabled by default).
+-- Messages always count; when enabled, reactions and voice channel joins
+-- also update user_activity.last_message_at.
+INSERT INTO features (name, enabled, guild_id) VALUES
+  ('activity_reactions', false, 0),
+  ('activity_voice', false, 0)
+ON CONFLICT (name, guild_id) DO NOTHING;
@@ -0,0 +1 @@
+DROP TABLE cull_scan_checkpoints;
@@ -0,0 +1,16 @@
+-- Per-channel progress of `/cull scan`, so an interrupted scan resumes from
+-- where it stopped instead of starting over.
+-- oldest_message_id: the oldest message reached so far; the next page is
+--                    fetched from before it. NULL until the first page.
+-- completed:         the channel's history (back to the scan cutoff) has
+--                    been fully read
+CREATE TABLE cull_scan_checkpoints (
+    guild_id BIGINT NOT NULL,
+    channel_id BIGINT NOT NULL,
+    channel_name TEXT NOT NULL,
+    oldest_message_id BIGINT,
+    messages_scanned BIGINT NOT NULL DEFAULT 0,
+    completed BOOLEAN NOT NULL DEFAULT FALSE,
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (guild_id, channel_id)
+);
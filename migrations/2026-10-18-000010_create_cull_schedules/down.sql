@@ -0,0 +1,2 @@
+DROP TABLE cull_warnings;
+DROP TABLE cull_schedules;
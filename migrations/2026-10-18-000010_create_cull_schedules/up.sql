@@ -0,0 +1,26 @@
+-- Automatic culls, configured with /config cull-schedule.
+-- Every interval_days, members inactive for inactive_days are warned and
+-- deadline_at is set warning_days ahead. At the deadline, warned members who
+-- are still inactive are kicked, and next_run_at moves on by interval_days.
+-- warning_channel_id: mention candidates there instead of DMing them
+-- deadline_at:        set while a warning round is open
+CREATE TABLE cull_schedules (
+    guild_id BIGINT PRIMARY KEY,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    inactive_days INTEGER NOT NULL,
+    interval_days INTEGER NOT NULL,
+    warning_days INTEGER NOT NULL,
+    include_never_posted BOOLEAN NOT NULL DEFAULT FALSE,
+    warning_channel_id BIGINT,
+    next_run_at TIMESTAMP NOT NULL,
+    deadline_at TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+-- Members warned in a guild's open round.
+CREATE TABLE cull_warnings (
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    warned_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (guild_id, user_id)
+);
@@ -0,0 +1 @@
+DROP TABLE cull_exemptions;
@@ -0,0 +1,11 @@
+-- Members /cull must never target, managed with /cull exempt.
+-- expires_at: NULL for a permanent exemption
+CREATE TABLE cull_exemptions (
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    reason TEXT,
+    expires_at TIMESTAMP,
+    added_by BIGINT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (guild_id, user_id)
+);
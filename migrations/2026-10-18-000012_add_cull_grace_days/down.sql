@@ -0,0 +1 @@
+ALTER TABLE guild_settings DROP COLUMN cull_grace_days;
@@ -0,0 +1,3 @@
+-- Members who joined less than this many days ago are never culled.
+-- NULL falls back to the default in src/tugbot/guild_config.rs.
+ALTER TABLE guild_settings ADD COLUMN cull_grace_days INTEGER;
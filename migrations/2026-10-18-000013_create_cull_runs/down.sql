@@ -0,0 +1,2 @@
+DROP TABLE cull_run_members;
+DROP TABLE cull_runs;
@@ -0,0 +1,32 @@
+-- Culls that reached the kick loop, recorded for /cull history.
+-- source: 'manual' (/cull run) or 'scheduled'
+-- invoked_by: the confirming moderator; NULL for scheduled culls
+-- finished_at: NULL while the kick loop is running (or if it never finished)
+CREATE TABLE cull_runs (
+    id BIGSERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    source VARCHAR NOT NULL,
+    invoked_by BIGINT,
+    inactive_days INTEGER NOT NULL,
+    include_never_posted BOOLEAN NOT NULL,
+    started_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMP
+);
+
+CREATE INDEX idx_cull_runs_guild ON cull_runs (guild_id, started_at);
+
+-- Every candidate of a cull run and what happened to them.
+-- outcome: 'pending' (not reached yet), 'kicked', 'failed', 'skipped'
+--          (no longer a member when the kick loop reached them), or
+--          'cancelled' (never reached because the run was cancelled)
+-- reinvited_at: when /cull reinvite last created an invite for them
+CREATE TABLE cull_run_members (
+    run_id BIGINT NOT NULL REFERENCES cull_runs (id) ON DELETE CASCADE,
+    user_id BIGINT NOT NULL,
+    outcome VARCHAR NOT NULL DEFAULT 'pending',
+    error TEXT,
+    reinvited_at TIMESTAMP,
+    PRIMARY KEY (run_id, user_id)
+);
+
+CREATE INDEX idx_cull_run_members_user ON cull_run_members (user_id, outcome);
@@ -0,0 +1,2 @@
+DROP TABLE message_hour_counts;
+DROP TABLE message_counts;
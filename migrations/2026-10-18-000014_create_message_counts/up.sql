@@ -0,0 +1,22 @@
+-- Daily message rollups, fed by live message tracking (not by /cull scan).
+-- day: days since the Unix epoch, UTC
+CREATE TABLE message_counts (
+    guild_id BIGINT NOT NULL,
+    channel_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    day INTEGER NOT NULL,
+    message_count INTEGER NOT NULL,
+    PRIMARY KEY (guild_id, day, channel_id, user_id)
+);
+
+CREATE INDEX idx_message_counts_guild_user ON message_counts (guild_id, user_id, day);
+
+-- Per-guild messages by hour of day, for the /stats heatmap.
+-- hour: 0-23, UTC
+CREATE TABLE message_hour_counts (
+    guild_id BIGINT NOT NULL,
+    day INTEGER NOT NULL,
+    hour SMALLINT NOT NULL,
+    message_count INTEGER NOT NULL,
+    PRIMARY KEY (guild_id, day, hour)
+);
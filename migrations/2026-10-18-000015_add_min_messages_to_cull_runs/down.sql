@@ -0,0 +1 @@
+ALTER TABLE cull_runs DROP COLUMN min_messages;
@@ -0,0 +1,2 @@
+-- /cull run's min-messages threshold, NULL when it wasn't used
+ALTER TABLE cull_runs ADD COLUMN min_messages INTEGER;
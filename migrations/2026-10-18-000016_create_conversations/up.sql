@@ -0,0 +1,35 @@
+-- Conversations with tugbot in the ask channel, so follow-ups keep context.
+-- thread_id: set when the conversation lives in a thread, which holds one
+--            conversation; NULL for a reply chain in the ask channel itself
+-- started_by: the member whose mention opened the conversation
+CREATE TABLE conversations (
+    id BIGSERIAL PRIMARY KEY,
+    guild_id BIGINT NOT NULL,
+    channel_id BIGINT NOT NULL,
+    thread_id BIGINT,
+    started_by BIGINT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_conversations_thread ON conversations (thread_id);
+CREATE INDEX idx_conversations_channel ON conversations (channel_id, started_by);
+CREATE INDEX idx_conversations_updated ON conversations (updated_at);
+
+-- The turns of a conversation. message_id is the Discord message: the
+-- member's mention for 'user' turns, tugbot's reply for 'assistant' turns.
+-- asked_by: the member whose question the turn asks or answers
+CREATE TABLE conversation_messages (
+    id BIGSERIAL PRIMARY KEY,
+    conversation_id BIGINT NOT NULL REFERENCES conversations (id) ON DELETE CASCADE,
+    message_id BIGINT NOT NULL,
+    asked_by BIGINT NOT NULL,
+    role VARCHAR NOT NULL,
+    author_name VARCHAR NOT NULL,
+    content TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_conversation_messages_conversation ON conversation_messages (conversation_id, id);
+CREATE INDEX idx_conversation_messages_message ON conversation_messages (message_id);
+CREATE INDEX idx_conversation_messages_asked_by ON conversation_messages (asked_by);
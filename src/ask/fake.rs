@@ -0,0 +1,127 @@
+//! A scripted [`AskBackend`] for tests: it hands out canned replies in
+//! order and records the prompts it was asked.
+
+use super::{AskBackend, AskBusy, AskEvent, AskStream};
+use anyhow::Result;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+/// One scripted reply of a [`FakeBackend`].
+#[derive(Debug, Clone)]
+pub enum FakeReply {
+    /// Stream `events`, then answer with `text`.
+    Answer { events: Vec<AskEvent>, text: String },
+    /// Fail the request with this message once it has been accepted.
+    Error(String),
+    /// Refuse the request with [`AskBusy`].
+    Busy,
+}
+
+impl FakeReply {
+    /// An answer streamed as a single text delta.
+    pub fn text(text: &str) -> FakeReply {
+        FakeReply::Answer {
+            events: vec![AskEvent::TextDelta(text.to_string())],
+            text: text.to_string(),
+        }
+    }
+}
+
+pub struct FakeBackend {
+    replies: Mutex<VecDeque<FakeReply>>,
+    prompts: Mutex<Vec<String>>,
+}
+
+impl FakeBackend {
+    pub fn new(replies: impl IntoIterator<Item = FakeReply>) -> FakeBackend {
+        FakeBackend {
+            replies: Mutex::new(replies.into_iter().collect()),
+            prompts: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Every prompt asked so far, in order.
+    pub fn prompts(&self) -> Vec<String> {
+        self.prompts
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+impl AskBackend for FakeBackend {
+    fn ask_streaming(&self, prompt: &str, _images: &[(String, String)]) -> Result<AskStream> {
+        self.prompts
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(prompt.to_string());
+        let reply = self
+            .replies
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .pop_front()
+            .ok_or_else(|| anyhow::anyhow!("FakeBackend has no scripted reply left"))?;
+
+        let (events_tx, events_rx) = mpsc::unbounded_channel();
+        let (response_tx, response_rx) = oneshot::channel();
+        let result = match reply {
+            FakeReply::Answer { events, text } => {
+                for event in events {
+                    let _ = events_tx.send(event);
+                }
+                Ok(text)
+            }
+            FakeReply::Error(message) => Err(anyhow::anyhow!(message)),
+            FakeReply::Busy => return Err(AskBusy.into()),
+        };
+        let _ = response_tx.send(result);
+
+        Ok(AskStream::new(
+            events_rx,
+            response_rx,
+            Duration::from_secs(5),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn fake_backend_replays_script() {
+        let backend = FakeBackend::new([
+            FakeReply::Answer {
+                events: vec![
+                    AskEvent::ToolStart {
+                        name: "web_search".to_string(),
+                        args: serde_json::Value::Null,
+                    },
+                    AskEvent::TextDelta("Yes".to_string()),
+                ],
+                text: "Yes".to_string(),
+            },
+            FakeReply::Busy,
+            FakeReply::Error("boom".to_string()),
+        ]);
+
+        let mut stream = backend.ask_streaming("first", &[]).unwrap();
+        let mut events = Vec::new();
+        while let Some(event) = stream.next_event().await {
+            events.push(event);
+        }
+        assert_eq!(events.len(), 2);
+        assert_eq!(stream.finish().await.unwrap(), "Yes");
+
+        let busy = backend.ask("second").await.unwrap_err();
+        assert!(busy.downcast_ref::<AskBusy>().is_some());
+        assert_eq!(backend.ask("third").await.unwrap_err().to_string(), "boom");
+        assert!(backend.ask("fourth").await.is_err());
+        assert_eq!(
+            backend.prompts(),
+            vec!["first", "second", "third", "fourth"]
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! The LLM behind tugbot's mention replies. [`AskBackend`] is implemented by
+//! [`PiRpc`] (the `pi` CLI in RPC mode), by [`OpenAiBackend`] for any
+//! OpenAI-compatible server such as llama.cpp or ollama, and, in tests only,
+//! by a scripted `FakeBackend`. `TUGBOT_ASK_BACKEND` picks one at startup.
+
+#[cfg(test)]
+pub mod fake;
+pub mod openai;
+
+#[cfg(test)]
+pub use fake::{FakeBackend, FakeReply};
+pub use openai::OpenAiBackend;
+
+use crate::pi_rpc::PiRpc;
+use anyhow::Result;
+use serde_json::Value;
+use serenity::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+/// Progress a backend reports while it works on an answer.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AskEvent {
+    /// A chunk of assistant text. Text written before a tool call belongs
+    /// to an earlier assistant message than the final answer.
+    TextDelta(String),
+    /// The backend started running a tool, e.g. `web_search`.
+    ToolStart { name: String, args: Value },
+    /// A tool finished running.
+    ToolEnd { name: String },
+}
+
+/// An answer in progress, returned by [`AskBackend::ask_streaming`].
+pub struct AskStream {
+    events: mpsc::UnboundedReceiver<AskEvent>,
+    response: oneshot::Receiver<Result<String>>,
+    deadline: tokio::time::Instant,
+    timeout: Duration,
+}
+
+impl AskStream {
+    /// A stream of the `events` a backend sends until it answers on
+    /// `response`, giving up after `timeout`. The backend ends the event
+    /// stream by dropping its sender.
+    pub fn new(
+        events: mpsc::UnboundedReceiver<AskEvent>,
+        response: oneshot::Receiver<Result<String>>,
+        timeout: Duration,
+    ) -> AskStream {
+        AskStream {
+            events,
+            response,
+            deadline: tokio::time::Instant::now() + timeout,
+            timeout,
+        }
+    }
+
+    /// The next progress event, or `None` once the backend has finished or
+    /// the request has timed out. Call [`AskStream::finish`] for the answer.
+    pub async fn next_event(&mut self) -> Option<AskEvent> {
+        tokio::time::timeout_at(self.deadline, self.events.recv())
+            .await
+            .ok()
+            .flatten()
+    }
+
+    /// Wait for the text of the last assistant message.
+    pub async fn finish(self) -> Result<String> {
+        tokio::time::timeout_at(self.deadline, self.response)
+            .await
+            .map_err(|_| anyhow::anyhow!("ask timed out after {:?}", self.timeout))?
+            .map_err(|_| anyhow::anyhow!("ask backend dropped the response"))?
+    }
+}
+
+/// Returned (inside the `anyhow::Error`) when a backend has no room for
+/// another request.
+#[derive(Debug)]
+pub struct AskBusy;
+
+impl std::fmt::Display for AskBusy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the ask backend is busy, try again later")
+    }
+}
+
+impl std::error::Error for AskBusy {}
+
+#[async_trait]
+pub trait AskBackend: Send + Sync {
+    /// Start answering a prompt with optional base64-encoded images, each a
+    /// `(mime_type, base64_data)` tuple. Fails straight away with
+    /// [`AskBusy`] if the backend can't take the request.
+    fn ask_streaming(&self, prompt: &str, images: &[(String, String)]) -> Result<AskStream>;
+
+    /// Send a prompt with optional images and wait for the whole answer.
+    async fn ask_with_images(&self, prompt: &str, images: &[(String, String)]) -> Result<String> {
+        self.ask_streaming(prompt, images)?.finish().await
+    }
+
+    /// Send a prompt and wait for the whole answer.
+    async fn ask(&self, prompt: &str) -> Result<String> {
+        self.ask_with_images(prompt, &[]).await
+    }
+}
+
+/// Start the backend `TUGBOT_ASK_BACKEND` names: `pi` (the default) or
+/// `openai`.
+pub async fn spawn_backend() -> Result<Arc<dyn AskBackend>> {
+    let backend = std::env::var("TUGBOT_ASK_BACKEND").unwrap_or_default();
+    match backend.trim() {
+        "" | "pi" => Ok(PiRpc::spawn().await?),
+        "openai" => Ok(Arc::new(OpenAiBackend::from_env()?)),
+        other => Err(anyhow::anyhow!(
+            "unknown TUGBOT_ASK_BACKEND `{}` (expected `pi` or `openai`)",
+            other
+        )),
+    }
+}
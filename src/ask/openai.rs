@@ -0,0 +1,306 @@
+//! [`AskBackend`] for OpenAI-compatible chat completion servers, such as
+//! llama.cpp's `llama-server` or ollama. Answers stream back as server-sent
+//! events. There are no tools, so there is no web search either.
+
+use super::{AskBackend, AskBusy, AskEvent, AskStream};
+use crate::pi_rpc::{system_prompt_path, PI_RPC_SECURITY_FALLBACK};
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, Semaphore};
+
+const TIMEOUT_SECS: u64 = 300;
+/// ollama's OpenAI-compatible endpoint.
+const DEFAULT_BASE_URL: &str = "http://localhost:11434/v1";
+/// Requests in flight before further ones are refused as busy.
+const MAX_CONCURRENT_REQUESTS: usize = 4;
+
+pub struct OpenAiBackend {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+    api_key: Option<String>,
+    system_prompt: String,
+    permits: Arc<Semaphore>,
+}
+
+impl OpenAiBackend {
+    pub fn new(base_url: &str, model: &str, api_key: Option<String>) -> OpenAiBackend {
+        OpenAiBackend {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(TIMEOUT_SECS))
+                .build()
+                .unwrap_or_else(|_| reqwest::Client::new()),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+            api_key,
+            system_prompt: system_prompt(),
+            permits: Arc::new(Semaphore::new(MAX_CONCURRENT_REQUESTS)),
+        }
+    }
+
+    /// Configure from `TUGBOT_OPENAI_MODEL` (required),
+    /// `TUGBOT_OPENAI_BASE_URL` (default: a local ollama) and
+    /// `TUGBOT_OPENAI_API_KEY` (optional).
+    pub fn from_env() -> Result<OpenAiBackend> {
+        let model = std::env::var("TUGBOT_OPENAI_MODEL")
+            .context("TUGBOT_OPENAI_MODEL must be set for the openai ask backend")?;
+        let base_url = std::env::var("TUGBOT_OPENAI_BASE_URL")
+            .unwrap_or_else(|_| DEFAULT_BASE_URL.to_string());
+        let api_key = std::env::var("TUGBOT_OPENAI_API_KEY")
+            .ok()
+            .filter(|key| !key.is_empty());
+        Ok(OpenAiBackend::new(&base_url, &model, api_key))
+    }
+
+    fn request_body(&self, prompt: &str, images: &[(String, String)]) -> Value {
+        let content = if images.is_empty() {
+            Value::from(prompt)
+        } else {
+            let mut parts = vec![serde_json::json!({ "type": "text", "text": prompt })];
+            parts.extend(images.iter().map(|(mime, b64)| {
+                serde_json::json!({
+                    "type": "image_url",
+                    "image_url": { "url": format!("data:{};base64,{}", mime, b64) }
+                })
+            }));
+            Value::from(parts)
+        };
+        serde_json::json!({
+            "model": self.model,
+            "stream": true,
+            "messages": [
+                { "role": "system", "content": self.system_prompt },
+                { "role": "user", "content": content }
+            ]
+        })
+    }
+}
+
+impl AskBackend for OpenAiBackend {
+    fn ask_streaming(&self, prompt: &str, images: &[(String, String)]) -> Result<AskStream> {
+        let permit = self
+            .permits
+            .clone()
+            .try_acquire_owned()
+            .map_err(|_| AskBusy)?;
+
+        let mut request = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .json(&self.request_body(prompt, images));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let (events_tx, events_rx) = mpsc::unbounded_channel();
+        let (response_tx, response_rx) = oneshot::channel();
+        tokio::spawn(async move {
+            let result = stream_completion(request, &events_tx).await;
+            drop(permit);
+            // Best-effort: receiver may have been dropped (caller cancelled/timed out)
+            let _ = response_tx.send(result);
+        });
+
+        Ok(AskStream::new(
+            events_rx,
+            response_rx,
+            Duration::from_secs(TIMEOUT_SECS),
+        ))
+    }
+}
+
+/// tugbot's system prompt file with the anti-injection guardrail appended,
+/// which pi would otherwise get through `--append-system-prompt`.
+fn system_prompt() -> String {
+    let path = system_prompt_path();
+    let mut prompt = std::fs::read_to_string(&path).unwrap_or_else(|e| {
+        eprintln!("[ask] Failed to read system prompt {}: {}", path, e);
+        String::new()
+    });
+    if !prompt.is_empty() {
+        prompt.push_str("\n\n");
+    }
+    prompt.push_str(PI_RPC_SECURITY_FALLBACK);
+    prompt
+}
+
+/// Send the request and relay the streamed answer as text deltas. Returns
+/// the whole answer.
+async fn stream_completion(
+    request: reqwest::RequestBuilder,
+    events: &mpsc::UnboundedSender<AskEvent>,
+) -> Result<String> {
+    let mut response = request
+        .send()
+        .await
+        .context("Failed to reach the OpenAI-compatible server")?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow::anyhow!(
+            "OpenAI-compatible server returned {}: {}",
+            status,
+            body
+        ));
+    }
+
+    let mut text = String::new();
+    // Bytes of an incomplete line, which may end mid UTF-8 character
+    let mut buffer: Vec<u8> = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .context("Failed to read the streamed answer")?
+    {
+        buffer.extend_from_slice(&chunk);
+        while let Some(end) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            match parse_sse_line(String::from_utf8_lossy(&line).trim())? {
+                SseLine::Delta(delta) => {
+                    text.push_str(&delta);
+                    let _ = events.send(AskEvent::TextDelta(delta));
+                }
+                SseLine::Done => return Ok(text),
+                SseLine::Other => {}
+            }
+        }
+    }
+    // Some servers just close the stream instead of sending [DONE]
+    Ok(text)
+}
+
+#[derive(Debug, PartialEq)]
+enum SseLine {
+    Delta(String),
+    Done,
+    Other,
+}
+
+/// Interpret one line of a streamed chat completion.
+fn parse_sse_line(line: &str) -> Result<SseLine> {
+    let Some(data) = line.strip_prefix("data:") else {
+        return Ok(SseLine::Other);
+    };
+    let data = data.trim();
+    if data == "[DONE]" {
+        return Ok(SseLine::Done);
+    }
+    let json: Value = match serde_json::from_str(data) {
+        Ok(json) => json,
+        Err(e) => {
+            eprintln!("[ask] Failed to parse streamed chunk: {} — {}", e, data);
+            return Ok(SseLine::Other);
+        }
+    };
+    if let Some(error) = json.get("error") {
+        return Err(anyhow::anyhow!("OpenAI-compatible server error: {}", error));
+    }
+    match json
+        .pointer("/choices/0/delta/content")
+        .and_then(|v| v.as_str())
+    {
+        Some(delta) if !delta.is_empty() => Ok(SseLine::Delta(delta.to_string())),
+        _ => Ok(SseLine::Other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    #[test]
+    fn parse_sse_line_reads_deltas() {
+        assert_eq!(
+            parse_sse_line(r#"data: {"choices":[{"delta":{"content":"Hi"}}]}"#).unwrap(),
+            SseLine::Delta("Hi".to_string())
+        );
+        assert_eq!(
+            parse_sse_line(r#"data: {"choices":[{"delta":{"role":"assistant"}}]}"#).unwrap(),
+            SseLine::Other
+        );
+        assert_eq!(parse_sse_line("data: [DONE]").unwrap(), SseLine::Done);
+        assert_eq!(parse_sse_line(": keep-alive").unwrap(), SseLine::Other);
+        assert!(parse_sse_line(r#"data: {"error":{"message":"no model"}}"#).is_err());
+    }
+
+    #[test]
+    fn request_body_inlines_images() {
+        let backend = OpenAiBackend::new("http://localhost:8080/v1/", "llama", None);
+        assert_eq!(backend.base_url, "http://localhost:8080/v1");
+
+        let body = backend.request_body("what's this?", &[("image/png".into(), "AAAA".into())]);
+        assert_eq!(body["model"], "llama");
+        assert_eq!(body["messages"][0]["role"], "system");
+        let content = &body["messages"][1]["content"];
+        assert_eq!(content[0]["text"], "what's this?");
+        assert_eq!(content[1]["image_url"]["url"], "data:image/png;base64,AAAA");
+
+        let body = backend.request_body("hello", &[]);
+        assert_eq!(body["messages"][1]["content"], "hello");
+    }
+
+    #[tokio::test]
+    async fn ask_streams_from_a_server() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut conn, _) = listener.accept().unwrap();
+            // Read the whole request before answering
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = conn.read(&mut buf).unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some(header_end) = text.find("\r\n\r\n") {
+                    let length: usize = text
+                        .lines()
+                        .find_map(|l| {
+                            l.to_ascii_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|v| v.trim().parse().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= header_end + 4 + length {
+                        break;
+                    }
+                }
+            }
+            let body = "data: {\"choices\":[{\"delta\":{\"content\":\"Hello\"}}]}\n\n\
+                        data: {\"choices\":[{\"delta\":{\"content\":\" there\"}}]}\n\n\
+                        data: [DONE]\n\n";
+            write!(
+                conn,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+            String::from_utf8_lossy(&request).to_string()
+        });
+
+        let backend = OpenAiBackend::new(&base_url, "llama", Some("secret".to_string()));
+        let mut stream = backend.ask_streaming("hi", &[]).unwrap();
+        let mut deltas = Vec::new();
+        while let Some(event) = stream.next_event().await {
+            deltas.push(event);
+        }
+        assert_eq!(stream.finish().await.unwrap(), "Hello there");
+        assert_eq!(
+            deltas,
+            vec![
+                AskEvent::TextDelta("Hello".to_string()),
+                AskEvent::TextDelta(" there".to_string()),
+            ]
+        );
+
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /v1/chat/completions "));
+        assert!(request
+            .to_ascii_lowercase()
+            .contains("authorization: bearer secret"));
+    }
+}
@@ -10,16 +10,31 @@ diesel::define_sql_function! {
 
 use self::{
     models::{
-        AiSlopUsage, GokuPollUsage, GulagUser, GulagVote, IsThisRealUsage, NewAiSlopUsage,
-        NewGokuPollUsage, NewGulagUser, NewGulagVote, NewIsThisRealUsage, NewServer,
-        NewUserActivity, Server, UserActivity,
+        Conversation, ConversationMessage, CullExemption, CullRun, CullRunMember,
+        CullScanCheckpoint, CullSchedule, CullWarning, GuildSettings, GulagAppeal, GulagUser,
+        GulagVote, IsThisRealUsage, MessageCount, MessageHourCount, ModerationEvent,
+        NewConversation, NewConversationMessage, NewCullRun, NewGulagAppeal, NewGulagUser,
+        NewGulagVote, NewIsThisRealUsage, NewModerationEvent, NewPermissionGrant, NewServer,
+        NewUserActivity, PermissionGrant, Server, UserActivity,
     },
     schema::{
-        ai_slop_usage::{self},
-        goku_poll_usage::{self},
+        conversation_messages::{self},
+        conversations::{self},
+        cull_exemptions::{self},
+        cull_run_members::{self},
+        cull_runs::{self},
+        cull_scan_checkpoints::{self},
+        cull_schedules::{self},
+        cull_warnings::{self},
+        guild_settings::{self},
+        gulag_appeals::{self},
         gulag_users::{self},
         gulag_votes::{self},
         is_this_real_usage::{self},
+        message_counts::{self},
+        message_hour_counts::{self},
+        moderation_events::{self},
+        permission_grants::{self},
         servers,
     },
 };
@@ -35,6 +50,9 @@ use std::{
 
 pub type DbPool = Pool<ConnectionManager<PgConnection>>;
 
+/// Rows per multi-row insert, well under Postgres' 65535 bind parameter limit.
+pub const INSERT_CHUNK: usize = 5000;
+
 /// Helper to convert pool errors to Diesel errors
 fn pool_error_to_diesel(e: diesel::r2d2::PoolError) -> diesel::result::Error {
     diesel::result::Error::QueryBuilderError(Box::new(e))
@@ -79,6 +97,7 @@ pub fn create_server(
         .get_result(&mut conn)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn send_to_gulag(
     pool: &DbPool,
     user_id: i64,
@@ -87,6 +106,7 @@ pub fn send_to_gulag(
     gulag_length: i32,
     channel_id: i64,
     message_id: i64,
+    reason: Option<&str>,
 ) -> Result<GulagUser, diesel::result::Error> {
     // Validate gulag_length is non-negative
     if gulag_length < 0 {
@@ -112,6 +132,7 @@ pub fn send_to_gulag(
         release_at: release_time,
         remod: false,
         message_id,
+        reason: reason.map(str::to_string),
     };
 
     diesel::insert_into(gulag_users::table)
@@ -119,12 +140,15 @@ pub fn send_to_gulag(
         .get_result(&mut conn)
 }
 
+/// Extend an existing sentence. A new `reason` replaces the stored one;
+/// `None` keeps it.
 pub fn add_time_to_gulag(
     pool: &DbPool,
     gulag_user_id: i32,
     gulag_length: i32,
     gulag_duration: i32,
     release_at: SystemTime,
+    reason: Option<&str>,
 ) -> Result<GulagUser, diesel::result::Error> {
     // Validate gulag_duration is non-negative
     if gulag_duration < 0 {
@@ -136,12 +160,22 @@ pub fn add_time_to_gulag(
     let mut conn = pool.get().map_err(pool_error_to_diesel)?;
     let gulag_duration = Duration::from_secs(gulag_duration as u64);
     let new_release_time = release_at.add(gulag_duration);
-    diesel::update(gulag_users::dsl::gulag_users.find(gulag_user_id))
-        .set((
-            gulag_users::gulag_length.eq(gulag_length),
-            gulag_users::release_at.eq(new_release_time),
-        ))
-        .get_result(&mut conn)
+    let target = gulag_users::dsl::gulag_users.find(gulag_user_id);
+    match reason {
+        Some(new_reason) => diesel::update(target)
+            .set((
+                gulag_users::gulag_length.eq(gulag_length),
+                gulag_users::release_at.eq(new_release_time),
+                gulag_users::reason.eq(new_reason),
+            ))
+            .get_result(&mut conn),
+        None => diesel::update(target)
+            .set((
+                gulag_users::gulag_length.eq(gulag_length),
+                gulag_users::release_at.eq(new_release_time),
+            ))
+            .get_result(&mut conn),
+    }
 }
 
 pub fn new_gulag_vote(
@@ -169,151 +203,282 @@ pub fn new_gulag_vote(
         .get_result(&mut conn)
 }
 
-pub fn get_or_create_ai_slop_usage(
+pub fn get_server_by_guild_id(pool: &DbPool, target_guild_id: i64) -> Option<Server> {
+    let mut conn = pool.get().ok()?;
+    use self::servers::dsl::*;
+
+    servers
+        .filter(guild_id.eq(target_guild_id))
+        .first::<Server>(&mut conn)
+        .ok()
+}
+
+pub fn get_guild_settings(
     pool: &DbPool,
-    target_user_id: i64,
     target_guild_id: i64,
-) -> Result<AiSlopUsage, diesel::result::Error> {
+) -> Result<Option<GuildSettings>, diesel::result::Error> {
     let mut conn = pool.get().map_err(pool_error_to_diesel)?;
-    use self::ai_slop_usage::dsl::*;
 
-    // Try to get existing record
-    match ai_slop_usage
-        .filter(user_id.eq(target_user_id))
-        .filter(guild_id.eq(target_guild_id))
-        .first::<AiSlopUsage>(&mut conn)
-    {
-        Ok(usage) => Ok(usage),
-        Err(diesel::result::Error::NotFound) => {
-            // Create new record with count 0
-            let new_usage = NewAiSlopUsage {
-                user_id: target_user_id,
-                guild_id: target_guild_id,
-                usage_count: 0,
-                last_slop_at: SystemTime::now(),
-                created_at: SystemTime::now(),
-            };
+    guild_settings::table
+        .find(target_guild_id)
+        .select(GuildSettings::as_select())
+        .first(&mut conn)
+        .optional()
+}
 
-            diesel::insert_into(ai_slop_usage)
-                .values(&new_usage)
-                .get_result(&mut conn)
-        }
-        Err(e) => Err(e),
-    }
+/// Insert or fully replace the settings row for a guild.
+pub fn upsert_guild_settings(
+    pool: &DbPool,
+    settings: &GuildSettings,
+) -> Result<GuildSettings, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    diesel::insert_into(guild_settings::table)
+        .values(settings)
+        .on_conflict(guild_settings::guild_id)
+        .do_update()
+        .set(settings)
+        .get_result(&mut conn)
 }
 
-pub fn increment_ai_slop_usage(
+/// Append an entry to the moderation audit log.
+pub fn insert_moderation_event(
     pool: &DbPool,
-    usage_id: i32,
-    new_count: i32,
-) -> Result<AiSlopUsage, diesel::result::Error> {
+    event: &NewModerationEvent,
+) -> Result<ModerationEvent, diesel::result::Error> {
     let mut conn = pool.get().map_err(pool_error_to_diesel)?;
-    use self::ai_slop_usage::dsl::*;
 
-    diesel::update(ai_slop_usage.find(usage_id))
-        .set((
-            usage_count.eq(new_count),
-            last_slop_at.eq(SystemTime::now()),
-        ))
+    diesel::insert_into(moderation_events::table)
+        .values(event)
         .get_result(&mut conn)
 }
 
-pub fn atomic_increment_ai_slop(
+/// A member's moderation history in a guild, newest first.
+pub fn get_moderation_events(
     pool: &DbPool,
-    target_user_id: i64,
     target_guild_id: i64,
-) -> Result<i32, diesel::result::Error> {
+    target_user_id: i64,
+    max: i64,
+) -> Result<Vec<ModerationEvent>, diesel::result::Error> {
     let mut conn = pool.get().map_err(pool_error_to_diesel)?;
-    use self::ai_slop_usage::dsl::*;
 
-    // Upsert: insert with count=1 or increment existing
-    // This is fully atomic and returns the NEW count
-    let time_now = SystemTime::now();
-    let new_record = NewAiSlopUsage {
-        user_id: target_user_id,
-        guild_id: target_guild_id,
-        usage_count: 1,
-        last_slop_at: time_now,
-        created_at: time_now,
-    };
+    moderation_events::table
+        .filter(moderation_events::guild_id.eq(target_guild_id))
+        .filter(moderation_events::user_id.eq(target_user_id))
+        .order((
+            moderation_events::created_at.desc(),
+            moderation_events::id.desc(),
+        ))
+        .limit(max)
+        .select(ModerationEvent::as_select())
+        .load(&mut conn)
+}
 
-    // Use Diesel's on_conflict API for proper upsert
-    let result: AiSlopUsage = diesel::insert_into(ai_slop_usage)
-        .values(&new_record)
-        .on_conflict((user_id, guild_id))
-        .do_update()
-        .set((usage_count.eq(usage_count + 1), last_slop_at.eq(time_now)))
-        .get_result(&mut conn)?;
+/// Count a member's moderation events by action in a guild.
+pub fn count_moderation_events(
+    pool: &DbPool,
+    target_guild_id: i64,
+    target_user_id: i64,
+    target_action: &str,
+) -> Result<i64, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
 
-    Ok(result.usage_count)
+    moderation_events::table
+        .filter(moderation_events::guild_id.eq(target_guild_id))
+        .filter(moderation_events::user_id.eq(target_user_id))
+        .filter(moderation_events::action.eq(target_action))
+        .count()
+        .get_result(&mut conn)
 }
 
-pub fn get_or_create_goku_poll_usage(
+/// The active gulag row for a member of a specific guild, if any.
+pub fn get_active_gulag_user(
     pool: &DbPool,
+    target_guild_id: i64,
     target_user_id: i64,
+) -> Result<Option<GulagUser>, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    gulag_users::table
+        .filter(gulag_users::guild_id.eq(target_guild_id))
+        .filter(gulag_users::user_id.eq(target_user_id))
+        .filter(gulag_users::in_gulag.eq(true))
+        .select(GulagUser::as_select())
+        .first(&mut conn)
+        .optional()
+}
+
+pub fn insert_gulag_appeal(
+    pool: &DbPool,
+    appeal: &NewGulagAppeal,
+) -> Result<GulagAppeal, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    diesel::insert_into(gulag_appeals::table)
+        .values(appeal)
+        .get_result(&mut conn)
+}
+
+pub fn get_gulag_appeal(
+    pool: &DbPool,
+    appeal_id: i64,
+) -> Result<Option<GulagAppeal>, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    gulag_appeals::table
+        .find(appeal_id)
+        .select(GulagAppeal::as_select())
+        .first(&mut conn)
+        .optional()
+}
+
+/// A member's most recent appeal in a guild.
+pub fn get_latest_gulag_appeal(
+    pool: &DbPool,
     target_guild_id: i64,
-) -> Result<GokuPollUsage, diesel::result::Error> {
+    target_user_id: i64,
+) -> Result<Option<GulagAppeal>, diesel::result::Error> {
     let mut conn = pool.get().map_err(pool_error_to_diesel)?;
-    use self::goku_poll_usage::dsl::*;
 
-    match goku_poll_usage
-        .filter(user_id.eq(target_user_id))
-        .filter(guild_id.eq(target_guild_id))
-        .first::<GokuPollUsage>(&mut conn)
-    {
-        Ok(usage) => Ok(usage),
-        Err(diesel::result::Error::NotFound) => {
-            let new_usage = NewGokuPollUsage {
-                user_id: target_user_id,
-                guild_id: target_guild_id,
-                usage_count: 0,
-                last_goku_at: SystemTime::now(),
-                created_at: SystemTime::now(),
-            };
+    gulag_appeals::table
+        .filter(gulag_appeals::guild_id.eq(target_guild_id))
+        .filter(gulag_appeals::user_id.eq(target_user_id))
+        .order((gulag_appeals::created_at.desc(), gulag_appeals::id.desc()))
+        .select(GulagAppeal::as_select())
+        .first(&mut conn)
+        .optional()
+}
 
-            diesel::insert_into(goku_poll_usage)
-                .values(&new_usage)
-                .get_result(&mut conn)
-        }
-        Err(e) => Err(e),
-    }
+/// Remember where an appeal's review message was posted.
+pub fn set_gulag_appeal_review_message(
+    pool: &DbPool,
+    appeal_id: i64,
+    review_channel_id: i64,
+    message_id: i64,
+) -> Result<(), diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    diesel::update(gulag_appeals::table.find(appeal_id))
+        .set((
+            gulag_appeals::channel_id.eq(review_channel_id),
+            gulag_appeals::review_message_id.eq(message_id),
+        ))
+        .execute(&mut conn)
+        .map(|_| ())
 }
 
-pub fn atomic_increment_goku_poll(
+/// Resolve a pending appeal. Returns None if it was already resolved, so two
+/// moderators clicking at once can't both act on it.
+pub fn resolve_gulag_appeal(
     pool: &DbPool,
+    appeal_id: i64,
+    new_status: &str,
+    resolved_by: Option<i64>,
+) -> Result<Option<GulagAppeal>, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    diesel::update(
+        gulag_appeals::table
+            .find(appeal_id)
+            .filter(gulag_appeals::status.eq("pending")),
+    )
+    .set((
+        gulag_appeals::status.eq(new_status),
+        gulag_appeals::moderator_id.eq(resolved_by),
+        gulag_appeals::resolved_at.eq(SystemTime::now()),
+    ))
+    .get_result(&mut conn)
+    .optional()
+}
+
+/// Expire a member's pending appeals once their sentence is over, so a stale
+/// appeal neither blocks a new one nor releases a later sentence.
+pub fn expire_pending_gulag_appeals(
+    pool: &DbPool,
+    target_guild_id: i64,
     target_user_id: i64,
+) -> Result<usize, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    diesel::update(
+        gulag_appeals::table
+            .filter(gulag_appeals::guild_id.eq(target_guild_id))
+            .filter(gulag_appeals::user_id.eq(target_user_id))
+            .filter(gulag_appeals::status.eq("pending")),
+    )
+    .set((
+        gulag_appeals::status.eq("expired"),
+        gulag_appeals::moderator_id.eq(None::<i64>),
+        gulag_appeals::resolved_at.eq(SystemTime::now()),
+    ))
+    .execute(&mut conn)
+}
+
+/// Mark an appeal expired with no moderator, whatever its status. Used when an
+/// approval couldn't be carried out, so it doesn't count against the member.
+pub fn expire_gulag_appeal(pool: &DbPool, appeal_id: i64) -> Result<(), diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    diesel::update(gulag_appeals::table.find(appeal_id))
+        .set((
+            gulag_appeals::status.eq("expired"),
+            gulag_appeals::moderator_id.eq(None::<i64>),
+            gulag_appeals::resolved_at.eq(SystemTime::now()),
+        ))
+        .execute(&mut conn)
+        .map(|_| ())
+}
+
+/// All permission grants for a guild, optionally restricted to one capability.
+pub fn get_permission_grants(
+    pool: &DbPool,
     target_guild_id: i64,
-) -> Result<i32, diesel::result::Error> {
+    target_capability: Option<&str>,
+) -> Result<Vec<PermissionGrant>, diesel::result::Error> {
     let mut conn = pool.get().map_err(pool_error_to_diesel)?;
-    use self::goku_poll_usage::dsl::*;
 
-    let time_now = SystemTime::now();
-    let new_record = NewGokuPollUsage {
-        user_id: target_user_id,
-        guild_id: target_guild_id,
-        usage_count: 1,
-        last_goku_at: time_now,
-        created_at: time_now,
-    };
+    let mut query = permission_grants::table
+        .filter(permission_grants::guild_id.eq(target_guild_id))
+        .into_boxed();
+    if let Some(cap) = target_capability {
+        query = query.filter(permission_grants::capability.eq(cap));
+    }
+    query
+        .order((permission_grants::capability, permission_grants::id))
+        .select(PermissionGrant::as_select())
+        .load(&mut conn)
+}
 
-    let result: GokuPollUsage = diesel::insert_into(goku_poll_usage)
-        .values(&new_record)
-        .on_conflict((user_id, guild_id))
-        .do_update()
-        .set((usage_count.eq(usage_count + 1), last_goku_at.eq(time_now)))
-        .get_result(&mut conn)?;
+/// Add a grant. Returns false if the grant already existed.
+pub fn insert_permission_grant(
+    pool: &DbPool,
+    grant: &NewPermissionGrant,
+) -> Result<bool, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
 
-    Ok(result.usage_count)
+    let inserted = diesel::insert_into(permission_grants::table)
+        .values(grant)
+        .on_conflict_do_nothing()
+        .execute(&mut conn)?;
+    Ok(inserted > 0)
 }
 
-pub fn get_server_by_guild_id(pool: &DbPool, target_guild_id: i64) -> Option<Server> {
-    let mut conn = pool.get().ok()?;
-    use self::servers::dsl::*;
+/// Remove a grant. Returns false if there was nothing to remove.
+pub fn delete_permission_grant(
+    pool: &DbPool,
+    grant: &NewPermissionGrant,
+) -> Result<bool, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
 
-    servers
-        .filter(guild_id.eq(target_guild_id))
-        .first::<Server>(&mut conn)
-        .ok()
+    let deleted = diesel::delete(
+        permission_grants::table
+            .filter(permission_grants::guild_id.eq(grant.guild_id))
+            .filter(permission_grants::capability.eq(grant.capability))
+            .filter(permission_grants::grantee_type.eq(grant.grantee_type))
+            .filter(permission_grants::grantee_id.eq(grant.grantee_id)),
+    )
+    .execute(&mut conn)?;
+    Ok(deleted > 0)
 }
 
 pub fn get_is_this_real_usage(
@@ -375,6 +540,22 @@ pub fn update_is_this_real_usage(
 pub fn bulk_upsert_activity(
     pool: &DbPool,
     records: Vec<(i64, i64)>,
+) -> Result<usize, diesel::result::Error> {
+    let time_now = SystemTime::now();
+    bulk_upsert_activity_at(
+        pool,
+        records
+            .into_iter()
+            .map(|(uid, gid)| (uid, gid, time_now))
+            .collect(),
+    )
+}
+
+/// Upsert `(user_id, guild_id, seen_at)` records. `last_message_at` only
+/// ever moves forward.
+pub fn bulk_upsert_activity_at(
+    pool: &DbPool,
+    records: Vec<(i64, i64, SystemTime)>,
 ) -> Result<usize, diesel::result::Error> {
     let mut conn = pool.get().map_err(pool_error_to_diesel)?;
     use crate::db::schema::user_activity;
@@ -383,10 +564,10 @@ pub fn bulk_upsert_activity(
     let time_now = SystemTime::now();
     let new_records: Vec<NewUserActivity> = records
         .into_iter()
-        .map(|(uid, gid)| NewUserActivity {
+        .map(|(uid, gid, seen_at)| NewUserActivity {
             user_id: uid,
             guild_id: gid,
-            last_message_at: time_now,
+            last_message_at: seen_at,
             created_at: time_now,
         })
         .collect();
@@ -455,3 +636,798 @@ pub fn query_user_activity_for_ids(
 
     Ok(results)
 }
+
+/// For each of `cutoffs`, how many tracked users in a guild were last active
+/// at or after it, in the same order.
+pub fn query_active_user_counts(
+    pool: &DbPool,
+    guild_id: i64,
+    cutoffs: &[SystemTime],
+) -> Result<Vec<i64>, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+    use crate::db::schema::user_activity;
+    use diesel::prelude::*;
+
+    cutoffs
+        .iter()
+        .map(|&cutoff| {
+            user_activity::table
+                .filter(user_activity::guild_id.eq(guild_id))
+                .filter(user_activity::last_message_at.ge(cutoff))
+                .count()
+                .get_result(&mut conn)
+        })
+        .collect()
+}
+
+pub fn query_tracked_user_count(
+    pool: &DbPool,
+    guild_id: i64,
+) -> Result<i64, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+    use crate::db::schema::user_activity;
+    use diesel::prelude::*;
+
+    user_activity::table
+        .filter(user_activity::guild_id.eq(guild_id))
+        .count()
+        .get_result(&mut conn)
+}
+
+/// The `max` tracked users in a guild who have been silent the longest.
+pub fn query_least_recently_active_users(
+    pool: &DbPool,
+    guild_id: i64,
+    max: i64,
+) -> Result<Vec<UserActivity>, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+    use crate::db::schema::user_activity;
+    use diesel::prelude::*;
+
+    user_activity::table
+        .filter(user_activity::guild_id.eq(guild_id))
+        .order((
+            user_activity::last_message_at.asc(),
+            user_activity::user_id.asc(),
+        ))
+        .limit(max)
+        .load(&mut conn)
+}
+
+/// Add buffered message counts to the daily and hourly rollups. Counts are
+/// added to existing rows, and either everything is written or nothing is.
+pub fn add_message_counts(
+    pool: &DbPool,
+    counts: &[MessageCount],
+    hour_counts: &[MessageHourCount],
+) -> Result<usize, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+    use diesel::upsert::excluded;
+
+    conn.transaction(|conn| {
+        let mut rows = 0;
+        for chunk in counts.chunks(INSERT_CHUNK) {
+            rows +=
+                diesel::insert_into(message_counts::table)
+                    .values(chunk)
+                    .on_conflict((
+                        message_counts::guild_id,
+                        message_counts::day,
+                        message_counts::channel_id,
+                        message_counts::user_id,
+                    ))
+                    .do_update()
+                    .set(
+                        message_counts::message_count
+                            .eq(message_counts::message_count
+                                + excluded(message_counts::message_count)),
+                    )
+                    .execute(conn)?;
+        }
+        for chunk in hour_counts.chunks(INSERT_CHUNK) {
+            rows += diesel::insert_into(message_hour_counts::table)
+                .values(chunk)
+                .on_conflict((
+                    message_hour_counts::guild_id,
+                    message_hour_counts::day,
+                    message_hour_counts::hour,
+                ))
+                .do_update()
+                .set(
+                    message_hour_counts::message_count.eq(message_hour_counts::message_count
+                        + excluded(message_hour_counts::message_count)),
+                )
+                .execute(conn)?;
+        }
+        Ok(rows)
+    })
+}
+
+/// Drop message rollups for days before `day`.
+pub fn delete_message_counts_before(
+    pool: &DbPool,
+    day: i32,
+) -> Result<usize, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    let daily = diesel::delete(message_counts::table.filter(message_counts::day.lt(day)))
+        .execute(&mut conn)?;
+    let hourly =
+        diesel::delete(message_hour_counts::table.filter(message_hour_counts::day.lt(day)))
+            .execute(&mut conn)?;
+    Ok(daily + hourly)
+}
+
+/// The `max` users in a guild (optionally in one channel) with the most
+/// messages since `since_day`, as `(user_id, messages)`.
+pub fn query_top_posters(
+    pool: &DbPool,
+    guild_id: i64,
+    since_day: i32,
+    channel_id: Option<i64>,
+    max: i64,
+) -> Result<Vec<(i64, i64)>, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+    use diesel::dsl::sum;
+
+    let mut query = message_counts::table
+        .filter(message_counts::guild_id.eq(guild_id))
+        .filter(message_counts::day.ge(since_day))
+        .group_by(message_counts::user_id)
+        .select((message_counts::user_id, sum(message_counts::message_count)))
+        .order((
+            sum(message_counts::message_count).desc(),
+            message_counts::user_id,
+        ))
+        .limit(max)
+        .into_boxed();
+    if let Some(channel_id) = channel_id {
+        query = query.filter(message_counts::channel_id.eq(channel_id));
+    }
+    let rows: Vec<(i64, Option<i64>)> = query.load(&mut conn)?;
+    Ok(rows
+        .into_iter()
+        .map(|(user_id, count)| (user_id, count.unwrap_or(0)))
+        .collect())
+}
+
+/// The `max` channels in a guild with the most messages since `since_day`,
+/// as `(channel_id, messages)`.
+pub fn query_busiest_channels(
+    pool: &DbPool,
+    guild_id: i64,
+    since_day: i32,
+    max: i64,
+) -> Result<Vec<(i64, i64)>, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+    use diesel::dsl::sum;
+
+    let rows: Vec<(i64, Option<i64>)> = message_counts::table
+        .filter(message_counts::guild_id.eq(guild_id))
+        .filter(message_counts::day.ge(since_day))
+        .group_by(message_counts::channel_id)
+        .select((
+            message_counts::channel_id,
+            sum(message_counts::message_count),
+        ))
+        .order((
+            sum(message_counts::message_count).desc(),
+            message_counts::channel_id,
+        ))
+        .limit(max)
+        .load(&mut conn)?;
+    Ok(rows
+        .into_iter()
+        .map(|(channel_id, count)| (channel_id, count.unwrap_or(0)))
+        .collect())
+}
+
+/// Each user's message total in a guild since `since_day`. Users without
+/// messages in that window are absent.
+pub fn query_message_totals(
+    pool: &DbPool,
+    guild_id: i64,
+    since_day: i32,
+) -> Result<Vec<(i64, i64)>, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+    use diesel::dsl::sum;
+
+    let rows: Vec<(i64, Option<i64>)> = message_counts::table
+        .filter(message_counts::guild_id.eq(guild_id))
+        .filter(message_counts::day.ge(since_day))
+        .group_by(message_counts::user_id)
+        .select((message_counts::user_id, sum(message_counts::message_count)))
+        .load(&mut conn)?;
+    Ok(rows
+        .into_iter()
+        .map(|(user_id, count)| (user_id, count.unwrap_or(0)))
+        .collect())
+}
+
+/// The first day a guild has message counts for, if any.
+pub fn query_first_message_count_day(
+    pool: &DbPool,
+    guild_id: i64,
+) -> Result<Option<i32>, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    message_counts::table
+        .filter(message_counts::guild_id.eq(guild_id))
+        .select(diesel::dsl::min(message_counts::day))
+        .first(&mut conn)
+}
+
+pub fn query_message_hour_counts(
+    pool: &DbPool,
+    guild_id: i64,
+    since_day: i32,
+) -> Result<Vec<MessageHourCount>, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    message_hour_counts::table
+        .filter(message_hour_counts::guild_id.eq(guild_id))
+        .filter(message_hour_counts::day.ge(since_day))
+        .select(MessageHourCount::as_select())
+        .load(&mut conn)
+}
+
+/// Exemptions in a guild that have not expired by `now`.
+pub fn get_active_cull_exemptions(
+    pool: &DbPool,
+    target_guild_id: i64,
+    now: SystemTime,
+) -> Result<Vec<CullExemption>, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    cull_exemptions::table
+        .filter(cull_exemptions::guild_id.eq(target_guild_id))
+        .filter(
+            cull_exemptions::expires_at
+                .is_null()
+                .or(cull_exemptions::expires_at.gt(now)),
+        )
+        .order(cull_exemptions::created_at.asc())
+        .select(CullExemption::as_select())
+        .load(&mut conn)
+}
+
+/// Add an exemption, replacing any existing one for the same member.
+pub fn upsert_cull_exemption(
+    pool: &DbPool,
+    exemption: &CullExemption,
+) -> Result<CullExemption, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    diesel::insert_into(cull_exemptions::table)
+        .values(exemption)
+        .on_conflict((cull_exemptions::guild_id, cull_exemptions::user_id))
+        .do_update()
+        .set(exemption)
+        .get_result(&mut conn)
+}
+
+/// Remove a member's exemption. Returns whether one existed.
+pub fn delete_cull_exemption(
+    pool: &DbPool,
+    target_guild_id: i64,
+    target_user_id: i64,
+) -> Result<bool, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    let rows = diesel::delete(cull_exemptions::table.find((target_guild_id, target_user_id)))
+        .execute(&mut conn)?;
+    Ok(rows > 0)
+}
+
+/// Scan progress for every channel `/cull scan` has touched in a guild.
+pub fn get_cull_scan_checkpoints(
+    pool: &DbPool,
+    target_guild_id: i64,
+) -> Result<Vec<CullScanCheckpoint>, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    cull_scan_checkpoints::table
+        .filter(cull_scan_checkpoints::guild_id.eq(target_guild_id))
+        .order(cull_scan_checkpoints::channel_name.asc())
+        .select(CullScanCheckpoint::as_select())
+        .load(&mut conn)
+}
+
+/// Insert or fully replace a channel's scan checkpoint.
+pub fn upsert_cull_scan_checkpoint(
+    pool: &DbPool,
+    checkpoint: &CullScanCheckpoint,
+) -> Result<CullScanCheckpoint, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    diesel::insert_into(cull_scan_checkpoints::table)
+        .values(checkpoint)
+        .on_conflict((
+            cull_scan_checkpoints::guild_id,
+            cull_scan_checkpoints::channel_id,
+        ))
+        .do_update()
+        .set(checkpoint)
+        .get_result(&mut conn)
+}
+
+/// Forget a guild's scan progress so the next `/cull scan` starts over.
+pub fn delete_cull_scan_checkpoints(
+    pool: &DbPool,
+    target_guild_id: i64,
+) -> Result<usize, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    diesel::delete(
+        cull_scan_checkpoints::table.filter(cull_scan_checkpoints::guild_id.eq(target_guild_id)),
+    )
+    .execute(&mut conn)
+}
+
+pub fn get_cull_schedule(
+    pool: &DbPool,
+    target_guild_id: i64,
+) -> Result<Option<CullSchedule>, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    cull_schedules::table
+        .find(target_guild_id)
+        .select(CullSchedule::as_select())
+        .first(&mut conn)
+        .optional()
+}
+
+/// Insert or fully replace a guild's cull schedule.
+pub fn upsert_cull_schedule(
+    pool: &DbPool,
+    schedule: &CullSchedule,
+) -> Result<CullSchedule, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    diesel::insert_into(cull_schedules::table)
+        .values(schedule)
+        .on_conflict(cull_schedules::guild_id)
+        .do_update()
+        .set(schedule)
+        .get_result(&mut conn)
+}
+
+/// Enabled schedules that need attention at `now`: either a warning round
+/// is due to start, or an open round has reached its deadline.
+pub fn get_due_cull_schedules(
+    pool: &DbPool,
+    now: SystemTime,
+) -> Result<Vec<CullSchedule>, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    cull_schedules::table
+        .filter(cull_schedules::enabled.eq(true))
+        .filter(
+            cull_schedules::deadline_at
+                .is_null()
+                .and(cull_schedules::next_run_at.le(now))
+                .or(cull_schedules::deadline_at.le(now)),
+        )
+        .select(CullSchedule::as_select())
+        .load(&mut conn)
+}
+
+/// Start a warning round: replace the guild's warnings with `user_ids` and
+/// set the schedule's deadline.
+pub fn open_cull_warnings(
+    pool: &DbPool,
+    target_guild_id: i64,
+    user_ids: &[i64],
+    warned_at: SystemTime,
+    deadline: SystemTime,
+) -> Result<(), diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    conn.transaction(|conn| {
+        diesel::delete(cull_warnings::table.filter(cull_warnings::guild_id.eq(target_guild_id)))
+            .execute(conn)?;
+        let warnings: Vec<CullWarning> = user_ids
+            .iter()
+            .map(|&user_id| CullWarning {
+                guild_id: target_guild_id,
+                user_id,
+                warned_at,
+            })
+            .collect();
+        diesel::insert_into(cull_warnings::table)
+            .values(&warnings)
+            .execute(conn)?;
+        diesel::update(cull_schedules::table.find(target_guild_id))
+            .set(cull_schedules::deadline_at.eq(Some(deadline)))
+            .execute(conn)?;
+        Ok(())
+    })
+}
+
+pub fn get_cull_warnings(
+    pool: &DbPool,
+    target_guild_id: i64,
+) -> Result<Vec<CullWarning>, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    cull_warnings::table
+        .filter(cull_warnings::guild_id.eq(target_guild_id))
+        .select(CullWarning::as_select())
+        .load(&mut conn)
+}
+
+/// Finish a guild's warning round (or skip one): clear its warnings and
+/// deadline and schedule the next round for `next_run_at`.
+pub fn close_cull_warnings(
+    pool: &DbPool,
+    target_guild_id: i64,
+    next_run_at: SystemTime,
+) -> Result<(), diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    conn.transaction(|conn| {
+        diesel::delete(cull_warnings::table.filter(cull_warnings::guild_id.eq(target_guild_id)))
+            .execute(conn)?;
+        diesel::update(cull_schedules::table.find(target_guild_id))
+            .set((
+                cull_schedules::deadline_at.eq(None::<SystemTime>),
+                cull_schedules::next_run_at.eq(next_run_at),
+            ))
+            .execute(conn)?;
+        Ok(())
+    })
+}
+
+/// Record a cull run and its candidates, all with outcome 'pending'.
+pub fn insert_cull_run(
+    pool: &DbPool,
+    run: &NewCullRun,
+    user_ids: &[i64],
+) -> Result<CullRun, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    conn.transaction(|conn| {
+        let run: CullRun = diesel::insert_into(cull_runs::table)
+            .values(run)
+            .returning(CullRun::as_returning())
+            .get_result(conn)?;
+        let members: Vec<_> = user_ids
+            .iter()
+            .map(|&user_id| {
+                (
+                    cull_run_members::run_id.eq(run.id),
+                    cull_run_members::user_id.eq(user_id),
+                )
+            })
+            .collect();
+        diesel::insert_into(cull_run_members::table)
+            .values(&members)
+            .execute(conn)?;
+        Ok(run)
+    })
+}
+
+pub fn set_cull_run_outcome(
+    pool: &DbPool,
+    target_run_id: i64,
+    target_user_id: i64,
+    outcome: &str,
+    error: Option<&str>,
+) -> Result<(), diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    diesel::update(cull_run_members::table.find((target_run_id, target_user_id)))
+        .set((
+            cull_run_members::outcome.eq(outcome),
+            cull_run_members::error.eq(error),
+        ))
+        .execute(&mut conn)?;
+    Ok(())
+}
+
+/// Mark the members a cancelled run never reached as `cancelled`.
+pub fn cancel_pending_cull_run_members(
+    pool: &DbPool,
+    target_run_id: i64,
+) -> Result<usize, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    diesel::update(
+        cull_run_members::table
+            .filter(cull_run_members::run_id.eq(target_run_id))
+            .filter(cull_run_members::outcome.eq("pending")),
+    )
+    .set(cull_run_members::outcome.eq("cancelled"))
+    .execute(&mut conn)
+}
+
+pub fn finish_cull_run(
+    pool: &DbPool,
+    target_run_id: i64,
+    finished_at: SystemTime,
+) -> Result<(), diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    diesel::update(cull_runs::table.find(target_run_id))
+        .set(cull_runs::finished_at.eq(Some(finished_at)))
+        .execute(&mut conn)?;
+    Ok(())
+}
+
+/// A guild's most recent cull runs, newest first.
+pub fn get_cull_runs(
+    pool: &DbPool,
+    target_guild_id: i64,
+    max: i64,
+) -> Result<Vec<CullRun>, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    cull_runs::table
+        .filter(cull_runs::guild_id.eq(target_guild_id))
+        .order((cull_runs::started_at.desc(), cull_runs::id.desc()))
+        .limit(max)
+        .select(CullRun::as_select())
+        .load(&mut conn)
+}
+
+/// How many members of each run ended up with each outcome, as
+/// `(run_id, outcome, count)`.
+pub fn count_cull_run_outcomes(
+    pool: &DbPool,
+    run_ids: &[i64],
+) -> Result<Vec<(i64, String, i64)>, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    cull_run_members::table
+        .filter(cull_run_members::run_id.eq_any(run_ids))
+        .group_by((cull_run_members::run_id, cull_run_members::outcome))
+        .select((
+            cull_run_members::run_id,
+            cull_run_members::outcome,
+            diesel::dsl::count_star(),
+        ))
+        .load(&mut conn)
+}
+
+/// A run of this guild's, if it exists.
+pub fn get_cull_run(
+    pool: &DbPool,
+    target_guild_id: i64,
+    target_run_id: i64,
+) -> Result<Option<CullRun>, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    cull_runs::table
+        .find(target_run_id)
+        .filter(cull_runs::guild_id.eq(target_guild_id))
+        .select(CullRun::as_select())
+        .first(&mut conn)
+        .optional()
+}
+
+pub fn get_cull_run_members(
+    pool: &DbPool,
+    target_run_id: i64,
+) -> Result<Vec<CullRunMember>, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    cull_run_members::table
+        .filter(cull_run_members::run_id.eq(target_run_id))
+        .order(cull_run_members::user_id)
+        .select(CullRunMember::as_select())
+        .load(&mut conn)
+}
+
+/// The most recent cull in a guild that kicked this user.
+pub fn get_latest_cull_kick(
+    pool: &DbPool,
+    target_guild_id: i64,
+    target_user_id: i64,
+) -> Result<Option<(CullRun, CullRunMember)>, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    cull_run_members::table
+        .inner_join(cull_runs::table)
+        .filter(cull_runs::guild_id.eq(target_guild_id))
+        .filter(cull_run_members::user_id.eq(target_user_id))
+        .filter(cull_run_members::outcome.eq("kicked"))
+        .order((cull_runs::started_at.desc(), cull_runs::id.desc()))
+        .select((CullRun::as_select(), CullRunMember::as_select()))
+        .first(&mut conn)
+        .optional()
+}
+
+pub fn set_cull_reinvited(
+    pool: &DbPool,
+    target_run_id: i64,
+    target_user_id: i64,
+    reinvited_at: SystemTime,
+) -> Result<(), diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    diesel::update(cull_run_members::table.find((target_run_id, target_user_id)))
+        .set(cull_run_members::reinvited_at.eq(Some(reinvited_at)))
+        .execute(&mut conn)?;
+    Ok(())
+}
+
+/// The conversation held in a thread, if tugbot has answered there before.
+pub fn get_thread_conversation(
+    pool: &DbPool,
+    target_guild_id: i64,
+    target_thread_id: i64,
+) -> Result<Option<Conversation>, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    conversations::table
+        .filter(conversations::guild_id.eq(target_guild_id))
+        .filter(conversations::thread_id.eq(target_thread_id))
+        .order(conversations::id.desc())
+        .select(Conversation::as_select())
+        .first(&mut conn)
+        .optional()
+}
+
+/// The conversation one of tugbot's answers belongs to.
+pub fn get_conversation_for_answer(
+    pool: &DbPool,
+    target_guild_id: i64,
+    target_message_id: i64,
+) -> Result<Option<Conversation>, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    conversation_messages::table
+        .inner_join(conversations::table)
+        .filter(conversations::guild_id.eq(target_guild_id))
+        .filter(conversation_messages::message_id.eq(target_message_id))
+        .filter(conversation_messages::role.eq("assistant"))
+        .select(Conversation::as_select())
+        .first(&mut conn)
+        .optional()
+}
+
+/// The latest `max` turns of a conversation, oldest first.
+pub fn get_conversation_history(
+    pool: &DbPool,
+    target_conversation_id: i64,
+    max: i64,
+) -> Result<Vec<ConversationMessage>, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    let mut messages: Vec<ConversationMessage> = conversation_messages::table
+        .filter(conversation_messages::conversation_id.eq(target_conversation_id))
+        .order(conversation_messages::id.desc())
+        .limit(max)
+        .select(ConversationMessage::as_select())
+        .load(&mut conn)?;
+    messages.reverse();
+    Ok(messages)
+}
+
+/// Create a conversation along with its first turns.
+pub fn start_conversation(
+    pool: &DbPool,
+    conversation: &NewConversation,
+    messages: &[NewConversationMessage],
+) -> Result<Conversation, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    conn.transaction(|conn| {
+        let conversation: Conversation = diesel::insert_into(conversations::table)
+            .values(conversation)
+            .returning(Conversation::as_returning())
+            .get_result(conn)?;
+        insert_conversation_messages(conn, conversation.id, messages)?;
+        Ok(conversation)
+    })
+}
+
+/// Append turns to a conversation and mark it as updated.
+pub fn continue_conversation(
+    pool: &DbPool,
+    target_conversation_id: i64,
+    messages: &[NewConversationMessage],
+) -> Result<(), diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    conn.transaction(|conn| {
+        insert_conversation_messages(conn, target_conversation_id, messages)?;
+        diesel::update(conversations::table.find(target_conversation_id))
+            .set(conversations::updated_at.eq(SystemTime::now()))
+            .execute(conn)?;
+        Ok(())
+    })
+}
+
+fn insert_conversation_messages(
+    conn: &mut PgConnection,
+    target_conversation_id: i64,
+    messages: &[NewConversationMessage],
+) -> Result<(), diesel::result::Error> {
+    let rows: Vec<_> = messages
+        .iter()
+        .map(|message| {
+            (
+                conversation_messages::conversation_id.eq(target_conversation_id),
+                message,
+            )
+        })
+        .collect();
+    diesel::insert_into(conversation_messages::table)
+        .values(rows)
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Delete the conversation held in `channel_id` if it is a thread, and
+/// otherwise the reply-chain conversations `user_id` started there.
+/// Returns how many conversations were deleted.
+pub fn forget_conversations(
+    pool: &DbPool,
+    target_guild_id: i64,
+    target_channel_id: i64,
+    target_user_id: i64,
+) -> Result<usize, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    diesel::delete(
+        conversations::table
+            .filter(conversations::guild_id.eq(target_guild_id))
+            .filter(
+                conversations::thread_id
+                    .eq(target_channel_id)
+                    .or(conversations::channel_id
+                        .eq(target_channel_id)
+                        .and(conversations::thread_id.is_null())
+                        .and(conversations::started_by.eq(target_user_id))),
+            ),
+    )
+    .execute(&mut conn)
+}
+
+/// Delete the turns of `user_id`'s questions, and tugbot's answers to them,
+/// from every conversation in the guild, along with conversations left with
+/// no turns. Returns how many questions were deleted.
+pub fn forget_conversation_turns(
+    pool: &DbPool,
+    target_guild_id: i64,
+    target_user_id: i64,
+) -> Result<usize, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    conn.transaction(|conn| {
+        let guild_conversations = conversations::table
+            .filter(conversations::guild_id.eq(target_guild_id))
+            .select(conversations::id);
+        let roles: Vec<String> = diesel::delete(
+            conversation_messages::table
+                .filter(conversation_messages::conversation_id.eq_any(guild_conversations))
+                .filter(conversation_messages::asked_by.eq(target_user_id)),
+        )
+        .returning(conversation_messages::role)
+        .get_results(conn)?;
+
+        let with_turns =
+            conversation_messages::table.select(conversation_messages::conversation_id);
+        diesel::delete(
+            conversations::table
+                .filter(conversations::guild_id.eq(target_guild_id))
+                .filter(conversations::id.ne_all(with_turns)),
+        )
+        .execute(conn)?;
+
+        Ok(roles.iter().filter(|role| *role == "user").count())
+    })
+}
+
+/// Delete conversations with no turns since `cutoff`. Returns how many were
+/// deleted.
+pub fn delete_conversations_before(
+    pool: &DbPool,
+    cutoff: SystemTime,
+) -> Result<usize, diesel::result::Error> {
+    let mut conn = pool.get().map_err(pool_error_to_diesel)?;
+
+    diesel::delete(conversations::table.filter(conversations::updated_at.lt(cutoff)))
+        .execute(&mut conn)
+}
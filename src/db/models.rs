@@ -7,48 +7,6 @@ use diesel::{
 use serde::{Deserialize, Serialize};
 use std::{io::Write, time::SystemTime};
 
-#[derive(Queryable, Selectable, Debug)]
-#[diesel(table_name = ai_slop_usage)]
-pub struct AiSlopUsage {
-    pub id: i32,
-    pub user_id: i64,
-    pub guild_id: i64,
-    pub usage_count: i32,
-    pub last_slop_at: SystemTime,
-    pub created_at: SystemTime,
-}
-
-#[derive(Insertable)]
-#[diesel(table_name = ai_slop_usage)]
-pub struct NewAiSlopUsage {
-    pub user_id: i64,
-    pub guild_id: i64,
-    pub usage_count: i32,
-    pub last_slop_at: SystemTime,
-    pub created_at: SystemTime,
-}
-
-#[derive(Queryable, Selectable, Debug)]
-#[diesel(table_name = goku_poll_usage)]
-pub struct GokuPollUsage {
-    pub id: i32,
-    pub user_id: i64,
-    pub guild_id: i64,
-    pub usage_count: i32,
-    pub last_goku_at: SystemTime,
-    pub created_at: SystemTime,
-}
-
-#[derive(Insertable)]
-#[diesel(table_name = goku_poll_usage)]
-pub struct NewGokuPollUsage {
-    pub user_id: i64,
-    pub guild_id: i64,
-    pub usage_count: i32,
-    pub last_goku_at: SystemTime,
-    pub created_at: SystemTime,
-}
-
 #[derive(Queryable)]
 pub struct Server {
     pub id: i32,
@@ -77,6 +35,7 @@ pub struct GulagUser {
     pub release_at: SystemTime,
     pub remod: bool,
     pub message_id: i64,
+    pub reason: Option<String>,
 }
 
 #[derive(Insertable)]
@@ -92,6 +51,7 @@ pub struct NewGulagUser {
     pub release_at: SystemTime,
     pub remod: bool,
     pub message_id: i64,
+    pub reason: Option<String>,
 }
 
 #[derive(Queryable, Selectable)]
@@ -222,6 +182,111 @@ pub struct Features {
     pub id: i32,
     pub name: String,
     pub enabled: bool,
+    pub guild_id: i64,
+}
+
+#[derive(Queryable, Selectable, Insertable, AsChangeset, Debug, Clone)]
+#[diesel(table_name = guild_settings, treat_none_as_null = true)]
+pub struct GuildSettings {
+    pub guild_id: i64,
+    pub ask_channel_id: Option<i64>,
+    pub mod_log_channel_id: Option<i64>,
+    pub gulag_channel_id: Option<i64>,
+    pub moderator_role_ids: Vec<Option<i64>>,
+    pub cull_whitelist_role_ids: Vec<Option<i64>>,
+    pub updated_at: SystemTime,
+    pub cull_grace_days: Option<i32>,
+}
+
+#[derive(Queryable, Selectable, Debug, Clone)]
+#[diesel(table_name = moderation_events)]
+pub struct ModerationEvent {
+    pub id: i64,
+    pub guild_id: i64,
+    pub user_id: i64,
+    pub action: String,
+    pub source: String,
+    pub moderator_id: Option<i64>,
+    pub reason: Option<String>,
+    pub duration_seconds: Option<i32>,
+    pub created_at: SystemTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = moderation_events)]
+pub struct NewModerationEvent<'a> {
+    pub guild_id: i64,
+    pub user_id: i64,
+    pub action: &'a str,
+    pub source: &'a str,
+    pub moderator_id: Option<i64>,
+    pub reason: Option<&'a str>,
+    pub duration_seconds: Option<i32>,
+}
+
+#[derive(Queryable, Selectable, Debug, Clone)]
+#[diesel(table_name = gulag_appeals)]
+pub struct GulagAppeal {
+    pub id: i64,
+    pub guild_id: i64,
+    pub user_id: i64,
+    pub message: String,
+    pub status: String,
+    pub moderator_id: Option<i64>,
+    pub channel_id: Option<i64>,
+    pub review_message_id: Option<i64>,
+    pub created_at: SystemTime,
+    pub resolved_at: Option<SystemTime>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = gulag_appeals)]
+pub struct NewGulagAppeal<'a> {
+    pub guild_id: i64,
+    pub user_id: i64,
+    pub message: &'a str,
+}
+
+#[derive(Queryable, Selectable, Insertable, AsChangeset, Debug, Clone, PartialEq)]
+#[diesel(table_name = offense_policies, treat_none_as_null = true)]
+pub struct OffensePolicy {
+    pub guild_id: i64,
+    pub offense_type: String,
+    pub base_seconds: i32,
+    pub multiplier: f64,
+    pub cap_seconds: i32,
+    pub decay_window_seconds: Option<i32>,
+}
+
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = offenses)]
+pub struct Offense {
+    pub user_id: i64,
+    pub guild_id: i64,
+    pub offense_type: String,
+    pub count: i32,
+    pub last_offense_at: SystemTime,
+    pub created_at: SystemTime,
+}
+
+#[derive(Queryable, Selectable, Debug, Clone)]
+#[diesel(table_name = permission_grants)]
+pub struct PermissionGrant {
+    pub id: i32,
+    pub guild_id: i64,
+    pub capability: String,
+    pub grantee_type: String,
+    pub grantee_id: i64,
+    pub created_at: SystemTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = permission_grants)]
+pub struct NewPermissionGrant<'a> {
+    pub guild_id: i64,
+    pub capability: &'a str,
+    pub grantee_type: &'a str,
+    pub grantee_id: i64,
 }
 
 #[derive(Queryable, Selectable, Debug)]
@@ -242,6 +307,106 @@ pub struct NewUserActivity {
     pub created_at: SystemTime,
 }
 
+#[derive(Queryable, Selectable, Insertable, AsChangeset, Debug, Clone)]
+#[diesel(table_name = cull_exemptions, treat_none_as_null = true)]
+pub struct CullExemption {
+    pub guild_id: i64,
+    pub user_id: i64,
+    pub reason: Option<String>,
+    pub expires_at: Option<SystemTime>,
+    pub added_by: i64,
+    pub created_at: SystemTime,
+}
+
+#[derive(Queryable, Selectable, Insertable, AsChangeset, Debug, Clone)]
+#[diesel(table_name = cull_scan_checkpoints, treat_none_as_null = true)]
+pub struct CullScanCheckpoint {
+    pub guild_id: i64,
+    pub channel_id: i64,
+    pub channel_name: String,
+    pub oldest_message_id: Option<i64>,
+    pub messages_scanned: i64,
+    pub completed: bool,
+    pub updated_at: SystemTime,
+}
+
+#[derive(Queryable, Selectable, Insertable, AsChangeset, Debug, Clone, PartialEq)]
+#[diesel(table_name = cull_schedules, treat_none_as_null = true)]
+pub struct CullSchedule {
+    pub guild_id: i64,
+    pub enabled: bool,
+    pub inactive_days: i32,
+    pub interval_days: i32,
+    pub warning_days: i32,
+    pub include_never_posted: bool,
+    pub warning_channel_id: Option<i64>,
+    pub next_run_at: SystemTime,
+    pub deadline_at: Option<SystemTime>,
+    pub updated_at: SystemTime,
+}
+
+#[derive(Queryable, Selectable, Insertable, Debug, Clone)]
+#[diesel(table_name = cull_warnings)]
+pub struct CullWarning {
+    pub guild_id: i64,
+    pub user_id: i64,
+    pub warned_at: SystemTime,
+}
+
+#[derive(Queryable, Selectable, Debug, Clone)]
+#[diesel(table_name = cull_runs)]
+pub struct CullRun {
+    pub id: i64,
+    pub guild_id: i64,
+    pub source: String,
+    pub invoked_by: Option<i64>,
+    pub inactive_days: i32,
+    pub include_never_posted: bool,
+    pub started_at: SystemTime,
+    pub finished_at: Option<SystemTime>,
+    pub min_messages: Option<i32>,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = cull_runs)]
+pub struct NewCullRun<'a> {
+    pub guild_id: i64,
+    pub source: &'a str,
+    pub invoked_by: Option<i64>,
+    pub inactive_days: i32,
+    pub include_never_posted: bool,
+    pub min_messages: Option<i32>,
+}
+
+#[derive(Queryable, Selectable, Debug, Clone)]
+#[diesel(table_name = cull_run_members)]
+pub struct CullRunMember {
+    pub run_id: i64,
+    pub user_id: i64,
+    pub outcome: String,
+    pub error: Option<String>,
+    pub reinvited_at: Option<SystemTime>,
+}
+
+#[derive(Queryable, Selectable, Insertable, Debug, Clone, PartialEq)]
+#[diesel(table_name = message_counts)]
+pub struct MessageCount {
+    pub guild_id: i64,
+    pub channel_id: i64,
+    pub user_id: i64,
+    pub day: i32,
+    pub message_count: i32,
+}
+
+#[derive(Queryable, Selectable, Insertable, Debug, Clone, PartialEq)]
+#[diesel(table_name = message_hour_counts)]
+pub struct MessageHourCount {
+    pub guild_id: i64,
+    pub day: i32,
+    pub hour: i16,
+    pub message_count: i32,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -278,6 +443,7 @@ mod tests {
             release_at: now + Duration::from_secs(300),
             remod: false,
             message_id: 777888999,
+            reason: Some("spamming".to_string()),
         };
         assert_eq!(user.user_id, 123456789);
         assert_eq!(user.gulag_length, 300);
@@ -307,3 +473,47 @@ mod tests {
         assert!(overdue.as_secs() >= 3599 && overdue.as_secs() <= 3601);
     }
 }
+
+#[derive(Queryable, Selectable, Debug, Clone)]
+#[diesel(table_name = conversations)]
+pub struct Conversation {
+    pub id: i64,
+    pub guild_id: i64,
+    pub channel_id: i64,
+    pub thread_id: Option<i64>,
+    pub started_by: i64,
+    pub created_at: SystemTime,
+    pub updated_at: SystemTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = conversations)]
+pub struct NewConversation {
+    pub guild_id: i64,
+    pub channel_id: i64,
+    pub thread_id: Option<i64>,
+    pub started_by: i64,
+}
+
+#[derive(Queryable, Selectable, Debug, Clone, PartialEq)]
+#[diesel(table_name = conversation_messages)]
+pub struct ConversationMessage {
+    pub id: i64,
+    pub conversation_id: i64,
+    pub message_id: i64,
+    pub asked_by: i64,
+    pub role: String,
+    pub author_name: String,
+    pub content: String,
+    pub created_at: SystemTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = conversation_messages)]
+pub struct NewConversationMessage<'a> {
+    pub message_id: i64,
+    pub asked_by: i64,
+    pub role: &'a str,
+    pub author_name: &'a str,
+    pub content: &'a str,
+}
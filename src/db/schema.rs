@@ -7,13 +7,97 @@ pub mod sql_types {
 }
 
 diesel::table! {
-    ai_slop_usage (id) {
-        id -> Int4,
-        user_id -> Int8,
+    conversation_messages (id) {
+        id -> Int8,
+        conversation_id -> Int8,
+        message_id -> Int8,
+        asked_by -> Int8,
+        role -> Varchar,
+        author_name -> Varchar,
+        content -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    conversations (id) {
+        id -> Int8,
         guild_id -> Int8,
-        usage_count -> Int4,
-        last_slop_at -> Timestamp,
+        channel_id -> Int8,
+        thread_id -> Nullable<Int8>,
+        started_by -> Int8,
         created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    cull_exemptions (guild_id, user_id) {
+        guild_id -> Int8,
+        user_id -> Int8,
+        reason -> Nullable<Text>,
+        expires_at -> Nullable<Timestamp>,
+        added_by -> Int8,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    cull_run_members (run_id, user_id) {
+        run_id -> Int8,
+        user_id -> Int8,
+        outcome -> Varchar,
+        error -> Nullable<Text>,
+        reinvited_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    cull_runs (id) {
+        id -> Int8,
+        guild_id -> Int8,
+        source -> Varchar,
+        invoked_by -> Nullable<Int8>,
+        inactive_days -> Int4,
+        include_never_posted -> Bool,
+        started_at -> Timestamp,
+        finished_at -> Nullable<Timestamp>,
+        min_messages -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    cull_scan_checkpoints (guild_id, channel_id) {
+        guild_id -> Int8,
+        channel_id -> Int8,
+        channel_name -> Text,
+        oldest_message_id -> Nullable<Int8>,
+        messages_scanned -> Int8,
+        completed -> Bool,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    cull_schedules (guild_id) {
+        guild_id -> Int8,
+        enabled -> Bool,
+        inactive_days -> Int4,
+        interval_days -> Int4,
+        warning_days -> Int4,
+        include_never_posted -> Bool,
+        warning_channel_id -> Nullable<Int8>,
+        next_run_at -> Timestamp,
+        deadline_at -> Nullable<Timestamp>,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    cull_warnings (guild_id, user_id) {
+        guild_id -> Int8,
+        user_id -> Int8,
+        warned_at -> Timestamp,
     }
 }
 
@@ -23,17 +107,22 @@ diesel::table! {
         #[max_length = 255]
         name -> Varchar,
         enabled -> Bool,
+        guild_id -> Int8,
     }
 }
 
 diesel::table! {
-    goku_poll_usage (id) {
-        id -> Int4,
-        user_id -> Int8,
+    gulag_appeals (id) {
+        id -> Int8,
         guild_id -> Int8,
-        usage_count -> Int4,
-        last_goku_at -> Timestamp,
+        user_id -> Int8,
+        message -> Text,
+        status -> Varchar,
+        moderator_id -> Nullable<Int8>,
+        channel_id -> Nullable<Int8>,
+        review_message_id -> Nullable<Int8>,
         created_at -> Timestamp,
+        resolved_at -> Nullable<Timestamp>,
     }
 }
 
@@ -50,6 +139,7 @@ diesel::table! {
         release_at -> Timestamp,
         remod -> Bool,
         message_id -> Int8,
+        reason -> Nullable<Text>,
     }
 }
 
@@ -67,6 +157,19 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    guild_settings (guild_id) {
+        guild_id -> Int8,
+        ask_channel_id -> Nullable<Int8>,
+        mod_log_channel_id -> Nullable<Int8>,
+        gulag_channel_id -> Nullable<Int8>,
+        moderator_role_ids -> Array<Nullable<Int8>>,
+        cull_whitelist_role_ids -> Array<Nullable<Int8>>,
+        updated_at -> Timestamp,
+        cull_grace_days -> Nullable<Int4>,
+    }
+}
+
 diesel::table! {
     is_this_real_usage (id) {
         id -> Int4,
@@ -77,6 +180,25 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    message_counts (guild_id, day, channel_id, user_id) {
+        guild_id -> Int8,
+        channel_id -> Int8,
+        user_id -> Int8,
+        day -> Int4,
+        message_count -> Int4,
+    }
+}
+
+diesel::table! {
+    message_hour_counts (guild_id, day, hour) {
+        guild_id -> Int8,
+        day -> Int4,
+        hour -> Int2,
+        message_count -> Int4,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::JobStatus;
@@ -93,6 +215,53 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    moderation_events (id) {
+        id -> Int8,
+        guild_id -> Int8,
+        user_id -> Int8,
+        action -> Varchar,
+        source -> Varchar,
+        moderator_id -> Nullable<Int8>,
+        reason -> Nullable<Text>,
+        duration_seconds -> Nullable<Int4>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    offense_policies (guild_id, offense_type) {
+        guild_id -> Int8,
+        offense_type -> Varchar,
+        base_seconds -> Int4,
+        multiplier -> Float8,
+        cap_seconds -> Int4,
+        decay_window_seconds -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    offenses (user_id, guild_id, offense_type) {
+        user_id -> Int8,
+        guild_id -> Int8,
+        offense_type -> Varchar,
+        count -> Int4,
+        last_offense_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    permission_grants (id) {
+        id -> Int4,
+        guild_id -> Int8,
+        capability -> Varchar,
+        grantee_type -> Varchar,
+        grantee_id -> Int8,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     reversal_of_fortunes (user_id) {
         user_id -> Int8,
@@ -117,14 +286,31 @@ diesel::table! {
     }
 }
 
+diesel::joinable!(conversation_messages -> conversations (conversation_id));
+diesel::joinable!(cull_run_members -> cull_runs (run_id));
+
 diesel::allow_tables_to_appear_in_same_query!(
-    ai_slop_usage,
+    conversation_messages,
+    conversations,
+    cull_exemptions,
+    cull_run_members,
+    cull_runs,
+    cull_scan_checkpoints,
+    cull_schedules,
+    cull_warnings,
     features,
-    goku_poll_usage,
+    guild_settings,
+    gulag_appeals,
     gulag_users,
     gulag_votes,
     is_this_real_usage,
+    message_counts,
+    message_hour_counts,
     message_votes,
+    moderation_events,
+    offense_policies,
+    offenses,
+    permission_grants,
     reversal_of_fortunes,
     servers,
     user_activity,
@@ -1,25 +1,43 @@
 use crate::db::{models, schema::features::dsl::*, DbPool};
 use anyhow::{Context, Result};
 use diesel::prelude::*;
+use std::collections::BTreeMap;
+
+/// `guild_id` value used for the global default row of a feature.
+/// A row for a specific guild overrides the default for that guild only.
+pub const GLOBAL_GUILD_ID: i64 = 0;
+
 pub struct Features;
 
 impl Features {
-    pub fn all(pool: &DbPool) -> Result<Vec<models::Features>> {
+    /// List every feature with the value that applies to `guild`.
+    /// Guild overrides replace the global default of the same name; with
+    /// `None` only the global defaults are returned.
+    pub fn all(pool: &DbPool, guild: Option<u64>) -> Result<Vec<models::Features>> {
+        let guild = Self::guild_key(guild)?;
         let mut conn = pool
             .get()
             .with_context(|| "Failed to get database connection from pool")?;
-        features
+        let rows = features
+            .filter(guild_id.eq_any([GLOBAL_GUILD_ID, guild]))
             .load(&mut conn)
-            .with_context(|| "Failed to get features")
+            .with_context(|| "Failed to get features")?;
+        Ok(Self::effective(rows))
     }
+
     /// Check if a feature is enabled, returning an error if the database is unreachable.
     /// Use this for user-facing commands where you want to report the actual error.
-    pub fn check_enabled(pool: &DbPool, feature_name: &str) -> Result<bool> {
+    pub fn check_enabled(pool: &DbPool, guild: Option<u64>, feature_name: &str) -> Result<bool> {
+        let guild = Self::guild_key(guild)?;
         let mut conn = pool
             .get()
             .with_context(|| "Failed to get database connection from pool")?;
+        // Discord IDs are always > 0, so ordering by guild_id DESC puts the
+        // guild override (if any) ahead of the global default.
         let is_on = features
             .filter(name.eq(feature_name))
+            .filter(guild_id.eq_any([GLOBAL_GUILD_ID, guild]))
+            .order(guild_id.desc())
             .select(enabled)
             .first::<bool>(&mut conn)
             .optional()
@@ -29,39 +47,111 @@ impl Features {
 
     /// Check if a feature is enabled, silently returning false on any error.
     /// Use this for background tasks where you don't want to crash on DB errors.
-    pub fn is_enabled(pool: &DbPool, feature_name: &str) -> bool {
-        let mut conn = match pool.get() {
-            Ok(c) => c,
-            Err(e) => {
-                eprintln!("Failed to get database connection: {}", e);
-                return false;
-            }
-        };
-        features
-            .filter(name.eq(feature_name))
-            .select(enabled)
-            .first::<bool>(&mut conn)
-            .optional()
-            .unwrap_or_else(|e| {
-                eprintln!("Error checking feature '{}': {}", feature_name, e);
-                None
-            })
-            .unwrap_or(false)
+    pub fn is_enabled(pool: &DbPool, guild: Option<u64>, feature_name: &str) -> bool {
+        Self::check_enabled(pool, guild, feature_name).unwrap_or_else(|e| {
+            eprintln!("Error checking feature '{}': {:#}", feature_name, e);
+            false
+        })
     }
 
-    pub fn update(pool: &DbPool, feature_name: &str, enable: bool) -> Result<()> {
+    /// Set a feature for a single guild, creating the override row if needed.
+    /// The feature must already exist as a global default.
+    pub fn update(pool: &DbPool, guild: u64, feature_name: &str, enable: bool) -> Result<()> {
+        let guild = Self::guild_key(Some(guild))?;
         let mut conn = pool
             .get()
             .with_context(|| "Failed to get database connection from pool")?;
-        let rows_affected = diesel::update(features.filter(name.eq(feature_name)))
+
+        let known = features
+            .filter(name.eq(feature_name))
+            .filter(guild_id.eq(GLOBAL_GUILD_ID))
+            .select(id)
+            .first::<i32>(&mut conn)
+            .optional()
+            .with_context(|| format!("Failed to query feature '{}'", feature_name))?;
+        if known.is_none() {
+            anyhow::bail!("Feature '{}' not found in database", feature_name);
+        }
+
+        diesel::insert_into(features)
+            .values((
+                name.eq(feature_name),
+                enabled.eq(enable),
+                guild_id.eq(guild),
+            ))
+            .on_conflict((name, guild_id))
+            .do_update()
             .set(enabled.eq(enable))
             .execute(&mut conn)
             .with_context(|| format!("Error updating feature '{}'", feature_name))?;
 
-        if rows_affected == 0 {
-            anyhow::bail!("Feature '{}' not found in database", feature_name);
+        Ok(())
+    }
+
+    fn guild_key(guild: Option<u64>) -> Result<i64> {
+        match guild {
+            Some(g) => i64::try_from(g).with_context(|| format!("Guild ID {} exceeds i64::MAX", g)),
+            None => Ok(GLOBAL_GUILD_ID),
         }
+    }
 
-        Ok(())
+    /// Collapse global and guild rows into one row per feature name, preferring
+    /// the guild override. Output is sorted by name.
+    fn effective(rows: Vec<models::Features>) -> Vec<models::Features> {
+        let mut by_name: BTreeMap<String, models::Features> = BTreeMap::new();
+        for row in rows {
+            match by_name.get(&row.name) {
+                Some(existing) if existing.guild_id != GLOBAL_GUILD_ID => {}
+                _ => {
+                    by_name.insert(row.name.clone(), row);
+                }
+            }
+        }
+        by_name.into_values().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(feature: &str, on: bool, guild: i64) -> models::Features {
+        models::Features {
+            id: 0,
+            name: feature.to_string(),
+            enabled: on,
+            guild_id: guild,
+        }
+    }
+
+    #[test]
+    fn effective_prefers_guild_override() {
+        let rows = vec![
+            row("twitter", true, GLOBAL_GUILD_ID),
+            row("twitter", false, 42),
+            row("gulag", true, GLOBAL_GUILD_ID),
+        ];
+        let result = Features::effective(rows);
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].name, "gulag");
+        assert!(result[0].enabled);
+        assert_eq!(result[1].name, "twitter");
+        assert!(!result[1].enabled);
+        assert_eq!(result[1].guild_id, 42);
+    }
+
+    #[test]
+    fn effective_override_before_default_is_kept() {
+        let rows = vec![row("cull", true, 42), row("cull", false, GLOBAL_GUILD_ID)];
+        let result = Features::effective(rows);
+        assert_eq!(result.len(), 1);
+        assert!(result[0].enabled);
+    }
+
+    #[test]
+    fn guild_key_defaults_to_global() {
+        assert_eq!(Features::guild_key(None).unwrap(), GLOBAL_GUILD_ID);
+        assert_eq!(Features::guild_key(Some(7)).unwrap(), 7);
+        assert!(Features::guild_key(Some(u64::MAX)).is_err());
     }
 }
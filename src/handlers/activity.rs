@@ -0,0 +1,274 @@
+//! `/activity`: what the `user_activity` table knows about a guild. Only
+//! members with recorded activity are counted, and former members stay in
+//! the table, so the numbers are a view of the data `/cull` works from
+//! rather than of the current member list.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serenity::{
+    all::{CommandDataOptionValue, CommandInteraction, CommandOptionType},
+    builder::{CreateCommand, CreateCommandOption},
+    client::Context,
+};
+
+use crate::db::{
+    models::UserActivity, query_active_user_counts, query_least_recently_active_users,
+    query_tracked_user_count, query_user_activity_for_ids, DbPool,
+};
+
+use super::{get_pool, subcommand_option, HandlerResponse};
+
+/// Inactivity histogram bucket edges, in days.
+const BUCKET_EDGES_DAYS: [u64; 4] = [7, 30, 90, 180];
+/// Members listed by `/activity silent` unless `count` is given.
+const DEFAULT_SILENT_COUNT: i64 = 10;
+
+const DAY: u64 = 86_400;
+
+pub struct ActivityHandler;
+
+impl ActivityHandler {
+    pub fn setup_command() -> CreateCommand {
+        CreateCommand::new("activity")
+            .description("Show member activity statistics")
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "overview",
+                "Active member counts and an inactivity histogram",
+            ))
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "silent",
+                    "Members who have been silent the longest",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "count",
+                        "How many members to list (default: 10)",
+                    )
+                    .required(false)
+                    .min_int_value(1)
+                    .max_int_value(25),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "member",
+                    "When a member was last active",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::User, "user", "The member")
+                        .required(true),
+                ),
+            )
+    }
+
+    pub async fn setup_interaction(ctx: &Context, command: &CommandInteraction) -> HandlerResponse {
+        let guild_id = match command.guild_id {
+            Some(id) => id.get() as i64,
+            None => return HandlerResponse::ephemeral("This command can only be used in a server"),
+        };
+        let pool = get_pool(ctx).await;
+
+        let subcommand = match command.data.options.first() {
+            Some(sub) => sub,
+            None => return HandlerResponse::ephemeral("Expected a subcommand"),
+        };
+        let now = SystemTime::now();
+
+        let result = match subcommand.name.as_str() {
+            "overview" => Self::overview(&pool, guild_id, now),
+            "silent" => {
+                let count = subcommand_option(subcommand, "count")
+                    .and_then(|value| match value {
+                        CommandDataOptionValue::Integer(n) => Some(*n),
+                        _ => None,
+                    })
+                    .unwrap_or(DEFAULT_SILENT_COUNT);
+                query_least_recently_active_users(&pool, guild_id, count)
+                    .map(|rows| Self::format_silent(&rows, now))
+            }
+            "member" => {
+                let Some(CommandDataOptionValue::User(user_id)) =
+                    subcommand_option(subcommand, "user")
+                else {
+                    return HandlerResponse::ephemeral("Missing required option `user`");
+                };
+                query_user_activity_for_ids(&pool, guild_id, vec![user_id.get() as i64])
+                    .map(|rows| Self::format_member(user_id.get(), rows.first(), now))
+            }
+            other => return HandlerResponse::ephemeral(format!("Unknown subcommand `{}`", other)),
+        };
+
+        match result {
+            Ok(content) => HandlerResponse::ephemeral(content),
+            Err(e) => {
+                eprintln!("[activity] Failed to query user activity: {}", e);
+                HandlerResponse::ephemeral("Failed to load activity data. Please try again later.")
+            }
+        }
+    }
+
+    fn overview(
+        pool: &DbPool,
+        guild_id: i64,
+        now: SystemTime,
+    ) -> Result<String, diesel::result::Error> {
+        let cutoffs: Vec<SystemTime> = BUCKET_EDGES_DAYS
+            .iter()
+            .map(|days| now - Duration::from_secs(days * DAY))
+            .collect();
+        let active = query_active_user_counts(pool, guild_id, &cutoffs)?;
+        let total = query_tracked_user_count(pool, guild_id)?;
+        Ok(Self::format_overview(total, &active))
+    }
+
+    /// `active[i]` is the number of members active within
+    /// `BUCKET_EDGES_DAYS[i]` days, so each bucket is the difference between
+    /// neighbouring counts.
+    fn format_overview(total: i64, active: &[i64]) -> String {
+        if total == 0 {
+            return "No activity has been recorded on this server yet.".to_string();
+        }
+
+        let mut content = format!(
+            "**Member activity** ({} members with recorded activity)\n",
+            total
+        );
+        for (days, count) in BUCKET_EDGES_DAYS.iter().zip(active).take(3) {
+            content.push_str(&format!(
+                "Active in the last {} days: {} ({}%)\n",
+                days,
+                count,
+                count * 100 / total
+            ));
+        }
+
+        content.push_str("\n**Last active**\n```\n");
+        let mut buckets = Vec::new();
+        let (mut lower, mut previous) = (0, 0);
+        for (&upper, &count) in BUCKET_EDGES_DAYS.iter().zip(active) {
+            buckets.push((format!("{}–{} days", lower, upper), count - previous));
+            (lower, previous) = (upper, count);
+        }
+        buckets.push((format!("{}+ days", lower), total - previous));
+        for (label, count) in buckets {
+            content.push_str(&format!(
+                "{:<12} {:<20} {}\n",
+                label,
+                Self::bar(count, total, 20),
+                count
+            ));
+        }
+        content.push_str("```");
+        content
+    }
+
+    /// A bar of up to `width` blocks for `count` out of `total`.
+    fn bar(count: i64, total: i64, width: i64) -> String {
+        let filled = if total > 0 { count * width / total } else { 0 };
+        // Make non-empty buckets visible
+        let filled = if count > 0 { filled.max(1) } else { 0 };
+        "█".repeat(filled as usize)
+    }
+
+    fn format_silent(rows: &[UserActivity], now: SystemTime) -> String {
+        if rows.is_empty() {
+            return "No activity has been recorded on this server yet.".to_string();
+        }
+        let mut content = "**Longest-silent members**".to_string();
+        for row in rows {
+            content.push_str(&format!(
+                "\n<@{}> — last active {} ({} days ago)",
+                row.user_id,
+                Self::discord_date(row.last_message_at),
+                Self::days_since(row.last_message_at, now)
+            ));
+        }
+        content
+    }
+
+    fn format_member(user_id: u64, row: Option<&UserActivity>, now: SystemTime) -> String {
+        match row {
+            Some(row) => format!(
+                "<@{}> was last active {} ({} days ago).",
+                user_id,
+                Self::discord_date(row.last_message_at),
+                Self::days_since(row.last_message_at, now)
+            ),
+            None => format!("No activity has been recorded for <@{}>.", user_id),
+        }
+    }
+
+    fn discord_date(ts: SystemTime) -> String {
+        ts.duration_since(UNIX_EPOCH)
+            .map(|d| format!("<t:{}:D>", d.as_secs()))
+            .unwrap_or_else(|_| "at an unknown time".to_string())
+    }
+
+    fn days_since(ts: SystemTime, now: SystemTime) -> u64 {
+        now.duration_since(ts)
+            .map(|d| d.as_secs() / DAY)
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_overview_buckets_cumulative_counts() {
+        // 40 tracked: 10 within 7 days, 25 within 30, 30 within 90, 34 within 180
+        let content = ActivityHandler::format_overview(40, &[10, 25, 30, 34]);
+        assert!(content.contains("(40 members with recorded activity)"));
+        assert!(content.contains("Active in the last 7 days: 10 (25%)\n"));
+        assert!(content.contains("Active in the last 90 days: 30 (75%)\n"));
+        assert!(!content.contains("last 180 days"));
+        let histogram: Vec<&str> = content
+            .lines()
+            .skip_while(|l| !l.starts_with("```"))
+            .skip(1)
+            .take_while(|l| !l.starts_with("```"))
+            .collect();
+        assert_eq!(histogram.len(), 5);
+        assert!(histogram[0].starts_with("0–7 days") && histogram[0].ends_with(" 10"));
+        assert!(histogram[1].ends_with(" 15"));
+        assert!(histogram[3].starts_with("90–180 days") && histogram[3].ends_with(" 4"));
+        assert!(histogram[4].starts_with("180+ days") && histogram[4].ends_with(" 6"));
+
+        assert_eq!(
+            ActivityHandler::format_overview(0, &[0, 0, 0, 0]),
+            "No activity has been recorded on this server yet."
+        );
+    }
+
+    #[test]
+    fn bar_scales_and_shows_small_buckets() {
+        assert_eq!(ActivityHandler::bar(10, 20, 20), "█".repeat(10));
+        assert_eq!(ActivityHandler::bar(1, 1000, 20), "█");
+        assert_eq!(ActivityHandler::bar(0, 1000, 20), "");
+    }
+
+    #[test]
+    fn format_member_reports_last_seen() {
+        let last = UNIX_EPOCH + Duration::from_secs(19737 * DAY);
+        let row = UserActivity {
+            user_id: 5,
+            guild_id: 1,
+            last_message_at: last,
+            created_at: last,
+        };
+        assert_eq!(
+            ActivityHandler::format_member(5, Some(&row), last + Duration::from_secs(3 * DAY)),
+            "<@5> was last active <t:1705276800:D> (3 days ago)."
+        );
+        assert_eq!(
+            ActivityHandler::format_member(5, None, last),
+            "No activity has been recorded for <@5>."
+        );
+    }
+}
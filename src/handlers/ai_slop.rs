@@ -1,7 +1,8 @@
 use super::{get_pool, HandlerResponse};
-use crate::db::{atomic_increment_ai_slop, get_server_by_guild_id};
+use crate::db::get_server_by_guild_id;
 use crate::features::Features;
-use crate::handlers::gulag::Gulag;
+use crate::handlers::gulag::{Gulag, GulagSource};
+use crate::offenses::{OffenseType, Offenses};
 use serenity::{
     all::{CommandInteraction, CommandType, Mentionable},
     builder::CreateCommand,
@@ -21,7 +22,7 @@ impl AiSlopHandler {
         let pool = get_pool(ctx).await;
 
         // Check feature flag
-        if !Features::is_enabled(&pool, "ai_slop") {
+        if !Features::is_enabled(&pool, command.guild_id.map(|g| g.get()), "ai_slop") {
             return HandlerResponse {
                 content: "This feature is currently disabled.".to_string(),
                 components: None,
@@ -42,30 +43,6 @@ impl AiSlopHandler {
             }
         };
 
-        // Check permissions: require Highly Regarded or admin role
-        let member = match ctx.http.get_member(guild_id.into(), command.user.id).await {
-            Ok(m) => m,
-            Err(_) => {
-                return HandlerResponse {
-                    content: "Error: Could not verify your permissions".to_string(),
-                    components: None,
-                    ephemeral: true,
-                    defer_response: None,
-                };
-            }
-        };
-
-        let allowed_roles = ["Highly Regarded", "admin"];
-        if !Gulag::member_has_any_role(&ctx.http, guild_id, &member, &allowed_roles).await {
-            return HandlerResponse {
-                content: "Error: You need Highly Regarded or admin role to use this command"
-                    .to_string(),
-                components: None,
-                ephemeral: true,
-                defer_response: None,
-            };
-        }
-
         // Extract target message from command data
         let target_message = match command.data.resolved.messages.values().next() {
             Some(msg) => msg,
@@ -127,11 +104,11 @@ impl AiSlopHandler {
             }
         };
 
-        // Calculate duration for the offense that just occurred (new_count - 1)
-        let new_count =
-            match atomic_increment_ai_slop(&pool, target_user.id.get() as i64, guild_id as i64) {
-                Ok(count) => count,
-                Err(_) => {
+        let outcome =
+            match Offenses::record(&pool, guild_id, target_user.id.get(), OffenseType::AiSlop) {
+                Ok(outcome) => outcome,
+                Err(e) => {
+                    eprintln!("Failed to record AI slop offense: {:#}", e);
                     return HandlerResponse {
                         content: "Error: Failed to record AI slop usage".to_string(),
                         components: None,
@@ -141,18 +118,6 @@ impl AiSlopHandler {
                 }
             };
 
-        let duration_seconds = match new_count.saturating_sub(1).try_into() {
-            Ok(u32_count) => Gulag::get_gulag_duration_for_offense(u32_count),
-            Err(_) => {
-                return HandlerResponse {
-                    content: "Error: Usage count too high for gulag calculation".to_string(),
-                    components: None,
-                    ephemeral: true,
-                    defer_response: None,
-                }
-            }
-        };
-
         // Send to gulag with calculated duration
         if let Err(e) = Gulag::add_to_gulag(
             &ctx.http,
@@ -161,14 +126,28 @@ impl AiSlopHandler {
                 guildid: guild_id,
                 userid: target_user.id.get(),
                 gulag_roleid: server.gulag_id as u64,
-                gulaglength: duration_seconds.try_into().unwrap_or(2_592_000u32), // Max ~30 days cap
+                gulaglength: outcome.duration_seconds.try_into().unwrap_or(u32::MAX),
                 channelid: command.channel_id.get(),
                 messageid: target_message.id.get(),
+                source: GulagSource::AiSlop,
+                moderator_id: Some(command.user.id.get()),
+                reason: Some(format!(
+                    "Posted AI slop {} (offense #{})",
+                    target_message.link(),
+                    outcome.count
+                )),
             },
         )
         .await
         {
             eprintln!("Failed to send user to gulag: {}", e);
+            Gulag::retract_offense(
+                &pool,
+                guild_id,
+                target_user.id.get(),
+                OffenseType::AiSlop,
+                &outcome,
+            );
             return HandlerResponse {
                 content: format!("Error: Failed to send to gulag: {}", e),
                 components: None,
@@ -177,16 +156,15 @@ impl AiSlopHandler {
             };
         }
 
-        // Post notification to #the-gulag channel
-        if let Ok(Some(gulag_channel)) =
-            Gulag::find_channel(&ctx.http, guild_id, "the-gulag".to_string()).await
+        // Post notification to the gulag channel
+        if let Ok(Some(gulag_channel)) = Gulag::find_gulag_channel(&ctx.http, &pool, guild_id).await
         {
             let channel_message = format!(
                 "{} has been sent to the gulag for {} for posting AI slop: {}\nThis is offense #{}",
                 target_user.mention(),
-                Gulag::format_duration(duration_seconds),
+                Gulag::format_duration(outcome.duration_seconds),
                 target_message.link(),
-                new_count
+                outcome.count
             );
 
             let _ = gulag_channel.say(&ctx.http, channel_message).await;
@@ -196,16 +174,13 @@ impl AiSlopHandler {
             content: format!(
                 "Sent {} to the gulag for {} for posting AI slop!\nThis is their offense #{} (next offense will be {})",
                 target_user.name,
-                Gulag::format_duration(duration_seconds),
-                new_count,
-                match new_count.try_into() {
-                    Ok(u32_count) => Gulag::format_duration(Gulag::get_gulag_duration_for_offense(u32_count)),
-                    Err(_) => Gulag::format_duration(2_592_000), // Max ~30 days for overflow protection
-                }
+                Gulag::format_duration(outcome.duration_seconds),
+                outcome.count,
+                Gulag::format_duration(outcome.next_duration_seconds),
             ),
             components: None,
             ephemeral: true,
-                    defer_response: None,
+            defer_response: None,
         }
     }
 }
@@ -0,0 +1,127 @@
+use serenity::{
+    all::{CommandDataOptionValue, CommandInteraction, CommandOptionType},
+    builder::{CreateCommand, CreateCommandOption},
+    client::Context,
+};
+
+use crate::offenses::{Amnesty, OffenseType, Offenses};
+
+use super::{get_pool, HandlerResponse};
+
+pub struct AmnestyHandler;
+
+impl AmnestyHandler {
+    pub fn setup_command() -> CreateCommand {
+        let mut offense = CreateCommandOption::new(
+            CommandOptionType::String,
+            "type",
+            "Which offense to forgive (default: all)",
+        )
+        .required(false);
+        for offense_type in OffenseType::ALL {
+            offense = offense.add_string_choice(offense_type.label(), offense_type.as_str());
+        }
+
+        CreateCommand::new("amnesty")
+            .description("Forgive a member's past offenses so escalation starts over")
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::User, "user", "The member to forgive")
+                    .required(true),
+            )
+            .add_option(offense)
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "reduce-by",
+                    "Forgive this many offenses instead of all of them",
+                )
+                .required(false)
+                .min_int_value(1),
+            )
+    }
+
+    pub async fn setup_interaction(ctx: &Context, command: &CommandInteraction) -> HandlerResponse {
+        let guild_id = match command.guild_id {
+            Some(id) => id.get(),
+            None => return HandlerResponse::ephemeral("This command can only be used in a server"),
+        };
+
+        let mut user = None;
+        let mut offense = None;
+        let mut reduce_by = None;
+        for option in &command.data.options {
+            match (option.name.as_str(), &option.value) {
+                ("user", CommandDataOptionValue::User(u)) => user = Some(u.get()),
+                ("type", CommandDataOptionValue::String(s)) => match OffenseType::parse(s) {
+                    Some(t) => offense = Some(t),
+                    None => {
+                        return HandlerResponse::ephemeral(format!("Unknown offense type `{}`", s))
+                    }
+                },
+                ("reduce-by", CommandDataOptionValue::Integer(n)) => {
+                    reduce_by = Some(i32::try_from(*n).unwrap_or(i32::MAX))
+                }
+                _ => {}
+            }
+        }
+        let Some(user) = user else {
+            return HandlerResponse::ephemeral("Please provide a valid user");
+        };
+
+        let pool = get_pool(ctx).await;
+        match Offenses::amnesty(&pool, guild_id, user, offense, reduce_by) {
+            Ok(changes) => HandlerResponse::ephemeral(Self::format_result(user, &changes)),
+            Err(e) => {
+                eprintln!("[amnesty] {:#}", e);
+                HandlerResponse::ephemeral("Failed to update offenses. Please try again later.")
+            }
+        }
+    }
+
+    fn format_result(user: u64, changes: &[Amnesty]) -> String {
+        let changes: Vec<&Amnesty> = changes.iter().filter(|c| c.before > 0).collect();
+        if changes.is_empty() {
+            return format!("<@{}> has no offenses to forgive.", user);
+        }
+
+        let mut content = format!("Amnesty granted to <@{}>:", user);
+        for change in changes {
+            content.push_str(&format!(
+                "\n- {}: {} → {}",
+                change.offense.label(),
+                change.before,
+                change.after
+            ));
+        }
+        content
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_changes_and_skips_decayed_types() {
+        let changes = [
+            Amnesty {
+                offense: OffenseType::AiSlop,
+                before: 4,
+                after: 2,
+            },
+            Amnesty {
+                offense: OffenseType::Elon,
+                before: 0,
+                after: 0,
+            },
+        ];
+        assert_eq!(
+            AmnestyHandler::format_result(7, &changes),
+            "Amnesty granted to <@7>:\n- AI Slop: 4 → 2"
+        );
+        assert_eq!(
+            AmnestyHandler::format_result(7, &changes[1..]),
+            "<@7> has no offenses to forgive."
+        );
+    }
+}
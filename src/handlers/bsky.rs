@@ -9,7 +9,7 @@ pub struct Bsky;
 impl Bsky {
     pub async fn handler(ctx: &Context, msg: &Message) {
         let pool = get_pool(ctx).await;
-        if Features::is_enabled(&pool, "bsky") {
+        if Features::is_enabled(&pool, msg.guild_id.map(|g| g.get()), "bsky") {
             match Self::fx_rewriter(&msg.content.to_owned()) {
                 None => (),
                 Some(fixed_message) => {
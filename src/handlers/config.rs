@@ -0,0 +1,688 @@
+use serenity::{
+    all::{
+        ChannelType, CommandDataOption, CommandDataOptionValue, CommandInteraction,
+        CommandOptionType,
+    },
+    builder::{CreateCommand, CreateCommandOption},
+    client::Context,
+};
+
+use crate::db::{get_cull_schedule, models::CullSchedule, upsert_cull_schedule};
+use crate::handlers::cull::schedule::{describe_schedule, new_schedule};
+use crate::handlers::gulag::Gulag;
+use crate::offenses::{OffenseType, Offenses, Policy};
+use crate::tugbot::guild_config::GuildConfig;
+use std::time::SystemTime;
+
+use super::{get_pool, HandlerResponse};
+
+pub struct ConfigHandler;
+
+/// A single modification requested through `/config`.
+#[derive(Debug, PartialEq)]
+enum ConfigChange {
+    SetChannel(String, u64),
+    Clear(String),
+    AddRole(String, u64),
+    RemoveRole(String, u64),
+    SetCullGrace(u32),
+}
+
+impl ConfigHandler {
+    pub fn setup_command() -> CreateCommand {
+        let channel_setting = || {
+            CreateCommandOption::new(CommandOptionType::String, "setting", "Which channel to set")
+                .required(true)
+                .add_string_choice("Ask channel", "ask-channel")
+                .add_string_choice("Moderation log channel", "mod-log-channel")
+                .add_string_choice("Gulag channel", "gulag-channel")
+        };
+        let role_list = || {
+            CreateCommandOption::new(CommandOptionType::String, "list", "Which role list")
+                .required(true)
+                .add_string_choice("Moderator roles", "moderator-roles")
+                .add_string_choice("Cull whitelist roles", "cull-whitelist-roles")
+        };
+        let role =
+            || CreateCommandOption::new(CommandOptionType::Role, "role", "The role").required(true);
+
+        CreateCommand::new("config")
+            .description("View or change this server's bot configuration")
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "view",
+                "Show the current configuration",
+            ))
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::SubCommand, "set", "Set a channel")
+                    .add_sub_option(channel_setting())
+                    .add_sub_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::Channel,
+                            "channel",
+                            "The channel to use",
+                        )
+                        .required(true)
+                        .channel_types(vec![ChannelType::Text]),
+                    ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "clear",
+                    "Reset a setting to the default",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "setting",
+                        "Which setting to reset",
+                    )
+                    .required(true)
+                    .add_string_choice("Ask channel", "ask-channel")
+                    .add_string_choice("Moderation log channel", "mod-log-channel")
+                    .add_string_choice("Gulag channel", "gulag-channel")
+                    .add_string_choice("Moderator roles", "moderator-roles")
+                    .add_string_choice("Cull whitelist roles", "cull-whitelist-roles")
+                    .add_string_choice("Cull grace period", "cull-grace-days"),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "add-role",
+                    "Add a role to a role list",
+                )
+                .add_sub_option(role_list())
+                .add_sub_option(role()),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "remove-role",
+                    "Remove a role from a role list",
+                )
+                .add_sub_option(role_list())
+                .add_sub_option(role()),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "cull-grace",
+                    "Protect new members from culls",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "days",
+                        "Members who joined less than this many days ago are never culled (0 = off)",
+                    )
+                    .required(true)
+                    .min_int_value(0)
+                    .max_int_value(365),
+                ),
+            )
+            .add_option(Self::escalation_command())
+            .add_option(Self::cull_schedule_command())
+    }
+
+    fn escalation_command() -> CreateCommandOption {
+        let mut offense =
+            CreateCommandOption::new(CommandOptionType::String, "offense", "Which offense")
+                .required(true);
+        for offense_type in OffenseType::ALL {
+            offense = offense.add_string_choice(offense_type.label(), offense_type.as_str());
+        }
+        let number = |kind, name: &str, description: &str| {
+            CreateCommandOption::new(kind, name, description).required(false)
+        };
+
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "escalation",
+            "View or change how an offense escalates",
+        )
+        .add_sub_option(offense)
+        .add_sub_option(
+            number(
+                CommandOptionType::Integer,
+                "base-minutes",
+                "Sentence for a first offense",
+            )
+            .min_int_value(1),
+        )
+        .add_sub_option(
+            number(
+                CommandOptionType::Number,
+                "multiplier",
+                "Factor applied per repeat offense",
+            )
+            .min_number_value(1.0),
+        )
+        .add_sub_option(
+            number(
+                CommandOptionType::Integer,
+                "cap-minutes",
+                "Longest possible sentence",
+            )
+            .min_int_value(1),
+        )
+        .add_sub_option(
+            number(
+                CommandOptionType::Integer,
+                "decay-days",
+                "Days without an offense before the count halves (0 = never)",
+            )
+            .min_int_value(0),
+        )
+    }
+
+    fn cull_schedule_command() -> CreateCommandOption {
+        let option = |kind, name: &str, description: &str| {
+            CreateCommandOption::new(kind, name, description).required(false)
+        };
+
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "cull-schedule",
+            "View or change the automatic cull schedule",
+        )
+        .add_sub_option(option(
+            CommandOptionType::Boolean,
+            "enabled",
+            "Run scheduled culls",
+        ))
+        .add_sub_option(
+            option(
+                CommandOptionType::Integer,
+                "inactive-days",
+                "Days without posting before a member is warned",
+            )
+            .min_int_value(1)
+            .max_int_value(365),
+        )
+        .add_sub_option(
+            option(
+                CommandOptionType::Integer,
+                "interval-days",
+                "Days between warning rounds",
+            )
+            .min_int_value(1)
+            .max_int_value(365),
+        )
+        .add_sub_option(
+            option(
+                CommandOptionType::Integer,
+                "warning-days",
+                "Days between the warning and the kick",
+            )
+            .min_int_value(1)
+            .max_int_value(60),
+        )
+        .add_sub_option(option(
+            CommandOptionType::Boolean,
+            "include-never-posted",
+            "Also warn members who have never posted",
+        ))
+        .add_sub_option(
+            option(
+                CommandOptionType::Channel,
+                "warning-channel",
+                "Mention members here instead of DMing them",
+            )
+            .channel_types(vec![ChannelType::Text]),
+        )
+        .add_sub_option(option(
+            CommandOptionType::Boolean,
+            "dm-warnings",
+            "Go back to DMing warnings instead of using the warning channel",
+        ))
+    }
+
+    pub async fn setup_interaction(ctx: &Context, command: &CommandInteraction) -> HandlerResponse {
+        let guild_id = match command.guild_id {
+            Some(id) => id.get(),
+            None => return HandlerResponse::ephemeral("This command can only be used in a server"),
+        };
+
+        let pool = get_pool(ctx).await;
+        let mut config = match GuildConfig::try_load(&pool, guild_id) {
+            Ok(c) => c,
+            Err(e) => {
+                eprintln!("[config] {:#}", e);
+                return HandlerResponse::ephemeral(
+                    "Failed to load configuration. Please try again later.",
+                );
+            }
+        };
+
+        let Some(subcommand) = command.data.options.first() else {
+            return HandlerResponse::ephemeral(config.describe());
+        };
+        if subcommand.name == "escalation" {
+            return Self::escalation(&pool, guild_id, subcommand);
+        }
+        if subcommand.name == "cull-schedule" {
+            return Self::cull_schedule(&pool, guild_id, subcommand);
+        }
+        let change = match Self::parse_change(subcommand) {
+            Ok(Some(change)) => change,
+            Ok(None) => return HandlerResponse::ephemeral(config.describe()),
+            Err(e) => return HandlerResponse::ephemeral(e),
+        };
+
+        if let Err(e) = Self::apply_change(&mut config, &change) {
+            return HandlerResponse::ephemeral(e);
+        }
+        if let Err(e) = config.save(&pool) {
+            eprintln!("[config] {:#}", e);
+            return HandlerResponse::ephemeral(
+                "Failed to save configuration. Please try again later.",
+            );
+        }
+
+        HandlerResponse::ephemeral(format!("Configuration updated.\n\n{}", config.describe()))
+    }
+
+    /// Turn a subcommand into a change. `Ok(None)` means "just view".
+    fn parse_change(subcommand: &CommandDataOption) -> Result<Option<ConfigChange>, String> {
+        let options = match &subcommand.value {
+            CommandDataOptionValue::SubCommand(options) => options,
+            _ => return Err("Invalid subcommand".to_string()),
+        };
+
+        let mut setting = None;
+        let mut channel = None;
+        let mut role = None;
+        let mut days = None;
+        for option in options {
+            match (option.name.as_str(), &option.value) {
+                ("setting" | "list", CommandDataOptionValue::String(s)) => {
+                    setting = Some(s.clone())
+                }
+                ("channel", CommandDataOptionValue::Channel(c)) => channel = Some(c.get()),
+                ("role", CommandDataOptionValue::Role(r)) => role = Some(r.get()),
+                ("days", CommandDataOptionValue::Integer(d)) => days = u32::try_from(*d).ok(),
+                _ => {}
+            }
+        }
+        let missing = |what: &str| format!("Missing required option `{}`", what);
+
+        match subcommand.name.as_str() {
+            "view" => Ok(None),
+            "set" => Ok(Some(ConfigChange::SetChannel(
+                setting.ok_or_else(|| missing("setting"))?,
+                channel.ok_or_else(|| missing("channel"))?,
+            ))),
+            "clear" => Ok(Some(ConfigChange::Clear(
+                setting.ok_or_else(|| missing("setting"))?,
+            ))),
+            "add-role" => Ok(Some(ConfigChange::AddRole(
+                setting.ok_or_else(|| missing("list"))?,
+                role.ok_or_else(|| missing("role"))?,
+            ))),
+            "remove-role" => Ok(Some(ConfigChange::RemoveRole(
+                setting.ok_or_else(|| missing("list"))?,
+                role.ok_or_else(|| missing("role"))?,
+            ))),
+            "cull-grace" => Ok(Some(ConfigChange::SetCullGrace(
+                days.ok_or_else(|| missing("days"))?,
+            ))),
+            other => Err(format!("Unknown subcommand `{}`", other)),
+        }
+    }
+
+    /// `/config escalation`: show the policy, or override the given fields.
+    fn escalation(
+        pool: &crate::db::DbPool,
+        guild_id: u64,
+        subcommand: &CommandDataOption,
+    ) -> HandlerResponse {
+        let CommandDataOptionValue::SubCommand(options) = &subcommand.value else {
+            return HandlerResponse::ephemeral("Invalid subcommand");
+        };
+        let offense = options
+            .iter()
+            .find_map(|o| match (o.name.as_str(), &o.value) {
+                ("offense", CommandDataOptionValue::String(s)) => OffenseType::parse(s),
+                _ => None,
+            });
+        let Some(offense) = offense else {
+            return HandlerResponse::ephemeral("Missing required option `offense`");
+        };
+
+        let mut policy = match Offenses::policy(pool, guild_id, offense) {
+            Ok(policy) => policy,
+            Err(e) => {
+                eprintln!("[config] {:#}", e);
+                return HandlerResponse::ephemeral(
+                    "Failed to load escalation policy. Please try again later.",
+                );
+            }
+        };
+        let changed = Self::apply_escalation(
+            &mut policy,
+            options.iter().map(|o| (o.name.as_str(), &o.value)),
+        );
+        if changed {
+            if let Err(e) = Offenses::set_policy(pool, guild_id, offense, &policy) {
+                eprintln!("[config] {:#}", e);
+                return HandlerResponse::ephemeral(
+                    "Failed to save escalation policy. Please try again later.",
+                );
+            }
+        }
+
+        HandlerResponse::ephemeral(format!(
+            "{}**{}** escalation: {} first offense, x{} per repeat, capped at {}, {}",
+            if changed {
+                "Escalation updated.\n\n"
+            } else {
+                ""
+            },
+            offense.label(),
+            Gulag::format_duration(policy.base_seconds),
+            policy.multiplier,
+            Gulag::format_duration(policy.cap_seconds),
+            match policy.decay_window {
+                Some(window) => format!(
+                    "count halves every {} days without an offense",
+                    window.as_secs() / 86_400
+                ),
+                None => "count never decays".to_string(),
+            }
+        ))
+    }
+
+    /// Apply the optional escalation fields. Returns whether anything changed.
+    fn apply_escalation<'a>(
+        policy: &mut Policy,
+        options: impl IntoIterator<Item = (&'a str, &'a CommandDataOptionValue)>,
+    ) -> bool {
+        let mut changed = false;
+        for option in options {
+            match option {
+                ("base-minutes", CommandDataOptionValue::Integer(m)) if *m > 0 => {
+                    policy.base_seconds = *m as u64 * 60;
+                }
+                ("multiplier", CommandDataOptionValue::Number(x)) if *x >= 1.0 => {
+                    policy.multiplier = *x;
+                }
+                ("cap-minutes", CommandDataOptionValue::Integer(m)) if *m > 0 => {
+                    policy.cap_seconds = *m as u64 * 60;
+                }
+                ("decay-days", CommandDataOptionValue::Integer(d)) if *d >= 0 => {
+                    policy.decay_window =
+                        (*d > 0).then(|| std::time::Duration::from_secs(*d as u64 * 86_400));
+                }
+                _ => continue,
+            }
+            changed = true;
+        }
+        changed
+    }
+
+    /// `/config cull-schedule`: show the schedule, or change the given fields.
+    fn cull_schedule(
+        pool: &crate::db::DbPool,
+        guild_id: u64,
+        subcommand: &CommandDataOption,
+    ) -> HandlerResponse {
+        let CommandDataOptionValue::SubCommand(options) = &subcommand.value else {
+            return HandlerResponse::ephemeral("Invalid subcommand");
+        };
+
+        let now = SystemTime::now();
+        let mut schedule = match get_cull_schedule(pool, guild_id as i64) {
+            Ok(Some(schedule)) => schedule,
+            Ok(None) => new_schedule(guild_id, now),
+            Err(e) => {
+                eprintln!("[config] {:#}", e);
+                return HandlerResponse::ephemeral(
+                    "Failed to load cull schedule. Please try again later.",
+                );
+            }
+        };
+        let changed = match Self::apply_cull_schedule(
+            &mut schedule,
+            options.iter().map(|o| (o.name.as_str(), &o.value)),
+            now,
+        ) {
+            Ok(changed) => changed,
+            Err(e) => return HandlerResponse::ephemeral(e),
+        };
+        if changed {
+            if let Err(e) = upsert_cull_schedule(pool, &schedule) {
+                eprintln!("[config] {:#}", e);
+                return HandlerResponse::ephemeral(
+                    "Failed to save cull schedule. Please try again later.",
+                );
+            }
+        }
+
+        HandlerResponse::ephemeral(format!(
+            "{}{}",
+            if changed {
+                "Cull schedule updated.\n\n"
+            } else {
+                ""
+            },
+            describe_schedule(&schedule)
+        ))
+    }
+
+    /// Apply the optional cull schedule fields. Returns whether anything
+    /// changed, or an error if the result would be inconsistent.
+    fn apply_cull_schedule<'a>(
+        schedule: &mut CullSchedule,
+        options: impl IntoIterator<Item = (&'a str, &'a CommandDataOptionValue)>,
+        now: SystemTime,
+    ) -> Result<bool, String> {
+        let was_enabled = schedule.enabled;
+        let mut changed = false;
+        for option in options {
+            match option {
+                ("enabled", CommandDataOptionValue::Boolean(b)) => schedule.enabled = *b,
+                ("inactive-days", CommandDataOptionValue::Integer(d)) if *d > 0 => {
+                    schedule.inactive_days = *d as i32;
+                }
+                ("interval-days", CommandDataOptionValue::Integer(d)) if *d > 0 => {
+                    schedule.interval_days = *d as i32;
+                }
+                ("warning-days", CommandDataOptionValue::Integer(d)) if *d > 0 => {
+                    schedule.warning_days = *d as i32;
+                }
+                ("include-never-posted", CommandDataOptionValue::Boolean(b)) => {
+                    schedule.include_never_posted = *b;
+                }
+                ("warning-channel", CommandDataOptionValue::Channel(c)) => {
+                    schedule.warning_channel_id = Some(c.get() as i64);
+                }
+                ("dm-warnings", CommandDataOptionValue::Boolean(true)) => {
+                    schedule.warning_channel_id = None;
+                }
+                _ => continue,
+            }
+            changed = true;
+        }
+        if schedule.warning_days >= schedule.interval_days {
+            return Err(format!(
+                "The warning period ({} days) must be shorter than the interval ({} days)",
+                schedule.warning_days, schedule.interval_days
+            ));
+        }
+
+        if schedule.enabled && !was_enabled {
+            // First round goes out at the next check
+            schedule.next_run_at = now;
+        }
+        if !schedule.enabled {
+            // Drop any open round; its warnings are replaced when one next opens
+            schedule.deadline_at = None;
+        }
+        schedule.updated_at = now;
+        Ok(changed)
+    }
+
+    fn apply_change(config: &mut GuildConfig, change: &ConfigChange) -> Result<(), String> {
+        match change {
+            ConfigChange::SetChannel(setting, id) => {
+                *Self::channel_slot(config, setting)? = Some(*id);
+            }
+            ConfigChange::Clear(setting) => match setting.as_str() {
+                "moderator-roles" | "cull-whitelist-roles" => {
+                    Self::role_slot(config, setting)?.clear();
+                }
+                "cull-grace-days" => config.cull_grace_days = None,
+                _ => *Self::channel_slot(config, setting)? = None,
+            },
+            ConfigChange::AddRole(list, id) => {
+                let roles = Self::role_slot(config, list)?;
+                if !roles.contains(id) {
+                    roles.push(*id);
+                }
+            }
+            ConfigChange::RemoveRole(list, id) => {
+                let roles = Self::role_slot(config, list)?;
+                if !roles.contains(id) {
+                    return Err(format!("<@&{}> is not in that list", id));
+                }
+                roles.retain(|r| r != id);
+            }
+            ConfigChange::SetCullGrace(days) => config.cull_grace_days = Some(*days),
+        }
+        Ok(())
+    }
+
+    fn channel_slot<'a>(
+        config: &'a mut GuildConfig,
+        setting: &str,
+    ) -> Result<&'a mut Option<u64>, String> {
+        match setting {
+            "ask-channel" => Ok(&mut config.ask_channel_id),
+            "mod-log-channel" => Ok(&mut config.mod_log_channel_id),
+            "gulag-channel" => Ok(&mut config.gulag_channel_id),
+            other => Err(format!("Unknown setting `{}`", other)),
+        }
+    }
+
+    fn role_slot<'a>(config: &'a mut GuildConfig, list: &str) -> Result<&'a mut Vec<u64>, String> {
+        match list {
+            "moderator-roles" => Ok(&mut config.moderator_role_ids),
+            "cull-whitelist-roles" => Ok(&mut config.cull_whitelist_role_ids),
+            other => Err(format!("Unknown role list `{}`", other)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_and_clear_channel() {
+        let mut config = GuildConfig::default();
+        ConfigHandler::apply_change(
+            &mut config,
+            &ConfigChange::SetChannel("gulag-channel".to_string(), 99),
+        )
+        .unwrap();
+        assert_eq!(config.gulag_channel_id, Some(99));
+
+        ConfigHandler::apply_change(
+            &mut config,
+            &ConfigChange::Clear("gulag-channel".to_string()),
+        )
+        .unwrap();
+        assert_eq!(config.gulag_channel_id, None);
+    }
+
+    #[test]
+    fn add_role_is_idempotent_and_remove_requires_presence() {
+        let mut config = GuildConfig::default();
+        let add = ConfigChange::AddRole("moderator-roles".to_string(), 5);
+        ConfigHandler::apply_change(&mut config, &add).unwrap();
+        ConfigHandler::apply_change(&mut config, &add).unwrap();
+        assert_eq!(config.moderator_role_ids, vec![5]);
+
+        let remove = ConfigChange::RemoveRole("moderator-roles".to_string(), 5);
+        ConfigHandler::apply_change(&mut config, &remove).unwrap();
+        assert!(config.moderator_role_ids.is_empty());
+        assert!(ConfigHandler::apply_change(&mut config, &remove).is_err());
+    }
+
+    #[test]
+    fn escalation_overrides_only_given_fields() {
+        let mut policy = Policy {
+            base_seconds: 300,
+            multiplier: 2.0,
+            cap_seconds: 86_400,
+            decay_window: None,
+        };
+        let options = [
+            (
+                "offense",
+                CommandDataOptionValue::String("elon".to_string()),
+            ),
+            ("base-minutes", CommandDataOptionValue::Integer(10)),
+            ("decay-days", CommandDataOptionValue::Integer(7)),
+        ];
+        let pairs = || options.iter().map(|(name, value)| (*name, value));
+        assert!(ConfigHandler::apply_escalation(&mut policy, pairs()));
+        assert_eq!(policy.base_seconds, 600);
+        assert_eq!(policy.multiplier, 2.0);
+        assert_eq!(policy.cap_seconds, 86_400);
+        assert_eq!(
+            policy.decay_window,
+            Some(std::time::Duration::from_secs(7 * 86_400))
+        );
+        assert!(!ConfigHandler::apply_escalation(
+            &mut policy,
+            pairs().take(1)
+        ));
+    }
+
+    #[test]
+    fn cull_schedule_validates_and_resets_round_state() {
+        let then = SystemTime::UNIX_EPOCH;
+        let now = then + std::time::Duration::from_secs(86_400);
+        let mut schedule = new_schedule(1, then);
+
+        let enable = [
+            ("enabled", CommandDataOptionValue::Boolean(true)),
+            ("warning-days", CommandDataOptionValue::Integer(3)),
+        ];
+        let pairs = enable.iter().map(|(name, value)| (*name, value));
+        assert_eq!(
+            ConfigHandler::apply_cull_schedule(&mut schedule, pairs, now),
+            Ok(true)
+        );
+        assert!(schedule.enabled);
+        assert_eq!(schedule.warning_days, 3);
+        assert_eq!(schedule.next_run_at, now);
+
+        let too_long = [("warning-days", CommandDataOptionValue::Integer(30))];
+        let pairs = too_long.iter().map(|(name, value)| (*name, value));
+        assert!(ConfigHandler::apply_cull_schedule(&mut schedule, pairs, now).is_err());
+
+        schedule.warning_days = 3;
+        schedule.deadline_at = Some(now);
+        let disable = [("enabled", CommandDataOptionValue::Boolean(false))];
+        let pairs = disable.iter().map(|(name, value)| (*name, value));
+        assert_eq!(
+            ConfigHandler::apply_cull_schedule(&mut schedule, pairs, now),
+            Ok(true)
+        );
+        assert_eq!(schedule.deadline_at, None);
+    }
+
+    #[test]
+    fn unknown_setting_is_rejected() {
+        let mut config = GuildConfig::default();
+        assert!(ConfigHandler::apply_change(
+            &mut config,
+            &ConfigChange::SetChannel("moderator-roles".to_string(), 1),
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,258 @@
+//! `/cull exempt add|remove|list`: members culls must skip, e.g. while on
+//! leave. Every cull path goes through `find_candidates`, which drops
+//! exempt members, so manual, dry-run and scheduled culls all honour them.
+
+use super::{format_timestamp, post_to_cat_herding, CullHandler};
+use crate::db::{
+    delete_cull_exemption, get_active_cull_exemptions, models::CullExemption,
+    upsert_cull_exemption, DbPool,
+};
+use crate::handlers::HandlerResponse;
+use serenity::{
+    all::{CommandDataOption, CommandDataOptionValue, CommandInteraction, CommandOptionType},
+    builder::CreateCommandOption,
+    client::Context,
+    model::id::ChannelId,
+};
+use std::time::{Duration, SystemTime};
+
+impl CullHandler {
+    pub fn exempt_command() -> CreateCommandOption {
+        let user = || {
+            CreateCommandOption::new(CommandOptionType::User, "user", "The member").required(true)
+        };
+
+        CreateCommandOption::new(
+            CommandOptionType::SubCommandGroup,
+            "exempt",
+            "Members culls must skip",
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "add",
+                "Exempt a member from culls",
+            )
+            .add_sub_option(user())
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "reason",
+                    "Why, e.g. \"on leave until March\"",
+                )
+                .required(false),
+            )
+            .add_sub_option(
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "days",
+                    "Expire after this many days (default: never)",
+                )
+                .required(false)
+                .min_int_value(1)
+                .max_int_value(3650),
+            ),
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "remove",
+                "Remove a member's exemption",
+            )
+            .add_sub_option(user()),
+        )
+        .add_sub_option(CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "list",
+            "Show exempt members",
+        ))
+    }
+
+    /// Handle the `exempt` subcommand group.
+    pub async fn exempt(
+        ctx: &Context,
+        pool: &DbPool,
+        guild_id: u64,
+        cat_herding: Option<ChannelId>,
+        command: &CommandInteraction,
+        group: &CommandDataOption,
+    ) -> HandlerResponse {
+        let CommandDataOptionValue::SubCommandGroup(subcommands) = &group.value else {
+            return HandlerResponse::deferred("Invalid subcommand");
+        };
+        let Some(subcommand) = subcommands.first() else {
+            return HandlerResponse::deferred("Expected a subcommand");
+        };
+        let CommandDataOptionValue::SubCommand(options) = &subcommand.value else {
+            return HandlerResponse::deferred("Invalid subcommand");
+        };
+        let options = options.iter().map(|o| (o.name.as_str(), &o.value));
+        let now = SystemTime::now();
+
+        match subcommand.name.as_str() {
+            "add" => {
+                let exemption =
+                    match exemption_from_options(guild_id, command.user.id.get(), options, now) {
+                        Ok(exemption) => exemption,
+                        Err(e) => return HandlerResponse::deferred(e),
+                    };
+                if let Err(e) = upsert_cull_exemption(pool, &exemption) {
+                    eprintln!("[cull] Failed to save exemption: {}", e);
+                    return HandlerResponse::deferred(
+                        "Failed to save the exemption. Please try again later.",
+                    );
+                }
+                let line = format_exemption(&exemption);
+                post_to_cat_herding(
+                    &ctx.http,
+                    cat_herding,
+                    &format!("Cull exemption added: {}", line),
+                )
+                .await;
+                HandlerResponse::deferred(format!("Exempted {}", line))
+            }
+            "remove" => {
+                let Some(user_id) = options.into_iter().find_map(|option| match option {
+                    ("user", CommandDataOptionValue::User(id)) => Some(id.get()),
+                    _ => None,
+                }) else {
+                    return HandlerResponse::deferred("Missing required option `user`");
+                };
+                match delete_cull_exemption(pool, guild_id as i64, user_id as i64) {
+                    Ok(true) => {
+                        post_to_cat_herding(
+                            &ctx.http,
+                            cat_herding,
+                            &format!(
+                                "Cull exemption removed: <@{}> (by <@{}>)",
+                                user_id, command.user.id
+                            ),
+                        )
+                        .await;
+                        HandlerResponse::deferred(format!(
+                            "<@{}> is no longer exempt from culls.",
+                            user_id
+                        ))
+                    }
+                    Ok(false) => {
+                        HandlerResponse::deferred(format!("<@{}> is not exempt.", user_id))
+                    }
+                    Err(e) => {
+                        eprintln!("[cull] Failed to remove exemption: {}", e);
+                        HandlerResponse::deferred(
+                            "Failed to remove the exemption. Please try again later.",
+                        )
+                    }
+                }
+            }
+            "list" => match get_active_cull_exemptions(pool, guild_id as i64, now) {
+                Ok(exemptions) => HandlerResponse::deferred(format_exemption_list(&exemptions)),
+                Err(e) => {
+                    eprintln!("[cull] Failed to load exemptions: {}", e);
+                    HandlerResponse::deferred("Failed to load exemptions. Please try again later.")
+                }
+            },
+            other => HandlerResponse::deferred(format!("Unknown subcommand `{}`", other)),
+        }
+    }
+}
+
+/// Build the exemption described by `/cull exempt add`'s options.
+fn exemption_from_options<'a>(
+    guild_id: u64,
+    added_by: u64,
+    options: impl IntoIterator<Item = (&'a str, &'a CommandDataOptionValue)>,
+    now: SystemTime,
+) -> Result<CullExemption, String> {
+    let mut user_id = None;
+    let mut reason = None;
+    let mut expires_at = None;
+    for option in options {
+        match option {
+            ("user", CommandDataOptionValue::User(id)) => user_id = Some(id.get()),
+            ("reason", CommandDataOptionValue::String(s)) if !s.trim().is_empty() => {
+                reason = Some(s.trim().to_string());
+            }
+            ("days", CommandDataOptionValue::Integer(d)) if *d > 0 => {
+                expires_at = Some(now + Duration::from_secs(*d as u64 * 86_400));
+            }
+            _ => {}
+        }
+    }
+    let user_id = user_id.ok_or("Missing required option `user`")?;
+
+    Ok(CullExemption {
+        guild_id: guild_id as i64,
+        user_id: user_id as i64,
+        reason,
+        expires_at,
+        added_by: added_by as i64,
+        created_at: now,
+    })
+}
+
+fn format_exemption(exemption: &CullExemption) -> String {
+    let mut line = format!("<@{}>", exemption.user_id);
+    if let Some(reason) = &exemption.reason {
+        line.push_str(&format!(" — {}", reason));
+    }
+    match exemption.expires_at {
+        Some(expires_at) => line.push_str(&format!(" (until {})", format_timestamp(expires_at))),
+        None => line.push_str(" (permanent)"),
+    }
+    line.push_str(&format!(", added by <@{}>", exemption.added_by));
+    line
+}
+
+fn format_exemption_list(exemptions: &[CullExemption]) -> String {
+    if exemptions.is_empty() {
+        return "No members are exempt from culls.".to_string();
+    }
+    let mut output = format!("**Cull exemptions** ({})\n", exemptions.len());
+    for (i, exemption) in exemptions.iter().enumerate() {
+        let line = format_exemption(exemption);
+        if output.len() + line.len() > 1900 {
+            output.push_str(&format!("...and {} more", exemptions.len() - i));
+            break;
+        }
+        output.push_str(&line);
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serenity::all::UserId;
+
+    #[test]
+    fn exemption_from_options_parses_expiry_and_reason() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(19737 * 86_400);
+        let options = [
+            ("user", CommandDataOptionValue::User(UserId::new(5))),
+            (
+                "reason",
+                CommandDataOptionValue::String("  parental leave ".to_string()),
+            ),
+            ("days", CommandDataOptionValue::Integer(7)),
+        ];
+        let pairs = || options.iter().map(|(name, value)| (*name, value));
+
+        let exemption = exemption_from_options(1, 9, pairs(), now).unwrap();
+        assert_eq!(exemption.user_id, 5);
+        assert_eq!(exemption.reason.as_deref(), Some("parental leave"));
+        assert_eq!(
+            format_exemption(&exemption),
+            "<@5> — parental leave (until 2024-01-22), added by <@9>"
+        );
+
+        let permanent = exemption_from_options(1, 9, pairs().take(1), now).unwrap();
+        assert_eq!(
+            format_exemption(&permanent),
+            "<@5> (permanent), added by <@9>"
+        );
+
+        assert!(exemption_from_options(1, 9, pairs().skip(1), now).is_err());
+    }
+}
@@ -0,0 +1,385 @@
+//! `/cull history` lists recorded cull runs (see `kick_candidates`) and
+//! `/cull reinvite` hands a member culled by mistake a single-use invite.
+
+use super::{format_timestamp, post_to_cat_herding, CullHandler};
+use crate::db::{
+    count_cull_run_outcomes, get_cull_run, get_cull_run_members, get_cull_runs,
+    get_latest_cull_kick,
+    models::{CullRun, CullRunMember},
+    set_cull_reinvited, DbPool,
+};
+use crate::handlers::{subcommand_options, HandlerResponse};
+use serenity::{
+    all::{CommandDataOption, CommandDataOptionValue, CommandInteraction, CommandOptionType},
+    builder::{CreateCommandOption, CreateInvite, CreateMessage},
+    client::Context,
+    model::id::ChannelId,
+};
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+/// Runs shown by `/cull history` without a run number.
+const HISTORY_LIMIT: i64 = 10;
+/// How long a re-invite stays valid (7 days).
+const REINVITE_MAX_AGE_SECS: u32 = 7 * 86_400;
+
+impl CullHandler {
+    pub fn history_command() -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "history",
+            "List past culls, or show who a run kicked",
+        )
+        .add_sub_option(
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "run",
+                "Run number to show in detail",
+            )
+            .required(false)
+            .min_int_value(1),
+        )
+    }
+
+    pub fn reinvite_command() -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "reinvite",
+            "Create a single-use invite for a member culled by mistake",
+        )
+        .add_sub_option(
+            CreateCommandOption::new(CommandOptionType::User, "user", "The culled member")
+                .required(true),
+        )
+    }
+
+    /// Handle `/cull history`.
+    pub async fn history(
+        pool: &DbPool,
+        guild_id: u64,
+        subcommand: &CommandDataOption,
+    ) -> HandlerResponse {
+        let options = subcommand_options(subcommand);
+        let run_id = options
+            .iter()
+            .find_map(|o| match (o.name.as_str(), &o.value) {
+                ("run", CommandDataOptionValue::Integer(id)) => Some(*id),
+                _ => None,
+            });
+
+        let Some(run_id) = run_id else {
+            let runs = match get_cull_runs(pool, guild_id as i64, HISTORY_LIMIT) {
+                Ok(runs) => runs,
+                Err(e) => {
+                    eprintln!("[cull] Failed to load cull runs: {}", e);
+                    return HandlerResponse::deferred(
+                        "Failed to load cull history. Please try again later.",
+                    );
+                }
+            };
+            let run_ids: Vec<i64> = runs.iter().map(|r| r.id).collect();
+            return match count_cull_run_outcomes(pool, &run_ids) {
+                Ok(counts) => HandlerResponse::deferred(format_run_list(&runs, &counts)),
+                Err(e) => {
+                    eprintln!("[cull] Failed to count cull outcomes: {}", e);
+                    HandlerResponse::deferred(
+                        "Failed to load cull history. Please try again later.",
+                    )
+                }
+            };
+        };
+
+        let run = match get_cull_run(pool, guild_id as i64, run_id) {
+            Ok(Some(run)) => run,
+            Ok(None) => {
+                return HandlerResponse::deferred(format!(
+                    "There is no cull #{} in this server.",
+                    run_id
+                ))
+            }
+            Err(e) => {
+                eprintln!("[cull] Failed to load cull run {}: {}", run_id, e);
+                return HandlerResponse::deferred(
+                    "Failed to load the cull run. Please try again later.",
+                );
+            }
+        };
+        match get_cull_run_members(pool, run.id) {
+            Ok(members) => HandlerResponse::deferred(format_run(&run, &members)),
+            Err(e) => {
+                eprintln!(
+                    "[cull] Failed to load members of cull run {}: {}",
+                    run.id, e
+                );
+                HandlerResponse::deferred("Failed to load the cull run. Please try again later.")
+            }
+        }
+    }
+
+    /// Handle `/cull reinvite`: create a single-use invite for a member a
+    /// cull kicked, try to DM it to them, and show it to the moderator.
+    pub async fn reinvite(
+        ctx: &Context,
+        pool: &DbPool,
+        guild_id: u64,
+        cat_herding: Option<ChannelId>,
+        command: &CommandInteraction,
+        subcommand: &CommandDataOption,
+    ) -> HandlerResponse {
+        let options = subcommand_options(subcommand);
+        let Some(user_id) = options
+            .iter()
+            .find_map(|o| match (o.name.as_str(), &o.value) {
+                ("user", CommandDataOptionValue::User(id)) => Some(*id),
+                _ => None,
+            })
+        else {
+            return HandlerResponse::deferred("Missing required option `user`");
+        };
+
+        let (run, _) = match get_latest_cull_kick(pool, guild_id as i64, user_id.get() as i64) {
+            Ok(Some(kick)) => kick,
+            Ok(None) => {
+                return HandlerResponse::deferred(format!(
+                    "<@{}> was not removed by a cull.",
+                    user_id
+                ))
+            }
+            Err(e) => {
+                eprintln!("[cull] Failed to look up cull kicks: {}", e);
+                return HandlerResponse::deferred(
+                    "Failed to look up cull history. Please try again later.",
+                );
+            }
+        };
+
+        // Land them in the system channel if there is one, else here
+        let channel_id = ctx
+            .http
+            .get_guild(guild_id.into())
+            .await
+            .ok()
+            .and_then(|g| g.system_channel_id)
+            .unwrap_or(command.channel_id);
+        let audit_reason = format!("Re-invite after cull #{}", run.id);
+        let invite = match channel_id
+            .create_invite(
+                &ctx.http,
+                CreateInvite::new()
+                    .max_uses(1)
+                    .max_age(REINVITE_MAX_AGE_SECS)
+                    .unique(true)
+                    .audit_log_reason(&audit_reason),
+            )
+            .await
+        {
+            Ok(invite) => invite,
+            Err(e) => {
+                eprintln!("[cull] Failed to create re-invite: {}", e);
+                return HandlerResponse::deferred(format!(
+                    "Failed to create an invite in <#{}>: {}",
+                    channel_id, e
+                ));
+            }
+        };
+        let url = invite.url();
+
+        if let Err(e) = set_cull_reinvited(pool, run.id, user_id.get() as i64, SystemTime::now()) {
+            eprintln!("[cull] Failed to record re-invite: {}", e);
+        }
+
+        let dm = format!(
+            "Sorry, you were removed from a server by mistake during an inactivity cleanup. You're welcome back: {}",
+            url
+        );
+        let delivered = user_id
+            .direct_message(&ctx.http, CreateMessage::new().content(dm))
+            .await
+            .is_ok();
+
+        post_to_cat_herding(
+            &ctx.http,
+            cat_herding,
+            &format!(
+                "Re-invite created for <@{}> (kicked by cull #{}) by <@{}>.",
+                user_id, run.id, command.user.id
+            ),
+        )
+        .await;
+
+        let sent = if delivered {
+            "It was sent to them by DM."
+        } else {
+            "I couldn't DM them, so please pass it on."
+        };
+        HandlerResponse::deferred(format!(
+            "Single-use invite for <@{}>, valid for 7 days: {}\n{}",
+            user_id, url, sent
+        ))
+    }
+}
+
+/// `#12 2024-01-15, manual by <@5>, inactive 30+ days (never posted: no)`
+fn describe_run(run: &CullRun) -> String {
+    let by = match run.invoked_by {
+        Some(id) => format!("{} by <@{}>", run.source, id),
+        None => run.source.clone(),
+    };
+    let mut description = format!(
+        "#{} {}, {}, inactive {}+ days (never posted: {}",
+        run.id,
+        format_timestamp(run.started_at),
+        by,
+        run.inactive_days,
+        if run.include_never_posted {
+            "yes"
+        } else {
+            "no"
+        },
+    );
+    if let Some(min_messages) = run.min_messages {
+        description.push_str(&format!(", under {} messages", min_messages));
+    }
+    description.push(')');
+    description
+}
+
+fn format_run_list(runs: &[CullRun], counts: &[(i64, String, i64)]) -> String {
+    if runs.is_empty() {
+        return "No culls have been run in this server.".to_string();
+    }
+    let mut by_run: HashMap<i64, HashMap<&str, i64>> = HashMap::new();
+    for (run_id, outcome, count) in counts {
+        by_run
+            .entry(*run_id)
+            .or_default()
+            .insert(outcome.as_str(), *count);
+    }
+
+    let mut output = String::from("**Recent culls**\n");
+    for run in runs {
+        let counts = by_run.get(&run.id);
+        let count = |outcome: &str| counts.and_then(|c| c.get(outcome)).copied().unwrap_or(0);
+        output.push_str(&format!(
+            "{}: {} kicked, {} failed, {} skipped",
+            describe_run(run),
+            count("kicked"),
+            count("failed"),
+            count("skipped"),
+        ));
+        if count("cancelled") > 0 {
+            output.push_str(&format!(", {} cancelled", count("cancelled")));
+        }
+        if run.finished_at.is_none() {
+            output.push_str(&format!(" ({} not reached)", count("pending")));
+        }
+        output.push('\n');
+    }
+    output.push_str("Use `/cull history run:<number>` for details.");
+    output
+}
+
+fn format_run(run: &CullRun, members: &[CullRunMember]) -> String {
+    let mut output = format!("**Cull {}**\n", describe_run(run));
+    if run.finished_at.is_none() {
+        output.push_str("Did not finish; members marked pending were never reached.\n");
+    }
+    for (i, member) in members.iter().enumerate() {
+        let mut line = format!("<@{}> — {}", member.user_id, member.outcome);
+        if let Some(error) = &member.error {
+            line.push_str(&format!(" ({})", error));
+        }
+        if let Some(at) = member.reinvited_at {
+            line.push_str(&format!(", re-invited {}", format_timestamp(at)));
+        }
+        if output.len() + line.len() > 1900 {
+            output.push_str(&format!("...and {} more", members.len() - i));
+            break;
+        }
+        output.push_str(&line);
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn run(id: i64, invoked_by: Option<i64>, finished: bool) -> CullRun {
+        let started_at = SystemTime::UNIX_EPOCH + Duration::from_secs(19737 * 86_400);
+        CullRun {
+            id,
+            guild_id: 1,
+            source: if invoked_by.is_some() {
+                "manual"
+            } else {
+                "scheduled"
+            }
+            .to_string(),
+            invoked_by,
+            inactive_days: 30,
+            include_never_posted: false,
+            started_at,
+            finished_at: finished.then_some(started_at),
+            min_messages: None,
+        }
+    }
+
+    #[test]
+    fn format_run_list_counts_outcomes() {
+        let runs = [run(2, None, false), run(1, Some(5), true)];
+        let counts = [
+            (1, "kicked".to_string(), 3),
+            (1, "failed".to_string(), 1),
+            (2, "kicked".to_string(), 1),
+            (2, "pending".to_string(), 4),
+        ];
+        let output = format_run_list(&runs, &counts);
+        assert!(output.contains(
+            "#2 2024-01-15, scheduled, inactive 30+ days (never posted: no): 1 kicked, 0 failed, 0 skipped (4 not reached)\n"
+        ));
+        assert!(output.contains(
+            "#1 2024-01-15, manual by <@5>, inactive 30+ days (never posted: no): 3 kicked, 1 failed, 0 skipped\n"
+        ));
+        let cancelled = format_run_list(
+            &[run(3, Some(5), true)],
+            &[
+                (3, "kicked".to_string(), 2),
+                (3, "cancelled".to_string(), 6),
+            ],
+        );
+        assert!(cancelled.contains("2 kicked, 0 failed, 0 skipped, 6 cancelled\n"));
+        assert_eq!(
+            format_run_list(&[], &[]),
+            "No culls have been run in this server."
+        );
+    }
+
+    #[test]
+    fn format_run_lists_members() {
+        let members = [
+            CullRunMember {
+                run_id: 1,
+                user_id: 10,
+                outcome: "kicked".to_string(),
+                error: None,
+                reinvited_at: Some(SystemTime::UNIX_EPOCH + Duration::from_secs(19744 * 86_400)),
+            },
+            CullRunMember {
+                run_id: 1,
+                user_id: 11,
+                outcome: "failed".to_string(),
+                error: Some("Missing Permissions".to_string()),
+                reinvited_at: None,
+            },
+        ];
+        assert_eq!(
+            format_run(&run(1, Some(5), true), &members),
+            "**Cull #1 2024-01-15, manual by <@5>, inactive 30+ days (never posted: no)**\n\
+             <@10> — kicked, re-invited 2024-01-22\n\
+             <@11> — failed (Missing Permissions)\n"
+        );
+    }
+}
@@ -0,0 +1,1762 @@
+pub mod exempt;
+pub mod history;
+pub mod running;
+pub mod schedule;
+
+use crate::db::{
+    bulk_upsert_activity_at, cancel_pending_cull_run_members, delete_cull_scan_checkpoints,
+    finish_cull_run, get_active_cull_exemptions, get_cull_scan_checkpoints, insert_cull_run,
+    models::{CullScanCheckpoint, NewCullRun},
+    query_all_tracked_user_ids_for_guild, query_first_message_count_day, query_inactive_users,
+    query_message_totals, query_user_activity_for_ids, set_cull_run_outcome,
+    upsert_cull_scan_checkpoint,
+};
+use crate::features::Features;
+use crate::handlers::{get_pool, gulag::Gulag, subcommand_options, HandlerResponse};
+use crate::tugbot::{activity::epoch_day, guild_config::GuildConfig};
+use running::{running_culls, RunningCull, RunningCulls};
+use serenity::{
+    all::{
+        ButtonStyle, ChannelType, CommandDataOptionValue, CommandInteraction, CommandOptionType,
+        ComponentInteraction, CreateActionRow, CreateButton, CreateInteractionResponse,
+        CreateInteractionResponseMessage, CreateMessage, GuildChannel, Member, MessageId,
+        MessagePagination, Permissions, ThreadsData, Timestamp,
+    },
+    builder::{CreateCommand, CreateCommandOption},
+    client::Context,
+    http::{LightMethod, Request, Route},
+    model::id::ChannelId,
+    prelude::TypeMapKey,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::{atomic::Ordering, Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+pub struct CullHandler;
+
+// Hard cap on kicks per invocation
+const MAX_KICKS: usize = 50;
+// Sleep between kicks to respect rate limits (1.5s)
+const KICK_DELAY_MS: u64 = 1500;
+// How long an execute-mode preview waits for Confirm
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(300);
+// How far back /cull scan reads message history
+const SCAN_CUTOFF_DAYS: u64 = 180;
+// Window for the `min-messages` threshold
+const MESSAGE_WINDOW_DAYS: i32 = 30;
+
+/// Prefix of the Confirm/Cancel button custom IDs: `cull:<action>:<token>`.
+pub const CUSTOM_ID_PREFIX: &str = "cull:";
+
+impl CullHandler {
+    pub fn setup_command() -> CreateCommand {
+        CreateCommand::new("cull")
+            .description("Cull inactive members from the server")
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "run",
+                    "Preview or kick inactive members",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "days",
+                        "Inactivity threshold in days (default: 30)",
+                    )
+                    .required(false),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Boolean,
+                        "dry-run",
+                        "Preview candidates without kicking (default: false)",
+                    )
+                    .required(false),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Boolean,
+                        "include-never-posted",
+                        "Include users who have never posted (default: false)",
+                    )
+                    .required(false),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Integer,
+                        "min-messages",
+                        "Also include members with fewer messages than this in the last 30 days",
+                    )
+                    .required(false)
+                    .min_int_value(1)
+                    .max_int_value(10_000),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "scan",
+                    "Seed activity data from message history (resumes if interrupted)",
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Boolean,
+                        "rescan",
+                        "Forget previous progress and read every channel again",
+                    )
+                    .required(false),
+                ),
+            )
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "scan-status",
+                "Show per-channel progress of the history scan",
+            ))
+            .add_option(Self::exempt_command())
+            .add_option(Self::history_command())
+            .add_option(Self::reinvite_command())
+            .add_option(Self::cancel_command())
+    }
+
+    pub async fn setup_interaction(ctx: &Context, command: &CommandInteraction) -> HandlerResponse {
+        let pool = get_pool(ctx).await;
+
+        // a. Feature flag check
+        match Features::check_enabled(&pool, command.guild_id.map(|g| g.get()), "cull") {
+            Ok(true) => {}
+            Ok(false) => {
+                return HandlerResponse {
+                    content: "Cull feature is currently disabled".to_string(),
+                    components: None,
+                    ephemeral: true,
+                    defer_response: Some(true),
+                };
+            }
+            Err(e) => {
+                return HandlerResponse {
+                    content: format!("Failed to check cull feature: {}", e),
+                    components: None,
+                    ephemeral: true,
+                    defer_response: Some(true),
+                };
+            }
+        }
+
+        // b. Guild check
+        let guild_id = match command.guild_id {
+            Some(id) => id.get(),
+            None => {
+                return HandlerResponse {
+                    content: "This command can only be used in a guild".to_string(),
+                    components: None,
+                    ephemeral: true,
+                    defer_response: Some(true),
+                };
+            }
+        };
+
+        // c. Invoker permissions are checked centrally (see crate::permissions)
+        let guild_config = GuildConfig::load(&pool, guild_id);
+
+        // Moderation log channel (cat-herding unless configured via /config)
+        let cat_herding = guild_config.mod_log_channel(&ctx.http).await;
+
+        // Subcommands other than `run` don't kick anyone
+        let Some(subcommand) = command.data.options.first() else {
+            return HandlerResponse::deferred("Expected a subcommand");
+        };
+        match subcommand.name.as_str() {
+            "scan" => {
+                let rescan = subcommand_options(subcommand).iter().any(|o| {
+                    o.name == "rescan" && matches!(o.value, CommandDataOptionValue::Boolean(true))
+                });
+                let scans = scanning_guilds(ctx).await;
+                return run_scan(
+                    ctx.http.clone(),
+                    &pool,
+                    &scans,
+                    guild_id,
+                    cat_herding,
+                    command,
+                    rescan,
+                )
+                .await;
+            }
+            "scan-status" => return scan_status(&ctx.http, &pool, guild_id).await,
+            "exempt" => {
+                return Self::exempt(ctx, &pool, guild_id, cat_herding, command, subcommand).await;
+            }
+            "history" => return Self::history(&pool, guild_id, subcommand).await,
+            "cancel" => return Self::cancel(ctx, guild_id, command).await,
+            "reinvite" => {
+                return Self::reinvite(ctx, &pool, guild_id, cat_herding, command, subcommand)
+                    .await;
+            }
+            _ => {}
+        }
+        let options = subcommand_options(subcommand);
+
+        // d. Bot KICK_MEMBERS permission check
+        let current_user = match ctx.http.get_current_user().await {
+            Ok(u) => u,
+            Err(e) => {
+                return HandlerResponse {
+                    content: format!("Failed to get bot info: {}", e),
+                    components: None,
+                    ephemeral: true,
+                    defer_response: Some(true),
+                };
+            }
+        };
+        let bot_member = match ctx.http.get_member(guild_id.into(), current_user.id).await {
+            Ok(m) => m,
+            Err(e) => {
+                return HandlerResponse {
+                    content: format!("Failed to get bot member: {}", e),
+                    components: None,
+                    ephemeral: true,
+                    defer_response: Some(true),
+                };
+            }
+        };
+
+        let guild = match ctx.http.get_guild(guild_id.into()).await {
+            Ok(g) => g,
+            Err(e) => {
+                return HandlerResponse {
+                    content: format!("Failed to get guild info: {}", e),
+                    components: None,
+                    ephemeral: true,
+                    defer_response: Some(true),
+                };
+            }
+        };
+
+        let bot_permissions = guild.member_permissions(&bot_member);
+        if !bot_permissions.contains(Permissions::KICK_MEMBERS) {
+            return HandlerResponse {
+                content: "I don't have KICK_MEMBERS permission on this server.".to_string(),
+                components: None,
+                ephemeral: true,
+                defer_response: Some(true),
+            };
+        }
+
+        // e. Parse options
+        let days: i64 = options
+            .iter()
+            .find(|opt| opt.name == "days")
+            .and_then(|opt| match &opt.value {
+                CommandDataOptionValue::Integer(v) => Some(*v),
+                _ => None,
+            })
+            .unwrap_or(30);
+
+        if days <= 0 || days > 365 {
+            return HandlerResponse {
+                content: "Days must be between 1 and 365".to_string(),
+                components: None,
+                ephemeral: true,
+                defer_response: Some(true),
+            };
+        }
+
+        let dry_run: bool = options
+            .iter()
+            .find(|opt| opt.name == "dry-run")
+            .and_then(|opt| match &opt.value {
+                CommandDataOptionValue::Boolean(v) => Some(*v),
+                _ => None,
+            })
+            .unwrap_or(false);
+
+        let include_never_posted: bool = options
+            .iter()
+            .find(|opt| opt.name == "include-never-posted")
+            .and_then(|opt| match &opt.value {
+                CommandDataOptionValue::Boolean(v) => Some(*v),
+                _ => None,
+            })
+            .unwrap_or(false);
+
+        let min_messages: Option<i64> = options
+            .iter()
+            .find(|opt| opt.name == "min-messages")
+            .and_then(|opt| match &opt.value {
+                CommandDataOptionValue::Integer(v) => Some(*v),
+                _ => None,
+            });
+
+        // f–j. Build candidate list
+        let criteria = CullCriteria {
+            days,
+            include_never_posted,
+            min_messages,
+        };
+        let mut candidates = match find_candidates(
+            &ctx.http,
+            &pool,
+            &guild_config,
+            guild_id,
+            &criteria,
+            cat_herding,
+        )
+        .await
+        {
+            Ok(candidates) => candidates,
+            Err(e) => {
+                return HandlerResponse {
+                    content: e,
+                    components: None,
+                    ephemeral: true,
+                    defer_response: Some(true),
+                };
+            }
+        };
+
+        // Cap at MAX_KICKS
+        candidates.truncate(MAX_KICKS);
+
+        if candidates.is_empty() {
+            let msg = format!("No candidates found ({})", criteria.describe());
+            post_to_cat_herding(&ctx.http, cat_herding, &msg).await;
+            return HandlerResponse {
+                content: "No candidates found.".to_string(),
+                components: None,
+                ephemeral: false,
+                defer_response: Some(true),
+            };
+        }
+
+        // k. Look up last activity for the preview (one roundtrip)
+        let guild_id_i64 = guild_id as i64;
+        let candidates_i64: Vec<i64> = candidates
+            .iter()
+            .filter_map(|c| i64::try_from(c.user_id).ok())
+            .collect();
+
+        let activity_results = query_user_activity_for_ids(&pool, guild_id_i64, candidates_i64);
+        let activity_map: HashMap<i64, SystemTime> = match activity_results {
+            Ok(results) => results
+                .into_iter()
+                .map(|a| (a.user_id, a.last_message_at))
+                .collect(),
+            Err(e) => {
+                post_to_cat_herding(
+                    &ctx.http,
+                    cat_herding,
+                    &format!("Error querying activity: {}", e),
+                )
+                .await;
+                return HandlerResponse {
+                    content: format!("Failed to query activity: {}", e),
+                    components: None,
+                    ephemeral: true,
+                    defer_response: Some(true),
+                };
+            }
+        };
+        let candidate_block = format_candidates(&candidates, &activity_map);
+
+        // l. Dry-run mode
+        if dry_run {
+            let message = format!(
+                "**Cull Dry-Run** ({})\n\n{}\n\nTotal candidates: {} (capped at {})\nRun `{}` to execute.",
+                criteria.describe(),
+                candidate_block,
+                candidates.len(),
+                MAX_KICKS,
+                criteria.run_command(),
+            );
+
+            let posted = post_to_cat_herding(&ctx.http, cat_herding, &message).await;
+
+            if posted {
+                HandlerResponse {
+                    content: format!("Dry-run posted to {}", mention_channel(cat_herding)),
+                    components: None,
+                    ephemeral: true,
+                    defer_response: Some(true),
+                }
+            } else {
+                HandlerResponse {
+                    content: format!(
+                        "Failed to post to {}. Dry-run results:\n\n{}",
+                        mention_channel(cat_herding),
+                        message
+                    ),
+                    components: None,
+                    ephemeral: true,
+                    defer_response: Some(true),
+                }
+            }
+        } else {
+            // m. Execute mode — freeze the candidate list and ask the invoking
+            // moderator to confirm. The kick loop only starts from the button.
+            let token = command.id.get();
+            let pending = PendingCull {
+                guild_id,
+                moderator_id: command.user.id.get(),
+                moderator_name: command.user.name.clone(),
+                criteria,
+                candidates: candidates.iter().map(|c| c.user_id).collect(),
+                cat_herding,
+                created_at: Instant::now(),
+            };
+            let total = pending.candidates.len();
+            insert_pending(&*pending_culls(ctx).await, token, pending);
+
+            HandlerResponse {
+                content: format!(
+                    "**Confirm cull** ({})\n\n{}\n\nThis will kick {} members. Confirm within {} minutes.",
+                    criteria.describe(),
+                    candidate_block,
+                    total,
+                    CONFIRM_TIMEOUT.as_secs() / 60,
+                ),
+                components: Some(vec![CreateActionRow::Buttons(vec![
+                    CreateButton::new(format!("{}confirm:{}", CUSTOM_ID_PREFIX, token))
+                        .label(format!("Kick {} members", total))
+                        .style(ButtonStyle::Danger),
+                    CreateButton::new(format!("{}cancel:{}", CUSTOM_ID_PREFIX, token))
+                        .label("Cancel")
+                        .style(ButtonStyle::Secondary),
+                ])]),
+                ephemeral: true,
+                defer_response: Some(true),
+            }
+        }
+    }
+
+    /// Handle the Confirm/Cancel buttons on an execute-mode preview.
+    pub async fn handle_component(ctx: &Context, component: &ComponentInteraction) {
+        let content = match parse_custom_id(&component.data.custom_id) {
+            None => "Unknown cull button.".to_string(),
+            Some((action, token)) => {
+                let pending = take_pending(
+                    &*pending_culls(ctx).await,
+                    token,
+                    component.user.id.get(),
+                    Instant::now(),
+                );
+                match (action, pending) {
+                    (_, Err(why)) => why,
+                    (CullAction::Cancel, Ok(_)) => "Cull cancelled. Nobody was kicked.".to_string(),
+                    (CullAction::Confirm, Ok(pending)) => {
+                        let total = pending.candidates.len();
+                        let cat_herding = pending.cat_herding;
+                        spawn_kick_loop(
+                            ctx.http.clone(),
+                            get_pool(ctx).await,
+                            running_culls(ctx).await,
+                            pending,
+                        );
+                        format!(
+                            "Cull started: {} candidates. Results will be posted to {}.",
+                            total,
+                            mention_channel(cat_herding)
+                        )
+                    }
+                }
+            }
+        };
+
+        let response = CreateInteractionResponse::UpdateMessage(
+            CreateInteractionResponseMessage::new()
+                .content(content)
+                .components(vec![]),
+        );
+        if let Err(why) = component.create_response(&ctx.http, response).await {
+            eprintln!("[cull] Cannot respond to button: {}", why);
+        }
+    }
+}
+
+/// Who a cull targets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CullCriteria {
+    /// Members last active more than this many days ago.
+    days: i64,
+    /// Also members with no recorded activity at all.
+    include_never_posted: bool,
+    /// Also members with recorded activity but fewer than this many
+    /// messages in the last [`MESSAGE_WINDOW_DAYS`].
+    min_messages: Option<i64>,
+}
+
+impl CullCriteria {
+    fn describe(&self) -> String {
+        let mut description = format!(
+            "inactive {}+ days, never posted: {}",
+            self.days,
+            if self.include_never_posted {
+                "yes"
+            } else {
+                "no"
+            }
+        );
+        if let Some(min_messages) = self.min_messages {
+            description.push_str(&format!(
+                ", or fewer than {} messages in {} days",
+                min_messages, MESSAGE_WINDOW_DAYS
+            ));
+        }
+        description
+    }
+
+    /// The `/cull run` invocation that repeats this cull.
+    fn run_command(&self) -> String {
+        let mut command = format!("/cull run days:{}", self.days);
+        if self.include_never_posted {
+            command.push_str(" include-never-posted:True");
+        }
+        if let Some(min_messages) = self.min_messages {
+            command.push_str(&format!(" min-messages:{}", min_messages));
+        }
+        command
+    }
+}
+
+/// Members a cull with these criteria would target, sorted by user ID and
+/// not yet capped at MAX_KICKS. Bots, whitelisted roles, exempt members (see
+/// `exempt`), recently joined and gulaged users are never candidates.
+/// Errors are user-facing messages.
+async fn find_candidates(
+    http: &serenity::all::Http,
+    pool: &crate::db::DbPool,
+    guild_config: &GuildConfig,
+    guild_id: u64,
+    criteria: &CullCriteria,
+    cat_herding: Option<ChannelId>,
+) -> Result<Vec<Candidate>, String> {
+    // Fetch member list via REST pagination
+    let mut all_members: Vec<Member> = Vec::new();
+    let mut after_id: Option<u64> = None;
+    loop {
+        let members: Vec<Member> = match serenity::all::GuildId::from(guild_id)
+            .members(http, Some(1000), after_id.map(serenity::all::UserId::from))
+            .await
+        {
+            Ok(ms) => ms,
+            Err(e) => {
+                post_to_cat_herding(http, cat_herding, &format!("Error fetching members: {}", e))
+                    .await;
+                return Err(format!("Failed to fetch members: {}", e));
+            }
+        };
+        let count = members.len();
+        all_members.extend(members);
+        if count < 1000 {
+            break;
+        }
+        after_id = all_members.last().map(|m| m.user.id.get());
+    }
+
+    // Fetch whitelist roles (fail-closed: abort if we can't resolve roles)
+    let whitelist_role_ids = guild_config
+        .cull_whitelist_role_ids(http)
+        .await
+        .map_err(|e| format!("Failed to resolve whitelist roles: {}", e))?;
+
+    // Fetch exemptions (fail-closed, like the whitelist)
+    let now = SystemTime::now();
+    let exempt_ids: HashSet<u64> = get_active_cull_exemptions(pool, guild_id as i64, now)
+        .map_err(|e| format!("Failed to load cull exemptions: {}", e))?
+        .into_iter()
+        .map(|exemption| exemption.user_id as u64)
+        .collect();
+
+    // Filter: remove bots, whitelisted, exempt, recently joined and gulaged users
+    let grace_period = guild_config.cull_grace_period();
+    let eligible_members: Vec<_> = all_members
+        .into_iter()
+        .filter(|member| {
+            !member.user.bot
+                && !member_has_any_role_ids(member, &whitelist_role_ids)
+                && !exempt_ids.contains(&member.user.id.get())
+                && !in_grace_period(joined_at(member), grace_period, now)
+                && Gulag::is_user_in_gulag(pool, member.user.id.get()).is_none()
+        })
+        .collect();
+
+    // Query inactive users from DB
+    let guild_id_i64 =
+        i64::try_from(guild_id).map_err(|e| format!("Failed to convert guild ID: {}", e))?;
+    let inactive_user_ids: HashSet<u64> =
+        match query_inactive_users(pool, guild_id_i64, criteria.days as i32) {
+            Ok(ids) => ids
+                .into_iter()
+                .filter_map(|id| u64::try_from(id).ok())
+                .collect(),
+            Err(e) => {
+                post_to_cat_herding(
+                    http,
+                    cat_herding,
+                    &format!("Error querying inactive users: {}", e),
+                )
+                .await;
+                return Err(format!("Failed to query inactive users: {}", e));
+            }
+        };
+
+    let mut candidates: Vec<Candidate> = eligible_members
+        .iter()
+        .filter(|member| inactive_user_ids.contains(&member.user.id.get()))
+        .map(Candidate::from_member)
+        .collect();
+
+    // Include never-posted users if requested
+    if criteria.include_never_posted {
+        match query_all_tracked_user_ids_for_guild(pool, guild_id_i64) {
+            Ok(tracked_ids) => {
+                let tracked_set: HashSet<u64> = tracked_ids
+                    .into_iter()
+                    .filter_map(|id| u64::try_from(id).ok())
+                    .collect();
+                candidates.extend(
+                    eligible_members
+                        .iter()
+                        .filter(|member| !tracked_set.contains(&member.user.id.get()))
+                        .map(Candidate::from_member),
+                );
+            }
+            Err(_) => {
+                let err_msg = "Failed to query tracked users for never-posted check".to_string();
+                post_to_cat_herding(http, cat_herding, &err_msg).await;
+            }
+        }
+    }
+
+    // Include low-volume posters if requested
+    if let Some(min_messages) = criteria.min_messages {
+        let low_volume = low_volume_user_ids(pool, guild_id_i64, min_messages, now)?;
+        candidates.extend(
+            eligible_members
+                .iter()
+                .filter(|member| low_volume.contains(&member.user.id.get()))
+                .map(Candidate::from_member),
+        );
+    }
+
+    // Deduplicate, sort by user ID for determinism
+    candidates.sort_by_key(|c| c.user_id);
+    candidates.dedup_by_key(|c| c.user_id);
+    Ok(candidates)
+}
+
+/// Tracked users with fewer than `min_messages` messages in the last
+/// [`MESSAGE_WINDOW_DAYS`]. Refuses until message counts cover the whole
+/// window, since counting only started with live tracking and a partial
+/// window would make everyone look quiet.
+fn low_volume_user_ids(
+    pool: &crate::db::DbPool,
+    guild_id: i64,
+    min_messages: i64,
+    now: SystemTime,
+) -> Result<HashSet<u64>, String> {
+    let since_day = epoch_day(now) - (MESSAGE_WINDOW_DAYS - 1);
+    let first_day = query_first_message_count_day(pool, guild_id)
+        .map_err(|e| format!("Failed to query message counts: {}", e))?;
+    match first_day {
+        Some(first_day) if first_day <= since_day => {}
+        Some(first_day) => {
+            return Err(format!(
+                "Message counts only go back {} days; `min-messages` needs {} days of data.",
+                epoch_day(now) - first_day + 1,
+                MESSAGE_WINDOW_DAYS
+            ))
+        }
+        None => return Err("No message counts have been recorded yet.".to_string()),
+    }
+
+    let totals: HashMap<i64, i64> = query_message_totals(pool, guild_id, since_day)
+        .map_err(|e| format!("Failed to query message counts: {}", e))?
+        .into_iter()
+        .collect();
+    let tracked = query_all_tracked_user_ids_for_guild(pool, guild_id)
+        .map_err(|e| format!("Failed to query tracked users: {}", e))?;
+    Ok(tracked
+        .into_iter()
+        .filter(|id| totals.get(id).copied().unwrap_or(0) < min_messages)
+        .filter_map(|id| u64::try_from(id).ok())
+        .collect())
+}
+
+/// A member a cull would target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Candidate {
+    user_id: u64,
+    joined_at: Option<SystemTime>,
+}
+
+impl Candidate {
+    fn from_member(member: &Member) -> Candidate {
+        Candidate {
+            user_id: member.user.id.get(),
+            joined_at: joined_at(member),
+        }
+    }
+}
+
+fn joined_at(member: &Member) -> Option<SystemTime> {
+    system_time(member.joined_at?)
+}
+
+fn system_time(timestamp: Timestamp) -> Option<SystemTime> {
+    let secs = u64::try_from(timestamp.unix_timestamp()).ok()?;
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+/// Whether a member who joined at `joined_at` is still protected as new.
+/// Members with an unknown join date are not.
+fn in_grace_period(joined_at: Option<SystemTime>, grace_period: Duration, now: SystemTime) -> bool {
+    joined_at.is_some_and(|joined| {
+        now.duration_since(joined)
+            .map_or(true, |since| since < grace_period)
+    })
+}
+
+/// Build the candidate preview lines (max 25).
+fn format_candidates(candidates: &[Candidate], activity_map: &HashMap<i64, SystemTime>) -> String {
+    let display_count = std::cmp::min(candidates.len(), 25);
+    let mut lines: Vec<String> = Vec::new();
+    for candidate in &candidates[..display_count] {
+        let uid = candidate.user_id;
+        let uid_i64: i64 = uid.try_into().unwrap_or(i64::MAX);
+        let date_str = match activity_map.get(&uid_i64) {
+            Some(&ts) => format_timestamp(ts),
+            None => "never posted".to_string(),
+        };
+        let joined_str = match candidate.joined_at {
+            Some(ts) => format_timestamp(ts),
+            None => "unknown".to_string(),
+        };
+        lines.push(format!(
+            "<@{}> (last active: {}, joined: {})",
+            uid, date_str, joined_str
+        ));
+    }
+
+    let extra = candidates.len().saturating_sub(25);
+    let mut block = lines.join("\n");
+    if extra > 0 {
+        block.push_str(&format!("\nand {} more...", extra));
+    }
+    block
+}
+
+/// Kick the frozen candidate list in the background so the interaction
+/// returns immediately. (MAX_KICKS * KICK_DELAY_MS = 75s >> Discord's 3s)
+fn spawn_kick_loop(
+    http: std::sync::Arc<serenity::all::Http>,
+    pool: crate::db::DbPool,
+    running: RunningCulls,
+    pending: PendingCull,
+) {
+    tokio::spawn(async move {
+        let reason = format!(
+            "Inactive {} days — /cull by {}",
+            pending.criteria.days, pending.moderator_name
+        );
+        let run = NewCullRun {
+            guild_id: pending.guild_id as i64,
+            source: "manual",
+            invoked_by: Some(pending.moderator_id as i64),
+            inactive_days: pending.criteria.days as i32,
+            include_never_posted: pending.criteria.include_never_posted,
+            min_messages: pending.criteria.min_messages.map(|n| n as i32),
+        };
+        kick_candidates(
+            &http,
+            &pool,
+            &running,
+            &run,
+            &pending.candidates,
+            &reason,
+            pending.cat_herding,
+        )
+        .await;
+    });
+}
+
+/// Kick each candidate in turn, recording the run and every outcome in
+/// `cull_runs` (see `/cull history`) and posting progress to cat-herding.
+/// Nobody is kicked if the run can't be recorded. The loop is registered in
+/// `running` while it runs and stops early if `/cull cancel` asks it to.
+async fn kick_candidates(
+    http: &serenity::all::Http,
+    pool: &crate::db::DbPool,
+    running: &RunningCulls,
+    run: &NewCullRun<'_>,
+    candidates: &[u64],
+    reason: &str,
+    cat_herding: Option<ChannelId>,
+) {
+    let user_ids: Vec<i64> = candidates.iter().map(|&uid| uid as i64).collect();
+    let run_id = match insert_cull_run(pool, run, &user_ids) {
+        Ok(run) => run.id,
+        Err(e) => {
+            eprintln!("[cull] Failed to record cull run: {}", e);
+            let msg = "Cull aborted: the run could not be recorded. Nobody was kicked.";
+            post_to_cat_herding(http, cat_herding, msg).await;
+            return;
+        }
+    };
+    let progress = Arc::new(RunningCull::new(
+        run.guild_id as u64,
+        run_id,
+        candidates.len(),
+    ));
+    let _registration = running::register(running, progress.clone());
+
+    let start_msg = format!(
+        "Starting cull #{}: {} candidates (inactive {}+ days). Stop it with `/cull cancel`.",
+        run_id,
+        candidates.len(),
+        run.inactive_days
+    );
+    let _ = post_to_cat_herding(http, cat_herding, &start_msg).await;
+
+    let mut kicked: usize = 0;
+    let mut failed: usize = 0;
+    let mut skipped: usize = 0;
+
+    for &uid in candidates {
+        if progress.cancelled_by().is_some() {
+            break;
+        }
+        let result = http
+            .kick_member((run.guild_id as u64).into(), uid.into(), Some(reason))
+            .await;
+        let (outcome, error) = match result {
+            Ok(_) => {
+                kicked += 1;
+                progress.kicked.fetch_add(1, Ordering::SeqCst);
+                ("kicked", None)
+            }
+            // Left (or was removed) since the candidate list was built
+            Err(e) if is_not_found(&e) => {
+                skipped += 1;
+                ("skipped", None)
+            }
+            Err(e) => {
+                failed += 1;
+                eprintln!("[cull] Failed to kick {}: {}", uid, e);
+                ("failed", Some(e.to_string()))
+            }
+        };
+        if let Err(e) = set_cull_run_outcome(pool, run_id, uid as i64, outcome, error.as_deref()) {
+            eprintln!("[cull] Failed to record outcome for {}: {}", uid, e);
+        }
+        progress.processed.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(std::time::Duration::from_millis(KICK_DELAY_MS)).await;
+    }
+
+    let cancelled_by = progress.cancelled_by();
+    if cancelled_by.is_some() {
+        if let Err(e) = cancel_pending_cull_run_members(pool, run_id) {
+            eprintln!(
+                "[cull] Failed to record cancellation of run {}: {}",
+                run_id, e
+            );
+        }
+    }
+    if let Err(e) = finish_cull_run(pool, run_id, SystemTime::now()) {
+        eprintln!("[cull] Failed to mark cull run {} finished: {}", run_id, e);
+    }
+
+    let summary = match cancelled_by {
+        Some(moderator_id) => {
+            let processed = progress.processed.load(Ordering::SeqCst);
+            format!(
+                "Cull #{} cancelled by <@{}> after {} of {} candidates: {} kicked, {} failed, {} skipped (already gone), {} not reached. Details: `/cull history run:{}`",
+                run_id,
+                moderator_id,
+                processed,
+                candidates.len(),
+                kicked,
+                failed,
+                skipped,
+                candidates.len().saturating_sub(processed),
+                run_id
+            )
+        }
+        None => format!(
+            "Cull #{} complete: {} kicked, {} failed, {} skipped (already gone). Details: `/cull history run:{}`",
+            run_id, kicked, failed, skipped, run_id
+        ),
+    };
+    let _ = post_to_cat_herding(http, cat_herding, &summary).await;
+}
+
+/// Execute-mode culls waiting for the moderator to press Confirm, keyed by
+/// the invoking interaction's ID.
+pub struct PendingCullsKey;
+
+impl TypeMapKey for PendingCullsKey {
+    type Value = Arc<Mutex<HashMap<u64, PendingCull>>>;
+}
+
+async fn pending_culls(ctx: &Context) -> Arc<Mutex<HashMap<u64, PendingCull>>> {
+    let mut data = ctx.data.write().await;
+    data.entry::<PendingCullsKey>().or_default().clone()
+}
+
+/// A previewed cull. `candidates` is frozen at preview time so activity
+/// between preview and confirmation can't change who gets kicked.
+pub struct PendingCull {
+    guild_id: u64,
+    moderator_id: u64,
+    moderator_name: String,
+    criteria: CullCriteria,
+    candidates: Vec<u64>,
+    cat_herding: Option<ChannelId>,
+    created_at: Instant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CullAction {
+    Confirm,
+    Cancel,
+}
+
+fn parse_custom_id(custom_id: &str) -> Option<(CullAction, u64)> {
+    let (action, token) = custom_id.strip_prefix(CUSTOM_ID_PREFIX)?.split_once(':')?;
+    let action = match action {
+        "confirm" => CullAction::Confirm,
+        "cancel" => CullAction::Cancel,
+        _ => return None,
+    };
+    Some((action, token.parse().ok()?))
+}
+
+/// Store a pending cull, dropping any that have timed out.
+fn insert_pending(pending: &Mutex<HashMap<u64, PendingCull>>, token: u64, cull: PendingCull) {
+    let mut pending = pending.lock().unwrap_or_else(|e| e.into_inner());
+    pending.retain(|_, p| p.created_at.elapsed() < CONFIRM_TIMEOUT);
+    pending.insert(token, cull);
+}
+
+/// Claim a pending cull for the moderator who started it. Expired entries
+/// are removed and reported as such.
+fn take_pending(
+    pending: &Mutex<HashMap<u64, PendingCull>>,
+    token: u64,
+    user_id: u64,
+    now: Instant,
+) -> Result<PendingCull, String> {
+    let mut pending = pending.lock().unwrap_or_else(|e| e.into_inner());
+    match pending.get(&token) {
+        None => return Err("This cull has expired or was already handled.".to_string()),
+        Some(cull) if cull.moderator_id != user_id => {
+            return Err("Only the moderator who ran /cull can confirm it.".to_string())
+        }
+        Some(_) => {}
+    }
+    let cull = pending.remove(&token).expect("checked above");
+    if now.duration_since(cull.created_at) >= CONFIRM_TIMEOUT {
+        return Err("This cull has expired. Run /cull again.".to_string());
+    }
+    Ok(cull)
+}
+
+/// Post a message to the guild's cat-herding (moderation log) channel.
+/// Returns false if no channel is configured for this guild.
+async fn post_to_cat_herding(
+    http: &serenity::all::Http,
+    channel_id: Option<ChannelId>,
+    content: &str,
+) -> bool {
+    let Some(channel_id) = channel_id else {
+        eprintln!(
+            "[cull] No cat-herding channel configured, dropping: {}",
+            content
+        );
+        return false;
+    };
+    match channel_id
+        .send_message(http, CreateMessage::new().content(content))
+        .await
+    {
+        Ok(_) => true,
+        Err(e) => {
+            eprintln!("[cull] Failed to post to cat-herding: {}", e);
+            false
+        }
+    }
+}
+
+/// Seed the user_activity table by paginating backwards through message history.
+/// Progress is checkpointed per channel after every page (see
+/// `cull_scan_checkpoints`), so an interrupted scan picks up where it stopped
+/// and channels that were already finished are not read again.
+/// Runs as a background task — returns immediately so we don't exceed Discord's 3s window.
+/// Guilds with a `/cull scan` in progress.
+pub struct ScanningGuildsKey;
+
+impl TypeMapKey for ScanningGuildsKey {
+    type Value = Arc<Mutex<HashSet<u64>>>;
+}
+
+async fn scanning_guilds(ctx: &Context) -> Arc<Mutex<HashSet<u64>>> {
+    let mut data = ctx.data.write().await;
+    data.entry::<ScanningGuildsKey>().or_default().clone()
+}
+
+/// A guild's claim on running a scan, released when dropped so a scan that
+/// panics or returns early can't block the next one.
+struct ScanClaim {
+    scans: Arc<Mutex<HashSet<u64>>>,
+    guild_id: u64,
+}
+
+impl ScanClaim {
+    /// `None` if the guild already has a scan running.
+    fn claim(scans: &Arc<Mutex<HashSet<u64>>>, guild_id: u64) -> Option<ScanClaim> {
+        let claimed = scans
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(guild_id);
+        claimed.then(|| ScanClaim {
+            scans: scans.clone(),
+            guild_id,
+        })
+    }
+}
+
+impl Drop for ScanClaim {
+    fn drop(&mut self) {
+        self.scans
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.guild_id);
+    }
+}
+
+async fn run_scan(
+    http: std::sync::Arc<serenity::all::Http>,
+    pool: &crate::db::DbPool,
+    scans: &Arc<Mutex<HashSet<u64>>>,
+    guild_id: u64,
+    cat_herding: Option<ChannelId>,
+    command: &CommandInteraction,
+    rescan: bool,
+) -> HandlerResponse {
+    let cutoff_secs = scan_cutoff_secs();
+
+    let Some(claim) = ScanClaim::claim(scans, guild_id) else {
+        return HandlerResponse::deferred(
+            "A scan is already running in this server. Check `/cull scan-status` for progress.",
+        );
+    };
+    if rescan {
+        if let Err(e) = delete_cull_scan_checkpoints(pool, guild_id as i64) {
+            eprintln!("[cull] Scan: failed to clear checkpoints: {}", e);
+            return HandlerResponse::deferred(
+                "Failed to clear the previous scan progress. Please try again later.",
+            );
+        }
+    }
+
+    let pool = pool.clone();
+    let user_name = command.user.name.clone();
+
+    tokio::spawn(async move {
+        let _claim = claim;
+        let channels = match scannable_channels(&http, guild_id, cutoff_secs).await {
+            Ok(chs) => chs,
+            Err(e) => {
+                eprintln!(
+                    "[cull] Scan: failed to get channels for guild {}: {}",
+                    guild_id, e
+                );
+                let _ = post_to_cat_herding(
+                    &http,
+                    cat_herding,
+                    &format!("Scan failed: could not fetch channels: {}", e),
+                )
+                .await;
+                return;
+            }
+        };
+
+        let guild_id_i64: i64 = match i64::try_from(guild_id) {
+            Ok(id) => id,
+            Err(e) => {
+                eprintln!("[cull] Scan: guild ID conversion error: {}", e);
+                return;
+            }
+        };
+
+        let mut checkpoints: HashMap<i64, CullScanCheckpoint> =
+            match get_cull_scan_checkpoints(&pool, guild_id_i64) {
+                Ok(rows) => rows.into_iter().map(|c| (c.channel_id, c)).collect(),
+                Err(e) => {
+                    eprintln!("[cull] Scan: failed to load checkpoints: {}", e);
+                    let _ = post_to_cat_herding(
+                        &http,
+                        cat_herding,
+                        &format!("Scan failed: could not load scan progress: {}", e),
+                    )
+                    .await;
+                    return;
+                }
+            };
+
+        let mut users_seen: HashSet<i64> = HashSet::new();
+        let mut total_msg_count = 0u64;
+        let mut already_done = 0usize;
+        let mut no_access = 0usize;
+        let mut unfinished = 0usize;
+
+        for (i, channel) in channels.iter().enumerate() {
+            let channel_id = channel.id.get() as i64;
+            let mut checkpoint =
+                checkpoints
+                    .remove(&channel_id)
+                    .unwrap_or_else(|| CullScanCheckpoint {
+                        guild_id: guild_id_i64,
+                        channel_id,
+                        channel_name: channel.name.clone(),
+                        oldest_message_id: None,
+                        messages_scanned: 0,
+                        completed: false,
+                        updated_at: SystemTime::now(),
+                    });
+            if checkpoint.completed {
+                already_done += 1;
+                continue;
+            }
+            checkpoint.channel_name = channel.name.clone();
+
+            let mut channel_msg_count = 0u64;
+            loop {
+                let before_id = checkpoint
+                    .oldest_message_id
+                    .and_then(|id| u64::try_from(id).ok())
+                    .map(|id| MessagePagination::Before(MessageId::new(id)));
+                let messages = match http.get_messages(channel.id, before_id, Some(100)).await {
+                    Ok(msgs) => msgs,
+                    Err(e) => {
+                        eprintln!(
+                            "[cull] Scan: failed to get messages from channel {}: {}",
+                            channel.name, e
+                        );
+                        if is_forbidden(&e) {
+                            no_access += 1;
+                        } else {
+                            unfinished += 1;
+                        }
+                        break;
+                    }
+                };
+
+                // Messages are returned newest-first; the oldest is last
+                let Some(oldest) = messages.last() else {
+                    checkpoint.completed = true;
+                    save_checkpoint(&pool, &mut checkpoint);
+                    break;
+                };
+
+                // Process this page first (may contain valid messages even if oldest crosses cutoff)
+                // Each author's newest message on the page, so old pages don't look recent
+                let mut newest: HashMap<i64, SystemTime> = HashMap::new();
+                for msg in messages
+                    .iter()
+                    .filter(|msg| !msg.author.bot && msg.webhook_id.is_none())
+                {
+                    let (Ok(user_id), Some(sent_at)) = (
+                        i64::try_from(msg.author.id.get()),
+                        system_time(msg.timestamp),
+                    ) else {
+                        continue;
+                    };
+                    let seen = newest.entry(user_id).or_insert(sent_at);
+                    *seen = (*seen).max(sent_at);
+                }
+                let page_users: Vec<(i64, i64, SystemTime)> = newest
+                    .into_iter()
+                    .map(|(user_id, sent_at)| (user_id, guild_id_i64, sent_at))
+                    .collect();
+                if !page_users.is_empty() {
+                    if let Err(e) = bulk_upsert_activity_at(&pool, page_users.clone()) {
+                        // Leave the checkpoint where it was so this page is read again
+                        eprintln!("[cull] Scan: failed to bulk upsert activity: {}", e);
+                        unfinished += 1;
+                        break;
+                    }
+                }
+                users_seen.extend(page_users.into_iter().map(|(user_id, _, _)| user_id));
+                channel_msg_count += messages.len() as u64;
+
+                checkpoint.oldest_message_id = Some(oldest.id.get() as i64);
+                checkpoint.messages_scanned += messages.len() as i64;
+                checkpoint.completed =
+                    messages.len() < 100 || oldest.timestamp.unix_timestamp() < cutoff_secs;
+                if !save_checkpoint(&pool, &mut checkpoint) {
+                    unfinished += 1;
+                    break;
+                }
+                if checkpoint.completed {
+                    break;
+                }
+
+                tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            }
+
+            total_msg_count += channel_msg_count;
+            eprintln!(
+                "[cull] Scan: {}/{} channels ({} msgs in {}), {} unique users so far",
+                i + 1,
+                channels.len(),
+                channel_msg_count,
+                channel.name,
+                users_seen.len()
+            );
+        }
+
+        let mut msg = format!(
+            "Scan {}: {} unique users seen in {} messages across {} channels",
+            if unfinished == 0 {
+                "complete"
+            } else {
+                "stopped early"
+            },
+            users_seen.len(),
+            total_msg_count,
+            channels.len() - already_done - no_access,
+        );
+        if already_done > 0 {
+            msg.push_str(&format!(
+                " ({} already scanned; use `/cull scan rescan:true` to read them again)",
+                already_done
+            ));
+        }
+        msg.push_str(&format!(". Initiated by {}", user_name));
+        if no_access > 0 {
+            msg.push_str(&format!(
+                "\n{} channel(s) skipped: I can't read their history.",
+                no_access
+            ));
+        }
+        if unfinished > 0 {
+            msg.push_str(&format!(
+                "\n{} channel(s) not finished. Run `/cull scan` again to resume.",
+                unfinished
+            ));
+        }
+        eprintln!("[cull] Scan: {}", msg);
+        let _ = post_to_cat_herding(&http, cat_herding, &msg).await;
+    });
+
+    HandlerResponse::deferred(format!(
+        "Scan started. {} Results will be posted to {}.",
+        if rescan {
+            "Every channel will be read again from the newest message."
+        } else {
+            "Channels scanned before will resume where they stopped."
+        },
+        mention_channel(cat_herding)
+    ))
+}
+
+/// Only messages newer than this (unix seconds) are scanned.
+fn scan_cutoff_secs() -> i64 {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    (now - SCAN_CUTOFF_DAYS * 86400) as i64
+}
+
+/// Everything `/cull scan` reads, sorted by name: text and announcement
+/// channels, active threads, and public threads archived since `cutoff_secs`.
+/// Forum channels have no messages of their own; their posts are threads and
+/// are picked up as such.
+async fn scannable_channels(
+    http: &serenity::all::Http,
+    guild_id: u64,
+    cutoff_secs: i64,
+) -> serenity::Result<Vec<GuildChannel>> {
+    let mut scannable: Vec<GuildChannel> = Vec::new();
+    let mut thread_parents: Vec<ChannelId> = Vec::new();
+    for channel in http.get_channels(guild_id.into()).await? {
+        match channel.kind {
+            ChannelType::Text | ChannelType::News => {
+                thread_parents.push(channel.id);
+                scannable.push(channel);
+            }
+            ChannelType::Forum => thread_parents.push(channel.id),
+            _ => {}
+        }
+    }
+
+    scannable.extend(
+        http.get_guild_active_threads(guild_id.into())
+            .await?
+            .threads,
+    );
+    for parent in thread_parents {
+        match archived_public_threads(http, parent, cutoff_secs).await {
+            Ok(threads) => scannable.extend(threads),
+            // Channels we can't read can't have threads we can read either
+            Err(e) if is_forbidden(&e) => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    let mut seen = HashSet::new();
+    scannable.retain(|ch| seen.insert(ch.id));
+    scannable.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(scannable)
+}
+
+/// Public threads under `channel_id` archived since `cutoff_secs`.
+async fn archived_public_threads(
+    http: &serenity::all::Http,
+    channel_id: ChannelId,
+    cutoff_secs: i64,
+) -> serenity::Result<Vec<GuildChannel>> {
+    let mut threads = Vec::new();
+    let mut before: Option<Timestamp> = None;
+    loop {
+        // Http::get_channel_archived_public_threads sends `before` as an
+        // integer, but Discord expects an ISO8601 timestamp
+        let mut params = vec![("limit", "100".to_string())];
+        if let Some(before) = before {
+            params.push(("before", before.to_string()));
+        }
+        let page: ThreadsData = http
+            .fire(
+                Request::new(
+                    Route::ChannelArchivedPublicThreads { channel_id },
+                    LightMethod::Get,
+                )
+                .params(Some(params)),
+            )
+            .await?;
+
+        // Newest-archived first; anything archived before the cutoff has
+        // no messages inside the scan window
+        let oldest = page
+            .threads
+            .last()
+            .and_then(|t| t.thread_metadata.as_ref())
+            .and_then(|m| m.archive_timestamp);
+        threads.extend(page.threads.into_iter().filter(|t| {
+            t.thread_metadata
+                .and_then(|m| m.archive_timestamp)
+                .is_none_or(|at| at.unix_timestamp() >= cutoff_secs)
+        }));
+
+        match oldest {
+            Some(oldest) if page.has_more && oldest.unix_timestamp() >= cutoff_secs => {
+                before = Some(oldest);
+                tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+            }
+            _ => break,
+        }
+    }
+    Ok(threads)
+}
+
+/// Persist a scan checkpoint. Returns false (and logs) if the write failed.
+fn save_checkpoint(pool: &crate::db::DbPool, checkpoint: &mut CullScanCheckpoint) -> bool {
+    checkpoint.updated_at = SystemTime::now();
+    match upsert_cull_scan_checkpoint(pool, checkpoint) {
+        Ok(_) => true,
+        Err(e) => {
+            eprintln!(
+                "[cull] Scan: failed to save checkpoint for channel {}: {}",
+                checkpoint.channel_name, e
+            );
+            false
+        }
+    }
+}
+
+fn is_forbidden(e: &serenity::Error) -> bool {
+    matches!(e, serenity::Error::Http(http) if http.status_code().map(|s| s.as_u16()) == Some(403))
+}
+
+fn is_not_found(e: &serenity::Error) -> bool {
+    matches!(e, serenity::Error::Http(http) if http.status_code().map(|s| s.as_u16()) == Some(404))
+}
+
+/// Show per-channel `/cull scan` progress.
+async fn scan_status(
+    http: &serenity::all::Http,
+    pool: &crate::db::DbPool,
+    guild_id: u64,
+) -> HandlerResponse {
+    let checkpoints = match get_cull_scan_checkpoints(pool, guild_id as i64) {
+        Ok(rows) => rows,
+        Err(e) => {
+            return HandlerResponse::deferred(format!("Failed to load scan progress: {}", e));
+        }
+    };
+    let channels = match scannable_channels(http, guild_id, scan_cutoff_secs()).await {
+        Ok(chs) => chs,
+        Err(e) => {
+            return HandlerResponse::deferred(format!("Failed to get channels: {}", e));
+        }
+    };
+
+    let channels: Vec<(u64, String)> = channels
+        .into_iter()
+        .map(|ch| (ch.id.get(), ch.name))
+        .collect();
+    HandlerResponse::deferred(format_scan_status(&channels, &checkpoints))
+}
+
+/// One line per channel: done, in progress (with the oldest date reached),
+/// or not started. Checkpoints for channels that no longer exist are ignored.
+fn format_scan_status(channels: &[(u64, String)], checkpoints: &[CullScanCheckpoint]) -> String {
+    let by_channel: HashMap<i64, &CullScanCheckpoint> =
+        checkpoints.iter().map(|c| (c.channel_id, c)).collect();
+
+    let mut done = 0;
+    let mut lines = Vec::new();
+    for (channel_id, name) in channels {
+        let line = match by_channel.get(&(*channel_id as i64)) {
+            Some(c) if c.completed => {
+                done += 1;
+                format!("✅ #{} — {} messages", name, c.messages_scanned)
+            }
+            Some(c) => format!(
+                "⏳ #{} — {} messages, back to {}",
+                name,
+                c.messages_scanned,
+                c.oldest_message_id
+                    .map(|id| format_timestamp(snowflake_time(id)))
+                    .unwrap_or_else(|| "the start".to_string())
+            ),
+            None => format!("▫️ #{} — not started", name),
+        };
+        lines.push(line);
+    }
+
+    let mut output = format!(
+        "**Scan progress:** {}/{} channels complete\n",
+        done,
+        channels.len()
+    );
+    for (i, line) in lines.iter().enumerate() {
+        if output.len() + line.len() > 1900 {
+            output.push_str(&format!("...and {} more", lines.len() - i));
+            break;
+        }
+        output.push_str(line);
+        output.push('\n');
+    }
+    output
+}
+
+/// When a Discord snowflake ID was created.
+fn snowflake_time(id: i64) -> SystemTime {
+    const DISCORD_EPOCH_MS: u64 = 1_420_070_400_000;
+    UNIX_EPOCH + Duration::from_millis(((id as u64) >> 22) + DISCORD_EPOCH_MS)
+}
+
+/// Render a channel mention for user-facing responses.
+fn mention_channel(channel_id: Option<ChannelId>) -> String {
+    match channel_id {
+        Some(id) => format!("<#{}>", id),
+        None => "the moderation log channel (not configured — see /config)".to_string(),
+    }
+}
+
+/// Check if a member has any of the given role IDs.
+fn member_has_any_role_ids(member: &Member, role_ids: &HashSet<u64>) -> bool {
+    member.roles.iter().any(|r| role_ids.contains(&r.get()))
+}
+
+/// Convert SystemTime to YYYY-MM-DD date string.
+fn format_timestamp(ts: SystemTime) -> String {
+    let duration = match ts.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(d) => d,
+        Err(_) => return "unknown".to_string(),
+    };
+    let days = duration.as_secs() as i64 / 86400;
+
+    // Convert days since Unix epoch (1970-01-01) to civil date
+    // Using the algorithm from Howard Hinnant's date library
+    let z = days + 719468;
+    let era = (if z >= 0 { z } else { z - 146096 }) / 146097;
+    let doe = z - era * 146097; // day of era [0, 146096]
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365; // year of era [0, 399]
+    let y = yoe + era * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100); // day of year [0, 365]
+    let mp = (5 * doy + 2) / 153; // month [0, 11]
+    let d = doy - (153 * mp + 2) / 5 + 1; // day [1, 31]
+    let m = if mp < 10 { mp + 3 } else { mp - 9 }; // month [1, 12]
+    let y = if m <= 2 { y + 1 } else { y };
+
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tugbot::guild_config::{DEFAULT_CULL_WHITELIST_ROLES, DEFAULT_MOD_LOG_CHANNEL_ID};
+    use std::time::Duration;
+
+    #[test]
+    fn test_format_timestamp_known_date() {
+        // 2024-01-15 = 19737 days since epoch
+        let ts = SystemTime::UNIX_EPOCH + Duration::from_secs(19737 * 86400);
+        assert_eq!(format_timestamp(ts), "2024-01-15");
+    }
+
+    #[test]
+    fn test_format_timestamp_epoch() {
+        let ts = SystemTime::UNIX_EPOCH;
+        assert_eq!(format_timestamp(ts), "1970-01-01");
+    }
+
+    #[test]
+    fn test_format_timestamp_y2k() {
+        // 2000-01-01 = 10957 days since epoch
+        let ts = SystemTime::UNIX_EPOCH + Duration::from_secs(10957 * 86400);
+        assert_eq!(format_timestamp(ts), "2000-01-01");
+    }
+
+    #[test]
+    fn test_format_timestamp_2025_june() {
+        // 2025-06-15 = 20254 days since epoch
+        let ts = SystemTime::UNIX_EPOCH + Duration::from_secs(20254 * 86400);
+        assert_eq!(format_timestamp(ts), "2025-06-15");
+    }
+
+    #[test]
+    fn test_format_timestamp_feb_leap_year() {
+        // 2024-02-29 (leap year) = 19782 days since epoch
+        let ts = SystemTime::UNIX_EPOCH + Duration::from_secs(19782 * 86400);
+        assert_eq!(format_timestamp(ts), "2024-02-29");
+    }
+
+    #[test]
+    fn test_max_kicks_constant() {
+        assert_eq!(MAX_KICKS, 50);
+    }
+
+    #[test]
+    fn test_kick_delay_constant() {
+        assert_eq!(KICK_DELAY_MS, 1500);
+    }
+
+    #[test]
+    fn test_cat_herding_channel_id() {
+        assert_eq!(DEFAULT_MOD_LOG_CHANNEL_ID, 1224402885786472659);
+    }
+
+    #[test]
+    fn test_whitelist_roles() {
+        assert!(DEFAULT_CULL_WHITELIST_ROLES.contains(&"Highly Regarded"));
+        assert!(DEFAULT_CULL_WHITELIST_ROLES.contains(&"admin"));
+    }
+
+    #[test]
+    fn test_mention_channel() {
+        assert_eq!(mention_channel(Some(ChannelId::new(42))), "<#42>");
+        assert!(mention_channel(None).contains("/config"));
+    }
+
+    #[test]
+    fn test_execute_mode_response_starts_with_cull_started() {
+        // Confirming must return immediately with "Cull started" message
+        // (not "Cull complete" which would indicate blocking behavior)
+        let cat_id = DEFAULT_MOD_LOG_CHANNEL_ID;
+        let candidate_count = 10;
+        let expected_prefix = format!("Cull started: {} candidates.", candidate_count);
+        let expected_suffix = format!("Results will be posted to <#{}>", cat_id);
+        // Verify the message format matches the non-blocking pattern
+        let msg = format!(
+            "Cull started: {} candidates. Results will be posted to <#{}>.",
+            candidate_count, cat_id
+        );
+        assert!(msg.starts_with(&expected_prefix));
+        assert!(msg.contains(&expected_suffix));
+    }
+
+    #[test]
+    fn test_execute_mode_max_kicks_would_block() {
+        // Verify that MAX_KICKS * KICK_DELAY_MS would exceed Discord's 3s window
+        let worst_case_ms = MAX_KICKS as u64 * KICK_DELAY_MS;
+        let discord_response_window_ms = 3000;
+        assert!(
+            worst_case_ms > discord_response_window_ms,
+            "worst case {}ms exceeds Discord's {}ms window — must spawn background task",
+            worst_case_ms,
+            discord_response_window_ms
+        );
+    }
+
+    fn pending(moderator_id: u64, created_at: Instant) -> PendingCull {
+        PendingCull {
+            guild_id: 1,
+            moderator_id,
+            moderator_name: "mod".to_string(),
+            criteria: CullCriteria {
+                days: 30,
+                include_never_posted: false,
+                min_messages: None,
+            },
+            candidates: vec![10, 11],
+            cat_herding: None,
+            created_at,
+        }
+    }
+
+    #[test]
+    fn test_parse_custom_id() {
+        assert_eq!(
+            parse_custom_id("cull:confirm:42"),
+            Some((CullAction::Confirm, 42))
+        );
+        assert_eq!(
+            parse_custom_id("cull:cancel:42"),
+            Some((CullAction::Cancel, 42))
+        );
+        assert_eq!(parse_custom_id("cull:kick:42"), None);
+        assert_eq!(parse_custom_id("gulag-appeal:approve:42"), None);
+    }
+
+    #[test]
+    fn test_take_pending_only_for_invoker_and_once() {
+        let map = Mutex::new(HashMap::new());
+        let now = Instant::now();
+        insert_pending(&map, 7, pending(100, now));
+
+        assert!(take_pending(&map, 7, 200, now).is_err());
+        let cull = take_pending(&map, 7, 100, now).unwrap();
+        assert_eq!(cull.candidates, vec![10, 11]);
+        assert!(take_pending(&map, 7, 100, now).is_err());
+    }
+
+    #[test]
+    fn test_take_pending_expires() {
+        let map = Mutex::new(HashMap::new());
+        let now = Instant::now();
+        insert_pending(&map, 7, pending(100, now));
+
+        let later = now + CONFIRM_TIMEOUT + Duration::from_secs(1);
+        assert!(take_pending(&map, 7, 100, later)
+            .err()
+            .unwrap()
+            .contains("expired"));
+        assert!(map.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_format_candidates_truncates() {
+        let joined = SystemTime::UNIX_EPOCH + Duration::from_secs(19000 * 86400);
+        let candidates: Vec<Candidate> = (1..=30)
+            .map(|user_id| Candidate {
+                user_id,
+                joined_at: (user_id == 1).then_some(joined),
+            })
+            .collect();
+        let mut activity = HashMap::new();
+        activity.insert(
+            1i64,
+            SystemTime::UNIX_EPOCH + Duration::from_secs(19737 * 86400),
+        );
+        let block = format_candidates(&candidates, &activity);
+        assert!(block.starts_with(
+            "<@1> (last active: 2024-01-15, joined: 2022-01-08)\n<@2> (last active: never posted, joined: unknown)"
+        ));
+        assert!(block.ends_with("and 5 more..."));
+        assert_eq!(block.lines().count(), 26);
+    }
+
+    #[test]
+    fn test_in_grace_period() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(19737 * 86400);
+        let grace = Duration::from_secs(14 * 86400);
+        let days_ago = |d: u64| Some(now - Duration::from_secs(d * 86400));
+        assert!(in_grace_period(days_ago(1), grace, now));
+        assert!(!in_grace_period(days_ago(14), grace, now));
+        assert!(!in_grace_period(days_ago(90), grace, now));
+        assert!(!in_grace_period(None, grace, now));
+        assert!(!in_grace_period(days_ago(1), Duration::ZERO, now));
+    }
+
+    #[test]
+    fn test_snowflake_time() {
+        // Example from Discord's API reference docs
+        assert_eq!(
+            format_timestamp(snowflake_time(175928847299117063)),
+            "2016-04-30"
+        );
+    }
+
+    fn checkpoint(channel_id: i64, completed: bool) -> CullScanCheckpoint {
+        CullScanCheckpoint {
+            guild_id: 1,
+            channel_id,
+            channel_name: "old-name".to_string(),
+            oldest_message_id: Some(175928847299117063),
+            messages_scanned: 1200,
+            completed,
+            updated_at: SystemTime::now(),
+        }
+    }
+
+    #[test]
+    fn scan_claims_are_per_guild_and_released_on_drop() {
+        let scans = Arc::new(Mutex::new(HashSet::new()));
+        let first = ScanClaim::claim(&scans, 1).expect("first scan claims the guild");
+        assert!(ScanClaim::claim(&scans, 1).is_none());
+        let _other = ScanClaim::claim(&scans, 2).expect("other guilds are independent");
+
+        drop(first);
+        assert!(ScanClaim::claim(&scans, 1).is_some());
+    }
+
+    #[test]
+    fn test_format_scan_status() {
+        let channels = vec![
+            (10, "general".to_string()),
+            (11, "memes".to_string()),
+            (12, "new".to_string()),
+        ];
+        // 99 is a checkpoint for a deleted channel and is ignored
+        let checkpoints = vec![
+            checkpoint(10, true),
+            checkpoint(11, false),
+            checkpoint(99, true),
+        ];
+        let status = format_scan_status(&channels, &checkpoints);
+        assert_eq!(
+            status,
+            "**Scan progress:** 1/3 channels complete\n\
+             ✅ #general — 1200 messages\n\
+             ⏳ #memes — 1200 messages, back to 2016-04-30\n\
+             ▫️ #new — not started\n"
+        );
+    }
+
+    #[test]
+    fn test_cull_criteria_describe_and_run_command() {
+        let mut criteria = CullCriteria {
+            days: 45,
+            include_never_posted: false,
+            min_messages: None,
+        };
+        assert_eq!(criteria.describe(), "inactive 45+ days, never posted: no");
+        assert_eq!(criteria.run_command(), "/cull run days:45");
+
+        criteria.include_never_posted = true;
+        criteria.min_messages = Some(5);
+        assert_eq!(
+            criteria.describe(),
+            "inactive 45+ days, never posted: yes, or fewer than 5 messages in 30 days"
+        );
+        assert_eq!(
+            criteria.run_command(),
+            "/cull run days:45 include-never-posted:True min-messages:5"
+        );
+    }
+}
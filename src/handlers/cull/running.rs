@@ -0,0 +1,198 @@
+//! Kick loops in progress, and `/cull cancel` to stop them. Every kick loop
+//! (manual or scheduled) registers itself in [`RunningCullsKey`] for as
+//! long as it runs and checks for cancellation before each kick.
+
+use super::CullHandler;
+use crate::handlers::HandlerResponse;
+use serenity::{
+    all::{CommandInteraction, CommandOptionType},
+    builder::CreateCommandOption,
+    client::Context,
+    prelude::TypeMapKey,
+};
+use std::collections::HashMap;
+use std::sync::{
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+pub type RunningCulls = Arc<Mutex<HashMap<i64, Arc<RunningCull>>>>;
+
+/// Kick loops in progress, keyed by cull run ID.
+pub struct RunningCullsKey;
+
+impl TypeMapKey for RunningCullsKey {
+    type Value = RunningCulls;
+}
+
+pub async fn running_culls(ctx: &Context) -> RunningCulls {
+    let mut data = ctx.data.write().await;
+    data.entry::<RunningCullsKey>().or_default().clone()
+}
+
+/// Progress of one kick loop, shared between the loop and `/cull cancel`.
+pub struct RunningCull {
+    pub guild_id: u64,
+    pub run_id: i64,
+    pub total: usize,
+    /// Candidates the loop has finished with, whatever the outcome.
+    pub processed: AtomicUsize,
+    pub kicked: AtomicUsize,
+    /// Moderator who cancelled the run; 0 while it should keep going.
+    cancelled_by: AtomicU64,
+}
+
+impl RunningCull {
+    pub fn new(guild_id: u64, run_id: i64, total: usize) -> RunningCull {
+        RunningCull {
+            guild_id,
+            run_id,
+            total,
+            processed: AtomicUsize::new(0),
+            kicked: AtomicUsize::new(0),
+            cancelled_by: AtomicU64::new(0),
+        }
+    }
+
+    pub fn cancel(&self, moderator_id: u64) {
+        self.cancelled_by.store(moderator_id, Ordering::SeqCst);
+    }
+
+    pub fn cancelled_by(&self) -> Option<u64> {
+        match self.cancelled_by.load(Ordering::SeqCst) {
+            0 => None,
+            id => Some(id),
+        }
+    }
+}
+
+/// Add a kick loop to the registry. It stays there until the returned guard
+/// is dropped, so a loop that panics or returns early can't linger.
+pub fn register(running: &RunningCulls, cull: Arc<RunningCull>) -> Registration {
+    running
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(cull.run_id, cull.clone());
+    Registration {
+        running: running.clone(),
+        run_id: cull.run_id,
+    }
+}
+
+pub struct Registration {
+    running: RunningCulls,
+    run_id: i64,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.run_id);
+    }
+}
+
+/// Ask every kick loop in the guild to stop after its current kick. Returns
+/// each loop still running, oldest run first, with whoever had already
+/// cancelled it (`None` if this call did).
+fn cancel_guild(
+    running: &RunningCulls,
+    guild_id: u64,
+    moderator_id: u64,
+) -> Vec<(Arc<RunningCull>, Option<u64>)> {
+    let running = running.lock().unwrap_or_else(|e| e.into_inner());
+    let mut culls: Vec<Arc<RunningCull>> = running
+        .values()
+        .filter(|cull| cull.guild_id == guild_id)
+        .cloned()
+        .collect();
+    culls.sort_by_key(|cull| cull.run_id);
+    culls
+        .into_iter()
+        .map(|cull| {
+            let earlier = cull.cancelled_by();
+            if earlier.is_none() {
+                cull.cancel(moderator_id);
+            }
+            (cull, earlier)
+        })
+        .collect()
+}
+
+impl CullHandler {
+    pub fn cancel_command() -> CreateCommandOption {
+        CreateCommandOption::new(
+            CommandOptionType::SubCommand,
+            "cancel",
+            "Stop a running cull after the current kick",
+        )
+    }
+
+    /// Handle `/cull cancel`. The kick loop itself posts the final tally to
+    /// cat-herding once it stops.
+    pub async fn cancel(
+        ctx: &Context,
+        guild_id: u64,
+        command: &CommandInteraction,
+    ) -> HandlerResponse {
+        let running = running_culls(ctx).await;
+        let cancelled = cancel_guild(&running, guild_id, command.user.id.get());
+
+        let content = if cancelled.is_empty() {
+            "No cull is running.".to_string()
+        } else {
+            let lines: Vec<String> = cancelled
+                .iter()
+                .map(|(cull, earlier)| match earlier {
+                    Some(moderator_id) => format!(
+                        "Cull #{} is already stopping (cancelled by <@{}>): {} of {} candidates processed so far ({} kicked).",
+                        cull.run_id,
+                        moderator_id,
+                        cull.processed.load(Ordering::SeqCst),
+                        cull.total,
+                        cull.kicked.load(Ordering::SeqCst),
+                    ),
+                    None => format!(
+                        "Cancelling cull #{}: {} of {} candidates processed so far ({} kicked). It will stop after the current kick.",
+                        cull.run_id,
+                        cull.processed.load(Ordering::SeqCst),
+                        cull.total,
+                        cull.kicked.load(Ordering::SeqCst),
+                    ),
+                })
+                .collect();
+            lines.join("\n")
+        };
+        HandlerResponse::deferred(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_guild_only_touches_that_guild_and_registration_cleans_up() {
+        let running = RunningCulls::default();
+        let ours = Arc::new(RunningCull::new(1, 7, 10));
+        let theirs = Arc::new(RunningCull::new(2, 8, 10));
+        let ours_guard = register(&running, ours.clone());
+        let _theirs_guard = register(&running, theirs.clone());
+
+        let cancelled = cancel_guild(&running, 1, 42);
+        assert_eq!(cancelled.len(), 1);
+        assert_eq!(cancelled[0].1, None);
+        assert_eq!(ours.cancelled_by(), Some(42));
+        assert_eq!(theirs.cancelled_by(), None);
+        // Already cancelled: reported as stopping, not re-cancelled
+        let again = cancel_guild(&running, 1, 43);
+        assert_eq!(again.len(), 1);
+        assert_eq!(again[0].1, Some(42));
+        assert_eq!(ours.cancelled_by(), Some(42));
+
+        drop(ours_guard);
+        assert!(!running.lock().unwrap().contains_key(&7));
+        assert!(running.lock().unwrap().contains_key(&8));
+    }
+}
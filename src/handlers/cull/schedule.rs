@@ -0,0 +1,355 @@
+//! Scheduled culls, configured with `/config cull-schedule`.
+//!
+//! A schedule runs in rounds. When a round is due, everyone a cull would
+//! target is warned (by DM, or with a mention in the warning channel) and
+//! given `warning_days` to post. At the deadline, warned members who are
+//! still candidates are kicked. Anyone who posted in the meantime is no
+//! longer inactive, so they drop out without special handling.
+
+use super::{
+    find_candidates, format_candidates, format_timestamp, kick_candidates, post_to_cat_herding,
+    running::RunningCulls, Candidate, CullCriteria, CullHandler, MAX_KICKS,
+};
+use crate::db::{
+    close_cull_warnings, get_cull_warnings, get_due_cull_schedules,
+    models::{CullSchedule, NewCullRun},
+    open_cull_warnings, query_user_activity_for_ids, DbPool,
+};
+use crate::features::Features;
+use crate::tugbot::guild_config::GuildConfig;
+use serenity::all::{ChannelId, CreateMessage, Http, UserId};
+use std::collections::{HashMap, HashSet};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::time::{Duration, SystemTime};
+
+/// How often due schedules are looked for.
+const CHECK_INTERVAL: Duration = Duration::from_secs(600);
+/// Pause between warning DMs.
+const DM_DELAY: Duration = Duration::from_secs(1);
+
+const DAY: u64 = 86_400;
+
+/// A disabled schedule with the defaults `/config cull-schedule` starts from.
+pub fn new_schedule(guild_id: u64, now: SystemTime) -> CullSchedule {
+    CullSchedule {
+        guild_id: guild_id as i64,
+        enabled: false,
+        inactive_days: 30,
+        interval_days: 30,
+        warning_days: 7,
+        include_never_posted: false,
+        warning_channel_id: None,
+        next_run_at: now,
+        deadline_at: None,
+        updated_at: now,
+    }
+}
+
+/// One-paragraph summary for `/config cull-schedule`.
+pub fn describe_schedule(schedule: &CullSchedule) -> String {
+    let warn_via = match schedule.warning_channel_id {
+        Some(id) => format!("a mention in <#{}>", id),
+        None => "DM".to_string(),
+    };
+    let mut description = format!(
+        "**Scheduled cull: {}**\nEvery {} days, members inactive {}+ days (never posted: {}) are warned by {} and kicked {} days later unless they post.",
+        if schedule.enabled { "on" } else { "off" },
+        schedule.interval_days,
+        schedule.inactive_days,
+        if schedule.include_never_posted { "yes" } else { "no" },
+        warn_via,
+        schedule.warning_days,
+    );
+    if schedule.enabled {
+        description.push_str(&match schedule.deadline_at {
+            Some(deadline) => format!(
+                "\nWarnings are out; kicks happen on {}.",
+                format_timestamp(deadline)
+            ),
+            None => format!(
+                "\nNext warning round: {}.",
+                format_timestamp(schedule.next_run_at)
+            ),
+        });
+    }
+    description
+}
+
+/// When the round after the current one starts: one interval after this
+/// round started, but never in the past (e.g. after downtime).
+fn next_run_at(schedule: &CullSchedule, now: SystemTime) -> SystemTime {
+    let next = schedule.next_run_at + Duration::from_secs(schedule.interval_days as u64 * DAY);
+    next.max(now)
+}
+
+fn warning_text(guild_name: Option<&str>, days: i32, deadline: SystemTime) -> String {
+    let place = match guild_name {
+        Some(name) => format!("in **{}**", name),
+        None => "here".to_string(),
+    };
+    format!(
+        "You haven't posted {} in {}+ days. Inactive members will be removed on {}. Send a message before then to stay.",
+        place,
+        days,
+        format_timestamp(deadline)
+    )
+}
+
+impl CullHandler {
+    /// Run due cull schedules every [`CHECK_INTERVAL`] for the life of the
+    /// process. Safe to call on every `ready`; only the first call starts
+    /// the loop.
+    pub fn run_schedule_check(http: &Arc<Http>, pool: DbPool, running: RunningCulls) {
+        static STARTED: AtomicBool = AtomicBool::new(false);
+        if STARTED.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let http = Arc::clone(http);
+        tokio::spawn(async move {
+            loop {
+                match get_due_cull_schedules(&pool, SystemTime::now()) {
+                    Ok(due) => {
+                        for schedule in due {
+                            if let Err(e) =
+                                run_due_schedule(&http, &pool, &running, &schedule).await
+                            {
+                                eprintln!(
+                                    "[cull] Scheduled cull for guild {} failed: {}",
+                                    schedule.guild_id, e
+                                );
+                            }
+                        }
+                    }
+                    Err(e) => eprintln!("[cull] Failed to load due cull schedules: {}", e),
+                }
+                tokio::time::sleep(CHECK_INTERVAL).await;
+            }
+        });
+    }
+}
+
+async fn run_due_schedule(
+    http: &Http,
+    pool: &DbPool,
+    running: &RunningCulls,
+    schedule: &CullSchedule,
+) -> Result<(), String> {
+    let guild_id = schedule.guild_id as u64;
+    // Left due while the feature is off; picked up again once it's back on
+    if !Features::is_enabled(pool, Some(guild_id), "cull") {
+        return Ok(());
+    }
+
+    let guild_config = GuildConfig::load(pool, guild_id);
+    let cat_herding = guild_config.mod_log_channel(http).await;
+    let criteria = CullCriteria {
+        days: schedule.inactive_days as i64,
+        include_never_posted: schedule.include_never_posted,
+        min_messages: None,
+    };
+    let candidates =
+        find_candidates(http, pool, &guild_config, guild_id, &criteria, cat_herding).await?;
+
+    match schedule.deadline_at {
+        None => start_round(http, pool, schedule, candidates, cat_herding).await,
+        Some(_) => finish_round(http, pool, running, schedule, candidates, cat_herding).await,
+    }
+}
+
+/// Record and send warnings to this round's candidates.
+async fn start_round(
+    http: &Http,
+    pool: &DbPool,
+    schedule: &CullSchedule,
+    mut candidates: Vec<Candidate>,
+    cat_herding: Option<ChannelId>,
+) -> Result<(), String> {
+    let now = SystemTime::now();
+    candidates.truncate(MAX_KICKS);
+
+    if candidates.is_empty() {
+        let next = next_run_at(schedule, now);
+        close_cull_warnings(pool, schedule.guild_id, next).map_err(|e| e.to_string())?;
+        let msg = format!(
+            "Scheduled cull: no members inactive {}+ days. Next check on {}.",
+            schedule.inactive_days,
+            format_timestamp(next)
+        );
+        post_to_cat_herding(http, cat_herding, &msg).await;
+        return Ok(());
+    }
+
+    // Persist before messaging anyone, so a crash can't leave members warned
+    // without a deadline
+    let deadline = now + Duration::from_secs(schedule.warning_days as u64 * DAY);
+    let user_ids: Vec<i64> = candidates.iter().map(|c| c.user_id as i64).collect();
+    open_cull_warnings(pool, schedule.guild_id, &user_ids, now, deadline)
+        .map_err(|e| e.to_string())?;
+
+    let recipients: Vec<u64> = candidates.iter().map(|c| c.user_id).collect();
+    let undelivered = send_warnings(http, schedule, &recipients, deadline).await;
+
+    let activity_map: HashMap<i64, SystemTime> =
+        query_user_activity_for_ids(pool, schedule.guild_id, user_ids)
+            .map(|rows| {
+                rows.into_iter()
+                    .map(|a| (a.user_id, a.last_message_at))
+                    .collect()
+            })
+            .unwrap_or_default();
+    let mut msg = format!(
+        "**Scheduled cull:** warned {} members inactive {}+ days. Anyone who hasn't posted by {} will be kicked.\n\n{}",
+        candidates.len(),
+        schedule.inactive_days,
+        format_timestamp(deadline),
+        format_candidates(&candidates, &activity_map)
+    );
+    if undelivered > 0 {
+        msg.push_str(&format!(
+            "\n\n{} warning(s) could not be delivered.",
+            undelivered
+        ));
+    }
+    post_to_cat_herding(http, cat_herding, &msg).await;
+    Ok(())
+}
+
+/// DM each candidate, or mention them all in the warning channel. Returns
+/// how many candidates the warning did not reach.
+async fn send_warnings(
+    http: &Http,
+    schedule: &CullSchedule,
+    candidates: &[u64],
+    deadline: SystemTime,
+) -> usize {
+    if let Some(channel_id) = schedule.warning_channel_id {
+        let mentions: Vec<String> = candidates.iter().map(|uid| format!("<@{}>", uid)).collect();
+        let content = format!(
+            "{}\n{}",
+            mentions.join(" "),
+            warning_text(None, schedule.inactive_days, deadline)
+        );
+        return match ChannelId::new(channel_id as u64)
+            .send_message(http, CreateMessage::new().content(content))
+            .await
+        {
+            Ok(_) => 0,
+            Err(e) => {
+                eprintln!("[cull] Failed to post cull warning: {}", e);
+                candidates.len()
+            }
+        };
+    }
+
+    let guild_name = http
+        .get_guild((schedule.guild_id as u64).into())
+        .await
+        .map(|g| g.name)
+        .ok();
+    let content = warning_text(guild_name.as_deref(), schedule.inactive_days, deadline);
+    let mut undelivered = 0;
+    for &uid in candidates {
+        if let Err(e) = UserId::new(uid)
+            .direct_message(http, CreateMessage::new().content(&content))
+            .await
+        {
+            eprintln!("[cull] Failed to DM cull warning to {}: {}", uid, e);
+            undelivered += 1;
+        }
+        tokio::time::sleep(DM_DELAY).await;
+    }
+    undelivered
+}
+
+/// Kick warned members who are still candidates, then schedule the next round.
+async fn finish_round(
+    http: &Http,
+    pool: &DbPool,
+    running: &RunningCulls,
+    schedule: &CullSchedule,
+    candidates: Vec<Candidate>,
+    cat_herding: Option<ChannelId>,
+) -> Result<(), String> {
+    let warned: HashSet<u64> = get_cull_warnings(pool, schedule.guild_id)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|w| w.user_id as u64)
+        .collect();
+    let to_kick: Vec<u64> = candidates
+        .into_iter()
+        .map(|c| c.user_id)
+        .filter(|uid| warned.contains(uid))
+        .collect();
+
+    // Close the round before kicking, so a crash mid-loop can't kick twice
+    let next = next_run_at(schedule, SystemTime::now());
+    close_cull_warnings(pool, schedule.guild_id, next).map_err(|e| e.to_string())?;
+
+    let msg = format!(
+        "**Scheduled cull deadline:** {} of {} warned members posted, left or became exempt. Next warning round on {}.",
+        warned.len() - to_kick.len(),
+        warned.len(),
+        format_timestamp(next)
+    );
+    post_to_cat_herding(http, cat_herding, &msg).await;
+
+    if !to_kick.is_empty() {
+        let reason = format!("Inactive {} days — scheduled cull", schedule.inactive_days);
+        let run = NewCullRun {
+            guild_id: schedule.guild_id,
+            source: "scheduled",
+            invoked_by: None,
+            inactive_days: schedule.inactive_days,
+            include_never_posted: schedule.include_never_posted,
+            min_messages: None,
+        };
+        kick_candidates(http, pool, running, &run, &to_kick, &reason, cat_herding).await;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at_day(day: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(day * DAY)
+    }
+
+    #[test]
+    fn next_run_keeps_cadence_but_never_goes_back() {
+        let mut schedule = new_schedule(1, at_day(19737));
+        schedule.interval_days = 30;
+        assert_eq!(next_run_at(&schedule, at_day(19744)), at_day(19767));
+        // Bot was down past the next run
+        assert_eq!(next_run_at(&schedule, at_day(19800)), at_day(19800));
+    }
+
+    #[test]
+    fn describe_reflects_round_state() {
+        let mut schedule = new_schedule(1, at_day(19737));
+        assert!(describe_schedule(&schedule).starts_with("**Scheduled cull: off**"));
+
+        schedule.enabled = true;
+        schedule.warning_channel_id = Some(42);
+        let description = describe_schedule(&schedule);
+        assert!(description.contains("warned by a mention in <#42>"));
+        assert!(description.ends_with("Next warning round: 2024-01-15."));
+
+        schedule.deadline_at = Some(at_day(19744));
+        assert!(describe_schedule(&schedule).ends_with("kicks happen on 2024-01-22."));
+    }
+
+    #[test]
+    fn warning_text_names_guild_in_dms() {
+        assert_eq!(
+            warning_text(Some("Tug"), 30, at_day(19744)),
+            "You haven't posted in **Tug** in 30+ days. Inactive members will be removed on 2024-01-22. Send a message before then to stay."
+        );
+        assert!(warning_text(None, 30, at_day(19744)).starts_with("You haven't posted here"));
+    }
+}
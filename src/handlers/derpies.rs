@@ -12,7 +12,7 @@ pub struct Derpies;
 impl Derpies {
     pub async fn message_handler(ctx: &Context, msg: &Message) {
         let pool = get_pool(ctx).await;
-        if !Features::is_enabled(&pool, "derpies") {
+        if !Features::is_enabled(&pool, msg.guild_id.map(|g| g.get()), "derpies") {
             return;
         }
 
@@ -32,7 +32,7 @@ impl Derpies {
 
     pub async fn reaction_add_handler(ctx: &Context, add_reaction: &Reaction) {
         let pool = get_pool(ctx).await;
-        if !Features::is_enabled(&pool, "derpies") {
+        if !Features::is_enabled(&pool, add_reaction.guild_id.map(|g| g.get()), "derpies") {
             return;
         }
 
@@ -5,14 +5,17 @@ use serenity::{
 };
 
 use crate::features::Features;
-use crate::handlers::{get_pool, gulag::Gulag};
+use crate::handlers::{
+    get_pool,
+    gulag::{Gulag, GulagSource},
+};
 
 pub struct Elon;
 
 impl Elon {
     pub async fn handler(ctx: &Context, msg: &Message) {
         let pool = get_pool(ctx).await;
-        if !Features::is_enabled(&pool, "elon") {
+        if !Features::is_enabled(&pool, msg.guild_id.map(|g| g.get()), "elon") {
             return;
         }
 
@@ -36,7 +39,7 @@ impl Elon {
                             member.user.id.get(),
                             channelid,
                             msg.id.get(),
-                            None,
+                            GulagSource::Elon,
                         )
                         .await
                         {
@@ -12,7 +12,7 @@ pub struct Feat;
 impl Feat {
     pub fn setup_command() -> CreateCommand {
         CreateCommand::new("feature")
-            .description("Toggle Feature")
+            .description("Toggle Feature for this server")
             .add_option(
                 CreateCommandOption::new(
                     CommandOptionType::String,
@@ -28,18 +28,25 @@ impl Feat {
         command: &CommandInteraction,
     ) -> HandlerResponse {
         let pool = get_pool(ctx).await;
+        let guild_id = match command.guild_id {
+            Some(id) => id.get(),
+            None => {
+                return Feat::handle_error("This command can only be used in a server".to_string())
+            }
+        };
+
         match command.data.options.first() {
             Some(feature_option_value) => {
                 if let CommandDataOptionValue::String(feature_name) = &feature_option_value.value {
-                    match features::Features::all(&pool) {
-                        Ok(f) => Feat::handle_feature(&pool, f, feature_name),
+                    match features::Features::all(&pool, Some(guild_id)) {
+                        Ok(f) => Feat::handle_feature(&pool, guild_id, f, feature_name),
                         Err(e) => Feat::handle_error(e.to_string()),
                     }
                 } else {
                     Feat::handle_error("Please provide a valid feature name".to_string())
                 }
             }
-            None => match features::Features::all(&pool) {
+            None => match features::Features::all(&pool, Some(guild_id)) {
                 Ok(features) => Feat::handle_list_features(features),
                 Err(e) => Feat::handle_error(e.to_string()),
             },
@@ -48,15 +55,18 @@ impl Feat {
 
     fn handle_feature(
         pool: &crate::db::DbPool,
+        guild_id: u64,
         features: Vec<models::Features>,
         feature_name: &String,
     ) -> HandlerResponse {
         for feat in features {
             if feat.name == *feature_name {
-                if let Err(e) = features::Features::update(pool, &feat.name, !feat.enabled) {
+                if let Err(e) =
+                    features::Features::update(pool, guild_id, &feat.name, !feat.enabled)
+                {
                     return Self::handle_error(format!("Failed to update feature: {}", e));
                 }
-                return match features::Features::all(pool) {
+                return match features::Features::all(pool, Some(guild_id)) {
                     Ok(f) => Self::handle_list_features(f),
                     Err(e) => Self::handle_error(e.to_string()),
                 };
@@ -72,12 +82,17 @@ impl Feat {
     }
 
     fn handle_list_features(features: Vec<models::Features>) -> HandlerResponse {
-        let mut content = "Here's all the features".to_string();
+        let mut content = "Here's all the features for this server".to_string();
 
         for feature in features {
+            let scope = if feature.guild_id == features::GLOBAL_GUILD_ID {
+                "default"
+            } else {
+                "server"
+            };
             content = format!(
-                "{}\nName: `{}` Enabled: `{}`",
-                content, feature.name, feature.enabled
+                "{}\nName: `{}` Enabled: `{}` ({})",
+                content, feature.name, feature.enabled, scope
             );
         }
 
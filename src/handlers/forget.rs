@@ -0,0 +1,99 @@
+//! `/forget`: reset tugbot's conversation memory. In a thread it forgets the
+//! thread's conversation; in the ask channel it forgets the reply-chain
+//! conversations the caller started there. Either way it also forgets the
+//! caller's questions, and the answers to them, in every other conversation.
+
+use serenity::{all::CommandInteraction, builder::CreateCommand, client::Context};
+
+use crate::db::{forget_conversation_turns, forget_conversations};
+
+use super::{get_pool, HandlerResponse};
+
+pub struct ForgetHandler;
+
+impl ForgetHandler {
+    pub fn setup_command() -> CreateCommand {
+        CreateCommand::new("forget").description(
+            "Make tugbot forget your conversation with it here and your questions elsewhere",
+        )
+    }
+
+    pub async fn setup_interaction(ctx: &Context, command: &CommandInteraction) -> HandlerResponse {
+        let guild_id = match command.guild_id {
+            Some(id) => id.get() as i64,
+            None => return HandlerResponse::ephemeral("This command can only be used in a server"),
+        };
+        let user_id = command.user.id.get() as i64;
+        let pool = get_pool(ctx).await;
+
+        let forgotten =
+            forget_conversations(&pool, guild_id, command.channel_id.get() as i64, user_id)
+                .and_then(|conversations| {
+                    let questions = forget_conversation_turns(&pool, guild_id, user_id)?;
+                    Ok((conversations, questions))
+                });
+        match forgotten {
+            Ok((conversations, questions)) => {
+                HandlerResponse::ephemeral(Self::format_forgotten(conversations, questions))
+            }
+            Err(e) => {
+                eprintln!("[forget] Failed to delete conversations: {}", e);
+                HandlerResponse::ephemeral(
+                    "Failed to forget the conversation. Please try again later.",
+                )
+            }
+        }
+    }
+
+    fn format_forgotten(conversations: usize, questions: usize) -> String {
+        let here = match conversations {
+            0 => None,
+            1 => Some("Forgotten. My next answer here starts fresh.".to_string()),
+            n => Some(format!(
+                "Forgot {} conversations. My next answer here starts fresh.",
+                n
+            )),
+        };
+        let elsewhere = match questions {
+            0 => None,
+            1 => Some("Forgot your question in another conversation.".to_string()),
+            n => Some(format!(
+                "Forgot your {} questions in other conversations.",
+                n
+            )),
+        };
+        match (here, elsewhere) {
+            (None, None) => "There's no conversation to forget here.".to_string(),
+            (Some(here), Some(elsewhere)) => format!("{} {}", here, elsewhere),
+            (Some(reply), None) | (None, Some(reply)) => reply,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_forgotten_counts_conversations() {
+        assert_eq!(
+            ForgetHandler::format_forgotten(0, 0),
+            "There's no conversation to forget here."
+        );
+        assert!(ForgetHandler::format_forgotten(1, 0).starts_with("Forgotten."));
+        assert!(ForgetHandler::format_forgotten(3, 0).starts_with("Forgot 3 conversations."));
+    }
+
+    #[test]
+    fn format_forgotten_counts_questions_elsewhere() {
+        assert_eq!(
+            ForgetHandler::format_forgotten(0, 1),
+            "Forgot your question in another conversation."
+        );
+        assert_eq!(
+            ForgetHandler::format_forgotten(1, 2),
+            "Forgotten. My next answer here starts fresh. \
+             Forgot your 2 questions in other conversations."
+        );
+    }
+}
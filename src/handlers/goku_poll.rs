@@ -1,9 +1,8 @@
 use super::get_pool;
-use crate::db::{
-    atomic_increment_goku_poll, get_or_create_goku_poll_usage, get_server_by_guild_id,
-};
+use crate::db::get_server_by_guild_id;
 use crate::features::Features;
-use crate::handlers::gulag::Gulag;
+use crate::handlers::gulag::{Gulag, GulagSource};
+use crate::offenses::{OffenseType, Offenses};
 use serenity::{
     all::{CreateMessage, Mentionable},
     client::Context,
@@ -32,7 +31,7 @@ impl GokuPoll {
 
         let pool = get_pool(ctx).await;
 
-        if !Features::is_enabled(&pool, "goku_poll") {
+        if !Features::is_enabled(&pool, Some(guild_id), "goku_poll") {
             return;
         }
 
@@ -76,46 +75,32 @@ impl GokuPoll {
             }
         };
 
-        // Get current usage count for exponential duration
-        let current_count = match get_or_create_goku_poll_usage(
-            &pool,
-            poll_creator.id.get() as i64,
-            guild_id as i64,
-        ) {
-            Ok(u) => u.usage_count,
+        // Find a channel to post in - use the guild's gulag channel
+        let gulag_channel = match Gulag::find_gulag_channel(&ctx.http, &pool, guild_id).await {
+            Ok(Some(c)) => c,
+            Ok(None) => {
+                eprintln!("Goku poll: could not find the gulag channel");
+                return;
+            }
             Err(e) => {
-                eprintln!("Goku poll: failed to get usage count: {}", e);
+                eprintln!("Goku poll: error looking up the gulag channel: {}", e);
                 return;
             }
         };
 
-        // Calculate duration BEFORE incrementing (to avoid TOCTOU race condition)
-        let duration_seconds = match current_count.try_into() {
-            Ok(u32_count) => Gulag::get_gulag_duration_for_offense(u32_count),
-            Err(_) => {
-                // Overflow case - use capped value for duration calculation
-                eprintln!(
-                    "Goku poll: usage count overflowed ({}), using max duration",
-                    current_count
-                );
-                2_592_000 // Max ~30 days cap
+        let outcome = match Offenses::record(
+            &pool,
+            guild_id,
+            poll_creator.id.get(),
+            OffenseType::GokuPoll,
+        ) {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                eprintln!("Goku poll: failed to record offense: {:#}", e);
+                return;
             }
         };
 
-        // Find a channel to post in - use the-gulag channel
-        let gulag_channel =
-            match Gulag::find_channel(&ctx.http, guild_id, "the-gulag".to_string()).await {
-                Ok(Some(c)) => c,
-                Ok(None) => {
-                    eprintln!("Goku poll: could not find the-gulag channel");
-                    return;
-                }
-                Err(e) => {
-                    eprintln!("Goku poll: error looking up the-gulag channel: {}", e);
-                    return;
-                }
-            };
-
         // Send to gulag with calculated duration
         if let Err(e) = Gulag::add_to_gulag(
             &ctx.http,
@@ -124,42 +109,37 @@ impl GokuPoll {
                 guildid: guild_id,
                 userid: poll_creator.id.get(),
                 gulag_roleid: server.gulag_id as u64,
-                gulaglength: duration_seconds.try_into().unwrap_or(2_592_000u32), // Max ~30 days cap
+                gulaglength: outcome.duration_seconds.try_into().unwrap_or(u32::MAX),
                 channelid: gulag_channel.id.get(),
                 messageid: message.id.get(),
+                source: GulagSource::GokuPoll,
+                moderator_id: None,
+                reason: Some(format!(
+                    "Created a poll and Goku won {} (offense #{})",
+                    message.link(),
+                    outcome.count
+                )),
             },
         )
         .await
         {
             eprintln!("Goku poll: failed to send user to gulag: {}", e);
+            Gulag::retract_offense(
+                &pool,
+                guild_id,
+                poll_creator.id.get(),
+                OffenseType::GokuPoll,
+                &outcome,
+            );
             return;
         }
 
-        // Increment usage count after successful gulag (with proper error handling)
-        let new_count = match atomic_increment_goku_poll(
-            &pool,
-            poll_creator.id.get() as i64,
-            guild_id as i64,
-        ) {
-            Ok(count) => count,
-            Err(e) => {
-                eprintln!("Goku poll: failed to increment usage count: {}", e);
-                // On error, still increment by 1 to track the offense
-                current_count.saturating_add(1)
-            }
-        };
-
-        let next_duration_seconds = match new_count.saturating_add(1).try_into() {
-            Ok(u32_count) => Gulag::get_gulag_duration_for_offense(u32_count),
-            Err(_) => 2_592_000, // Max ~30 days for overflow protection
-        };
-
         let content = format!(
             "{} created a poll and Goku won. Sent to the gulag for {}!\nThis is offense #{} (next offense will be {})",
             poll_creator.mention(),
-            Gulag::format_duration(duration_seconds),
-            new_count,
-            Gulag::format_duration(next_duration_seconds),
+            Gulag::format_duration(outcome.duration_seconds),
+            outcome.count,
+            Gulag::format_duration(outcome.next_duration_seconds),
         );
 
         let _ = gulag_channel
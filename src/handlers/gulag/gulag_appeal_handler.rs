@@ -0,0 +1,417 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{Gulag, GulagSource};
+use crate::db::{
+    expire_gulag_appeal, get_active_gulag_user, get_gulag_appeal, get_latest_gulag_appeal,
+    insert_gulag_appeal,
+    models::{GulagAppeal, NewGulagAppeal},
+    resolve_gulag_appeal, set_gulag_appeal_review_message,
+};
+use crate::handlers::{get_pool, HandlerResponse};
+use crate::permissions::{authorize_member, Capability};
+use crate::tugbot::guild_config::GuildConfig;
+use serenity::{
+    all::{
+        ButtonStyle, CommandDataOptionValue, CommandInteraction, CommandOptionType,
+        ComponentInteraction, CreateActionRow, CreateButton, CreateEmbed, CreateEmbedFooter,
+        CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage,
+    },
+    builder::{CreateCommand, CreateCommandOption},
+    client::Context,
+};
+
+/// Prefix of the Approve/Deny button custom IDs: `gulag-appeal:<decision>:<id>`.
+pub const CUSTOM_ID_PREFIX: &str = "gulag-appeal:";
+
+/// Minimum time between a member's appeals.
+const APPEAL_COOLDOWN: Duration = Duration::from_secs(3600);
+
+/// `gulag_appeals.status` values.
+const STATUS_PENDING: &str = "pending";
+const STATUS_APPROVED: &str = "approved";
+const STATUS_DENIED: &str = "denied";
+const STATUS_EXPIRED: &str = "expired";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Decision {
+    Approve,
+    Deny,
+}
+
+pub struct GulagAppealHandler;
+
+impl GulagAppealHandler {
+    pub fn setup_command() -> CreateCommand {
+        CreateCommand::new("gulag-appeal")
+            .description("Ask the moderators to release you from the Gulag")
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::String,
+                    "message",
+                    "Why you should be let out",
+                )
+                .required(true)
+                .max_length(1000),
+            )
+    }
+
+    pub async fn setup_interaction(ctx: &Context, command: &CommandInteraction) -> HandlerResponse {
+        let guildid = match command.guild_id {
+            Some(guild) => guild.get(),
+            None => return Gulag::send_error("This command can only be used in a server"),
+        };
+        let appeal_text = match command.data.options.first().map(|opt| &opt.value) {
+            Some(CommandDataOptionValue::String(text)) => text.clone(),
+            _ => return Gulag::send_error("Please explain why you should be released"),
+        };
+        let userid = command.user.id.get();
+        let (guild_i64, user_i64) = match (i64::try_from(guildid), i64::try_from(userid)) {
+            (Ok(g), Ok(u)) => (g, u),
+            _ => return Gulag::send_error("ID out of range"),
+        };
+
+        let pool = get_pool(ctx).await;
+        let gulag_user = match get_active_gulag_user(&pool, guild_i64, user_i64) {
+            Ok(Some(gulag_user)) => gulag_user,
+            Ok(None) => return Gulag::send_error("You're not in the gulag"),
+            Err(e) => {
+                eprintln!("Error loading gulag user for appeal: {}", e);
+                return Gulag::send_error("Failed to file your appeal");
+            }
+        };
+        match get_latest_gulag_appeal(&pool, guild_i64, user_i64) {
+            Ok(latest) => {
+                if let Err(why) = Self::check_cooldown(latest.as_ref(), SystemTime::now()) {
+                    return Gulag::send_error(&why);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error loading previous appeals: {}", e);
+                return Gulag::send_error("Failed to file your appeal");
+            }
+        }
+
+        let Some(review_channel) = GuildConfig::load(&pool, guildid)
+            .mod_log_channel(&ctx.http)
+            .await
+        else {
+            return Gulag::send_error("This server has no moderator channel for appeals");
+        };
+
+        let appeal = match insert_gulag_appeal(
+            &pool,
+            &NewGulagAppeal {
+                guild_id: guild_i64,
+                user_id: user_i64,
+                message: &appeal_text,
+            },
+        ) {
+            Ok(appeal) => appeal,
+            Err(e) => {
+                eprintln!("Error saving appeal: {}", e);
+                return Gulag::send_error("Failed to file your appeal");
+            }
+        };
+
+        let release = gulag_user
+            .release_at
+            .duration_since(UNIX_EPOCH)
+            .map(|d| format!("<t:{}:R>", d.as_secs()))
+            .unwrap_or_else(|_| "unknown".to_string());
+        let embed = CreateEmbed::new()
+            .title("Gulag appeal")
+            .description(&appeal_text)
+            .field("Member", format!("<@{}>", userid), true)
+            .field("Release due", release, true)
+            .field(
+                "Sent for",
+                gulag_user.reason.as_deref().unwrap_or("No reason recorded"),
+                false,
+            )
+            .footer(CreateEmbedFooter::new(format!("Appeal #{}", appeal.id)));
+        let buttons = CreateActionRow::Buttons(vec![
+            CreateButton::new(Self::custom_id(Decision::Approve, appeal.id))
+                .label("Approve")
+                .style(ButtonStyle::Success),
+            CreateButton::new(Self::custom_id(Decision::Deny, appeal.id))
+                .label("Deny")
+                .style(ButtonStyle::Danger),
+        ]);
+
+        let posted = review_channel
+            .send_message(
+                &ctx.http,
+                CreateMessage::new().embed(embed).components(vec![buttons]),
+            )
+            .await;
+        match posted {
+            Ok(msg) => {
+                if let Err(e) = set_gulag_appeal_review_message(
+                    &pool,
+                    appeal.id,
+                    msg.channel_id.get() as i64,
+                    msg.id.get() as i64,
+                ) {
+                    eprintln!("Error saving appeal review message: {}", e);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error posting appeal {}: {}", appeal.id, e);
+                // Don't hold a cooldown against an appeal nobody could see.
+                let _ = resolve_gulag_appeal(&pool, appeal.id, STATUS_EXPIRED, None);
+                return Gulag::send_error("Failed to send your appeal to the moderators");
+            }
+        }
+
+        HandlerResponse::ephemeral("Your appeal has been sent to the moderators.")
+    }
+
+    /// Handle an Approve/Deny click on an appeal review message.
+    pub async fn handle_component(ctx: &Context, component: &ComponentInteraction) {
+        let outcome = Self::decide(ctx, component).await;
+        let response = match outcome {
+            Ok(outcome) => {
+                let mut embed = component
+                    .message
+                    .embeds
+                    .first()
+                    .cloned()
+                    .map(CreateEmbed::from)
+                    .unwrap_or_default();
+                embed = embed.field("Outcome", outcome, false);
+                CreateInteractionResponse::UpdateMessage(
+                    CreateInteractionResponseMessage::new()
+                        .embed(embed)
+                        .components(vec![]),
+                )
+            }
+            Err(why) => CreateInteractionResponse::Message(
+                CreateInteractionResponseMessage::new()
+                    .content(why)
+                    .ephemeral(true),
+            ),
+        };
+        if let Err(why) = component.create_response(&ctx.http, response).await {
+            eprintln!("Cannot respond to appeal button: {}", why);
+        }
+    }
+
+    /// Apply a moderator's decision. `Ok` is the outcome line for the review
+    /// message; `Err` is an ephemeral error for the clicker.
+    async fn decide(ctx: &Context, component: &ComponentInteraction) -> Result<String, String> {
+        let (decision, appeal_id) = Self::parse_custom_id(&component.data.custom_id)
+            .ok_or_else(|| "Error: Unknown appeal button".to_string())?;
+        let (Some(guildid), Some(member)) = (component.guild_id, component.member.as_ref()) else {
+            return Err("Error: Appeals can only be handled in a server".to_string());
+        };
+
+        let pool = get_pool(ctx).await;
+        authorize_member(
+            &ctx.http,
+            &pool,
+            guildid.get(),
+            member,
+            Capability::GulagRelease,
+        )
+        .await?;
+
+        let appeal = match get_gulag_appeal(&pool, appeal_id) {
+            Ok(Some(appeal)) if appeal.guild_id == guildid.get() as i64 => appeal,
+            Ok(_) => return Err("Error: Appeal not found".to_string()),
+            Err(e) => {
+                eprintln!("Error loading appeal {}: {}", appeal_id, e);
+                return Err("Error: Failed to load the appeal".to_string());
+            }
+        };
+        let moderator = component.user.id.get();
+
+        // An appeal only covers the sentence it was filed against; a later
+        // sentence needs its own.
+        let gulag_user = match decision {
+            Decision::Approve => {
+                match get_active_gulag_user(&pool, appeal.guild_id, appeal.user_id) {
+                    Ok(gulag_user) => {
+                        gulag_user.filter(|g| Self::covers_sentence(&appeal, g.created_at))
+                    }
+                    Err(e) => {
+                        eprintln!("Error loading gulag user for appeal {}: {}", appeal.id, e);
+                        return Err("Error: Failed to load the gulag record".to_string());
+                    }
+                }
+            }
+            Decision::Deny => None,
+        };
+        let status = match (decision, &gulag_user) {
+            (Decision::Approve, Some(_)) => STATUS_APPROVED,
+            (Decision::Approve, None) => STATUS_EXPIRED,
+            (Decision::Deny, _) => STATUS_DENIED,
+        };
+
+        // Claim the appeal first so a double click can't release twice.
+        match resolve_gulag_appeal(&pool, appeal.id, status, Some(moderator as i64)) {
+            Ok(Some(_)) => {}
+            Ok(None) => return Err("This appeal has already been handled".to_string()),
+            Err(e) => {
+                eprintln!("Error resolving appeal {}: {}", appeal.id, e);
+                return Err("Error: Failed to update the appeal".to_string());
+            }
+        }
+
+        match (status, gulag_user) {
+            (STATUS_APPROVED, Some(gulag_user)) => {
+                if let Err(e) = Gulag::release_early(
+                    ctx.http.clone(),
+                    &pool,
+                    &gulag_user,
+                    GulagSource::Appeal,
+                    Some(moderator),
+                )
+                .await
+                {
+                    eprintln!("Error releasing user for appeal {}: {:?}", appeal.id, e);
+                    // Nothing happened, so let the member appeal again.
+                    if let Err(e) = expire_gulag_appeal(&pool, appeal.id) {
+                        eprintln!("Error expiring appeal {}: {}", appeal.id, e);
+                    }
+                    return Ok(format!(
+                        "Approved by <@{}>, but the release failed. Use /gulag-release.",
+                        moderator
+                    ));
+                }
+                Ok(format!("Approved by <@{}>", moderator))
+            }
+            (STATUS_DENIED, _) => {
+                if let Ok(Some(channel)) =
+                    Gulag::find_gulag_channel(&ctx.http, &pool, guildid.get()).await
+                {
+                    let _ = channel
+                        .say(
+                            &ctx.http,
+                            format!("<@{}>'s appeal was denied.", appeal.user_id),
+                        )
+                        .await;
+                }
+                Ok(format!("Denied by <@{}>", moderator))
+            }
+            _ => Ok("Closed: the sentence this appeal was for is over".to_string()),
+        }
+    }
+
+    /// Whether an appeal was filed during the sentence that started at `sentenced_at`.
+    fn covers_sentence(appeal: &GulagAppeal, sentenced_at: SystemTime) -> bool {
+        appeal.created_at >= sentenced_at
+    }
+
+    /// Whether a member may file a new appeal given their latest one.
+    fn check_cooldown(latest: Option<&GulagAppeal>, now: SystemTime) -> Result<(), String> {
+        let Some(latest) = latest else {
+            return Ok(());
+        };
+        if latest.status == STATUS_PENDING {
+            return Err("You already have an appeal waiting for a moderator".to_string());
+        }
+        // Appeals that expired unanswered don't count against the member.
+        if latest.status == STATUS_EXPIRED && latest.moderator_id.is_none() {
+            return Ok(());
+        }
+        let next = latest.created_at + APPEAL_COOLDOWN;
+        if next > now {
+            let at = next
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            return Err(format!("You can appeal again <t:{}:R>", at));
+        }
+        Ok(())
+    }
+
+    fn custom_id(decision: Decision, appeal_id: i64) -> String {
+        let decision = match decision {
+            Decision::Approve => "approve",
+            Decision::Deny => "deny",
+        };
+        format!("{}{}:{}", CUSTOM_ID_PREFIX, decision, appeal_id)
+    }
+
+    fn parse_custom_id(custom_id: &str) -> Option<(Decision, i64)> {
+        let (decision, appeal_id) = custom_id.strip_prefix(CUSTOM_ID_PREFIX)?.split_once(':')?;
+        let decision = match decision {
+            "approve" => Decision::Approve,
+            "deny" => Decision::Deny,
+            _ => return None,
+        };
+        Some((decision, appeal_id.parse().ok()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn appeal(status: &str, moderator_id: Option<i64>, age: Duration) -> GulagAppeal {
+        GulagAppeal {
+            id: 1,
+            guild_id: 1,
+            user_id: 2,
+            message: "let me out".to_string(),
+            status: status.to_string(),
+            moderator_id,
+            channel_id: None,
+            review_message_id: None,
+            created_at: SystemTime::now() - age,
+            resolved_at: None,
+        }
+    }
+
+    #[test]
+    fn custom_ids_round_trip() {
+        for decision in [Decision::Approve, Decision::Deny] {
+            let id = GulagAppealHandler::custom_id(decision, 42);
+            assert_eq!(
+                GulagAppealHandler::parse_custom_id(&id),
+                Some((decision, 42))
+            );
+        }
+        assert_eq!(
+            GulagAppealHandler::parse_custom_id("gulag-appeal:maybe:1"),
+            None
+        );
+        assert_eq!(GulagAppealHandler::parse_custom_id("cull:approve:1"), None);
+    }
+
+    #[test]
+    fn cooldown_rules() {
+        let now = SystemTime::now();
+        let minute = Duration::from_secs(60);
+        assert!(GulagAppealHandler::check_cooldown(None, now).is_ok());
+        assert!(GulagAppealHandler::check_cooldown(
+            Some(&appeal(STATUS_PENDING, None, minute)),
+            now
+        )
+        .is_err());
+        assert!(GulagAppealHandler::check_cooldown(
+            Some(&appeal(STATUS_DENIED, Some(9), minute)),
+            now
+        )
+        .is_err());
+        assert!(GulagAppealHandler::check_cooldown(
+            Some(&appeal(STATUS_DENIED, Some(9), APPEAL_COOLDOWN + minute)),
+            now
+        )
+        .is_ok());
+        assert!(GulagAppealHandler::check_cooldown(
+            Some(&appeal(STATUS_EXPIRED, None, minute)),
+            now
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn appeals_only_cover_their_own_sentence() {
+        let hour = Duration::from_secs(3600);
+        let filed = appeal(STATUS_PENDING, None, hour);
+        let now = SystemTime::now();
+        assert!(GulagAppealHandler::covers_sentence(&filed, now - 2 * hour));
+        assert!(!GulagAppealHandler::covers_sentence(&filed, now));
+    }
+}
@@ -6,7 +6,8 @@ use serenity::{
     client::Context,
 };
 
-use super::Gulag;
+use super::{Gulag, GulagSource};
+use crate::offenses::{OffenseType, Offenses};
 
 pub struct GulagHandler;
 
@@ -27,14 +28,18 @@ impl GulagHandler {
                 .required(true),
             )
             .add_option(
-                CreateCommandOption::new(CommandOptionType::Integer, "length", "How Long minutes")
-                    .required(true),
+                CreateCommandOption::new(
+                    CommandOptionType::Integer,
+                    "length",
+                    "How Long minutes (defaults to the escalation policy)",
+                )
+                .required(false),
             )
     }
 
     pub async fn setup_interaction(ctx: &Context, command: &CommandInteraction) -> HandlerResponse {
         let pool = get_pool(ctx).await;
-        if !Features::is_enabled(&pool, "gulag") {
+        if !Features::is_enabled(&pool, command.guild_id.map(|g| g.get()), "gulag") {
             return HandlerResponse {
                 content: String::from("Gulag feature is currently disabled"),
                 components: None,
@@ -43,42 +48,6 @@ impl GulagHandler {
             };
         }
 
-        // Check permissions: require Highly Regarded or admin role
-        let guild_id = match command.guild_id {
-            Some(id) => id.get(),
-            None => {
-                return HandlerResponse {
-                    content: "Error: This command can only be used in a guild".to_string(),
-                    components: None,
-                    ephemeral: true,
-                    defer_response: None,
-                }
-            }
-        };
-
-        let member = match ctx.http.get_member(guild_id.into(), command.user.id).await {
-            Ok(m) => m,
-            Err(_) => {
-                return HandlerResponse {
-                    content: "Error: Could not verify your permissions".to_string(),
-                    components: None,
-                    ephemeral: true,
-                    defer_response: None,
-                }
-            }
-        };
-
-        let allowed_roles = ["Highly Regarded", "admin"];
-        if !Gulag::member_has_any_role(&ctx.http, guild_id, &member, &allowed_roles).await {
-            return HandlerResponse {
-                content: "Error: You need Highly Regarded or admin role to use this command"
-                    .to_string(),
-                components: None,
-                ephemeral: true,
-                defer_response: None,
-            };
-        }
-
         let user_option = match command.data.options.iter().find(|opt| opt.name == "user") {
             Some(opt) => &opt.value,
             None => {
@@ -103,33 +72,29 @@ impl GulagHandler {
             }
         };
 
-        let length_option = match command.data.options.iter().find(|opt| opt.name == "length") {
-            Some(opt) => &opt.value,
-            None => {
-                return HandlerResponse {
-                    content: "Error: Missing required length option".to_string(),
-                    components: None,
-                    ephemeral: true,
-                    defer_response: None,
-                }
-            }
-        };
+        let length_option = command
+            .data
+            .options
+            .iter()
+            .find(|opt| opt.name == "length")
+            .map(|opt| &opt.value);
 
         let channelid = command.channel_id.get();
 
-        let mut gulaglength = 300;
-        if let CommandDataOptionValue::Integer(length) = length_option {
-            if *length > 0 && *length <= 10080 {
+        let requested_length = match length_option {
+            Some(CommandDataOptionValue::Integer(length)) if *length > 0 && *length <= 10080 => {
                 // Max 1 week
-                gulaglength = length * 60;
-            } else if *length <= 0 {
+                Some(*length as u32 * 60)
+            }
+            Some(CommandDataOptionValue::Integer(length)) if *length <= 0 => {
                 return HandlerResponse {
                     content: String::from("Gulag length must be positive"),
                     components: None,
                     ephemeral: true,
                     defer_response: None,
                 };
-            } else {
+            }
+            Some(CommandDataOptionValue::Integer(_)) => {
                 return HandlerResponse {
                     content: String::from("Gulag length cannot exceed 10080 minutes (1 week)"),
                     components: None,
@@ -137,7 +102,8 @@ impl GulagHandler {
                     defer_response: None,
                 };
             }
-        }
+            _ => None,
+        };
 
         if let CommandDataOptionValue::User(user) = user_option {
             match command.guild_id {
@@ -155,6 +121,23 @@ impl GulagHandler {
                         defer_response: None,
                     },
                     Some(gulag_role) => {
+                        // Explicit lengths still count towards the escalation.
+                        let outcome = match Offenses::record(
+                            &pool,
+                            guildid.get(),
+                            user.get(),
+                            OffenseType::SlashCommand,
+                        ) {
+                            Ok(outcome) => outcome,
+                            Err(e) => {
+                                eprintln!("Failed to record gulag offense: {:#}", e);
+                                return HandlerResponse::ephemeral("Failed to record offense");
+                            }
+                        };
+                        let gulaglength = requested_length.unwrap_or_else(|| {
+                            u32::try_from(outcome.duration_seconds).unwrap_or(u32::MAX)
+                        });
+
                         let gulag_user = match Gulag::add_to_gulag(
                             &ctx.http,
                             &pool,
@@ -162,15 +145,28 @@ impl GulagHandler {
                                 guildid: guildid.get(),
                                 userid: user.get(),
                                 gulag_roleid: gulag_role.id.get(),
-                                gulaglength: gulaglength as u32,
+                                gulaglength,
                                 channelid,
                                 messageid: 0,
+                                source: GulagSource::SlashCommand,
+                                moderator_id: Some(command.user.id.get()),
+                                reason: match reason_option {
+                                    CommandDataOptionValue::String(reason) => Some(reason.clone()),
+                                    _ => None,
+                                },
                             },
                         )
                         .await
                         {
                             Ok(u) => u,
                             Err(e) => {
+                                Gulag::retract_offense(
+                                    &pool,
+                                    guildid.get(),
+                                    user.get(),
+                                    OffenseType::SlashCommand,
+                                    &outcome,
+                                );
                                 return HandlerResponse {
                                     content: format!("Failed to send to gulag: {}", e),
                                     components: None,
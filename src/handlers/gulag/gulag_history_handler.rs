@@ -0,0 +1,188 @@
+use std::time::UNIX_EPOCH;
+
+use super::{Gulag, GulagSource, ACTION_GULAG};
+use crate::db::{count_moderation_events, get_moderation_events, models::ModerationEvent};
+use crate::handlers::{get_pool, HandlerResponse};
+use serenity::{
+    all::{CommandDataOptionValue, CommandInteraction, CommandOptionType},
+    builder::{CreateCommand, CreateCommandOption},
+    client::Context,
+};
+
+/// Most recent events loaded for the reply.
+const MAX_EVENTS: i64 = 15;
+
+/// Longest reply, leaving headroom under Discord's 2000 char limit.
+const MAX_REPLY_LEN: usize = 1900;
+
+/// Reasons are free text, so each one is cut to this many characters.
+const MAX_REASON_CHARS: usize = 200;
+
+pub struct GulagHistoryHandler;
+
+impl GulagHistoryHandler {
+    pub fn setup_command() -> CreateCommand {
+        CreateCommand::new("gulag-history")
+            .description("Show a member's gulag record")
+            .add_option(
+                CreateCommandOption::new(CommandOptionType::User, "user", "The member to look up")
+                    .required(true),
+            )
+    }
+
+    pub async fn setup_interaction(ctx: &Context, command: &CommandInteraction) -> HandlerResponse {
+        let guildid = match command.guild_id {
+            Some(guild) => guild.get(),
+            None => return Gulag::send_error("This command can only be used in a server"),
+        };
+        let userid = match command.data.options.first().map(|opt| &opt.value) {
+            Some(CommandDataOptionValue::User(user)) => user.get(),
+            _ => return Gulag::send_error("Please provide a valid user"),
+        };
+
+        let pool = get_pool(ctx).await;
+        let (guild_i64, user_i64) = match (i64::try_from(guildid), i64::try_from(userid)) {
+            (Ok(g), Ok(u)) => (g, u),
+            _ => return Gulag::send_error("ID out of range"),
+        };
+
+        let events = match get_moderation_events(&pool, guild_i64, user_i64, MAX_EVENTS) {
+            Ok(events) => events,
+            Err(e) => {
+                eprintln!("Error loading moderation events: {}", e);
+                return Gulag::send_error("Failed to load gulag history");
+            }
+        };
+        let total = match count_moderation_events(&pool, guild_i64, user_i64, ACTION_GULAG) {
+            Ok(n) => n,
+            Err(e) => {
+                eprintln!("Error counting moderation events: {}", e);
+                return Gulag::send_error("Failed to load gulag history");
+            }
+        };
+
+        HandlerResponse::ephemeral(Self::format_history(userid, &events, total))
+    }
+
+    fn format_history(userid: u64, events: &[ModerationEvent], total_gulags: i64) -> String {
+        if events.is_empty() {
+            return format!("<@{}> has a clean record.", userid);
+        }
+
+        let mut content = format!(
+            "**Gulag record for <@{}>** — sent {} time{}",
+            userid,
+            total_gulags,
+            if total_gulags == 1 { "" } else { "s" }
+        );
+        for (i, event) in events.iter().enumerate() {
+            let line = Self::format_event(event);
+            if content.len() + line.len() + 1 > MAX_REPLY_LEN {
+                content.push_str(&format!("\n...and {} more", events.len() - i));
+                return content;
+            }
+            content.push('\n');
+            content.push_str(&line);
+        }
+        if events.len() as i64 >= MAX_EVENTS {
+            content.push_str(&format!(
+                "\n_Showing the {} most recent events._",
+                MAX_EVENTS
+            ));
+        }
+        content
+    }
+
+    fn format_event(event: &ModerationEvent) -> String {
+        let when = event
+            .created_at
+            .duration_since(UNIX_EPOCH)
+            .map(|d| format!("<t:{}:f>", d.as_secs()))
+            .unwrap_or_else(|_| "unknown time".to_string());
+        let source = GulagSource::parse(&event.source)
+            .map(|s| s.label())
+            .unwrap_or(event.source.as_str());
+        let by = event
+            .moderator_id
+            .map(|id| format!(" by <@{}>", id))
+            .unwrap_or_default();
+
+        let mut line = if event.action == ACTION_GULAG {
+            let length = event
+                .duration_seconds
+                .map(|secs| format!(" for {}", Gulag::format_duration(secs.max(0) as u64)))
+                .unwrap_or_default();
+            format!("{} — Gulagged{}{} ({})", when, length, by, source)
+        } else {
+            format!("{} — Released{} ({})", when, by, source)
+        };
+        if let Some(reason) = &event.reason {
+            line.push_str(": ");
+            if reason.chars().count() > MAX_REASON_CHARS {
+                line.extend(reason.chars().take(MAX_REASON_CHARS));
+                line.push('…');
+            } else {
+                line.push_str(reason);
+            }
+        }
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handlers::gulag::ACTION_RELEASE;
+    use std::time::Duration;
+
+    fn event(action: &str, source: GulagSource, moderator: Option<i64>) -> ModerationEvent {
+        ModerationEvent {
+            id: 1,
+            guild_id: 1,
+            user_id: 2,
+            action: action.to_string(),
+            source: source.as_str().to_string(),
+            moderator_id: moderator,
+            reason: None,
+            duration_seconds: Some(300),
+            created_at: UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        }
+    }
+
+    #[test]
+    fn empty_history_is_clean() {
+        assert_eq!(
+            GulagHistoryHandler::format_history(2, &[], 0),
+            "<@2> has a clean record."
+        );
+    }
+
+    #[test]
+    fn formats_gulag_and_release() {
+        let mut sent = event(ACTION_GULAG, GulagSource::SlashCommand, Some(9));
+        sent.reason = Some("spamming".to_string());
+        let freed = event(ACTION_RELEASE, GulagSource::Timer, None);
+
+        let text = GulagHistoryHandler::format_history(2, &[freed, sent], 1);
+        assert!(text.contains("sent 1 time\n"));
+        assert!(text.contains("<t:1700000000:f> — Released (sentence served)"));
+        assert!(text.contains("Gulagged for 5m by <@9> (command): spamming"));
+    }
+
+    #[test]
+    fn long_reasons_stay_under_the_reply_limit() {
+        let events: Vec<ModerationEvent> = (0..MAX_EVENTS)
+            .map(|_| {
+                let mut sent = event(ACTION_GULAG, GulagSource::SlashCommand, Some(9));
+                sent.reason = Some("é".repeat(1000));
+                sent
+            })
+            .collect();
+
+        let text = GulagHistoryHandler::format_history(2, &events, MAX_EVENTS);
+        assert!(text.chars().count() <= 2000);
+        assert!(text.len() <= MAX_REPLY_LEN);
+        assert!(text.contains(&format!("{}…", "é".repeat(MAX_REASON_CHARS))));
+        assert!(text.ends_with("more"));
+    }
+}
@@ -86,6 +86,9 @@ impl GulagListHandler {
                     };
 
                     userlist.push_str(&format!("\n{} - {}", user, time_info));
+                    if let Some(why) = &gulaguser.reason {
+                        userlist.push_str(&format!(" - {}", why));
+                    }
                 }
                 let content = format!("Users in the Gulag:{}", userlist);
                 HandlerResponse {
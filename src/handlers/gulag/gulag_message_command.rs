@@ -20,7 +20,8 @@ impl GulagMessageCommandHandler {
     ) -> HandlerResponse {
         let pool = get_pool(ctx).await;
 
-        if !crate::features::Features::is_enabled(&pool, "gulag") {
+        if !crate::features::Features::is_enabled(&pool, command.guild_id.map(|g| g.get()), "gulag")
+        {
             return HandlerResponse {
                 content: "Gulag feature is currently disabled.".to_string(),
                 components: None,
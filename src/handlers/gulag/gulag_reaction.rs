@@ -72,7 +72,7 @@ impl GulagReaction {
         let pool = get_pool(ctx).await;
 
         // Check if gulag feature is enabled
-        if !Features::is_enabled(&pool, "gulag") {
+        if !Features::is_enabled(&pool, add_reaction.guild_id.map(|g| g.get()), "gulag") {
             return;
         }
 
@@ -1,10 +1,8 @@
-use super::Gulag;
-use crate::db::schema::gulag_users::dsl::*;
+use super::{Gulag, GulagSource};
 use crate::handlers::{get_pool, HandlerResponse};
-use diesel::*;
 use serenity::{
     all::{CommandDataOptionValue, CommandInteraction, CommandOptionType},
-    builder::{CreateCommand, CreateCommandOption, CreateMessage},
+    builder::{CreateCommand, CreateCommandOption},
     client::Context,
 };
 
@@ -23,28 +21,6 @@ impl GulagRemoveHandler {
     pub async fn setup_interaction(ctx: &Context, command: &CommandInteraction) -> HandlerResponse {
         let pool = get_pool(ctx).await;
 
-        // Check permissions: require Highly Regarded or admin role
-        let guildid = match command.guild_id {
-            Some(guild) => guild.get(),
-            None => return Gulag::send_error("This command can only be used in a server"),
-        };
-
-        let member = match ctx.http.get_member(guildid.into(), command.user.id).await {
-            Ok(m) => m,
-            Err(_) => return Gulag::send_error("Could not verify your permissions"),
-        };
-
-        let allowed_roles = ["Highly Regarded", "admin"];
-        if !Gulag::member_has_any_role(&ctx.http, guildid, &member, &allowed_roles).await {
-            return HandlerResponse {
-                content: "Error: You need Highly Regarded or admin role to use this command"
-                    .to_string(),
-                components: None,
-                ephemeral: true,
-                defer_response: None,
-            };
-        }
-
         let user_options = match command.data.options.first() {
             Some(opt) => &opt.value,
             None => {
@@ -65,101 +41,30 @@ impl GulagRemoveHandler {
                     ephemeral: true,
                     defer_response: None,
                 },
-                Some(guildid) => {
-                    match Gulag::find_gulag_role(&ctx.http, guildid.get()).await {
-                        Some(gulag_role) => {
-                            match Gulag::is_user_in_gulag(&pool, user.get()) {
-                                Some(db_gulag_user) => {
-                                    // release
-                                    match Gulag::find_channel(
-                                        &ctx.http,
-                                        guildid.get(),
-                                        "the-gulag".to_string(),
-                                    )
-                                    .await
-                                    {
-                                        Ok(Some(gulag_channel)) => {
-                                            match ctx
-                                                .http
-                                                .get_member(
-                                                    guildid,
-                                                    (db_gulag_user.user_id as u64).into(),
-                                                )
-                                                .await
-                                            {
-                                                Ok(member) => {
-                                                    if (member
-                                                        .remove_role(&ctx.http, gulag_role.id.get())
-                                                        .await)
-                                                        .is_err()
-                                                    {
-                                                        return Gulag::send_error(
-                                                            "Couldn't remove role",
-                                                        );
-                                                    };
-
-                                                    let message = format!(
-                                                        "Freeing {} from the gulag",
-                                                        member
-                                                    );
-
-                                                    if (gulag_channel
-                                                        .send_message(
-                                                            &ctx.http,
-                                                            CreateMessage::new().content(message),
-                                                        )
-                                                        .await)
-                                                        .is_err()
-                                                    {
-                                                        return Gulag::send_error(
-                                                            "Couldn't Send message to release",
-                                                        );
-                                                    };
-                                                    match pool.get() {
-                                                        Ok(mut conn) => {
-                                                            match diesel::delete(
-                                                                gulag_users.filter(id.eq(db_gulag_user.id)),
-                                                            )
-                                                            .execute(&mut conn)
-                                                            {
-                                                                Ok(_) => eprintln!("Removed from database"),
-                                                                Err(e) => eprintln!(
-                                                                    "Failed to delete gulag user from DB: {}",
-                                                                    e
-                                                                ),
-                                                            }
-                                                        }
-                                                        Err(e) => {
-                                                            eprintln!("Failed to get database connection for cleanup: {}", e);
-                                                        }
-                                                    }
+                Some(_) => match Gulag::is_user_in_gulag(&pool, user.get()) {
+                    Some(db_gulag_user) => {
+                        if let Err(e) = Gulag::release_early(
+                            ctx.http.clone(),
+                            &pool,
+                            &db_gulag_user,
+                            GulagSource::SlashCommand,
+                            Some(command.user.id.get()),
+                        )
+                        .await
+                        {
+                            eprintln!("Error releasing user from gulag: {:?}", e);
+                            return Gulag::send_error("Couldn't release user from the Gulag");
+                        }
 
-                                                    HandlerResponse {
-                                                        content: "Releasing User from the Gulag"
-                                                            .to_string(),
-                                                        components: None,
-                                                        ephemeral: true,
-                                                        defer_response: None,
-                                                    }
-                                                }
-                                                Err(_) => Gulag::send_error("Couldn't get member"),
-                                            }
-                                        }
-                                        Ok(None) => {
-                                            Gulag::send_error("Couldn't find Gulag Channel")
-                                        }
-                                        Err(e) => {
-                                            eprintln!("Error looking up Gulag Channel: {}", e);
-                                            Gulag::send_error("Error looking up Gulag Channel")
-                                        }
-                                    }
-                                }
-                                None => Gulag::send_error("Couldn't find user in Database"),
-                            }
+                        HandlerResponse {
+                            content: "Releasing User from the Gulag".to_string(),
+                            components: None,
+                            ephemeral: true,
+                            defer_response: None,
                         }
-                        None => Gulag::send_error("Couldn't find gulag Role"),
                     }
-                }
+                    None => Gulag::send_error("Couldn't find user in Database"),
+                },
             }
         } else {
             HandlerResponse {
@@ -1,13 +1,15 @@
 use super::HandlerResponse;
 use crate::db::{
-    add_time_to_gulag,
-    models::{GulagUser, JobStatus, MessageVotes},
+    add_time_to_gulag, expire_pending_gulag_appeals, insert_moderation_event,
+    models::{GulagUser, JobStatus, MessageVotes, NewModerationEvent},
     schema::{
         gulag_users::{self, dsl::*},
         message_votes::{self, dsl::*},
     },
     send_to_gulag, DbPool,
 };
+use crate::offenses::{OffenseType, Offenses, Outcome};
+use crate::tugbot::guild_config::GuildConfig;
 use anyhow::{Context, Result};
 use diesel::*;
 use serenity::{
@@ -26,7 +28,9 @@ use std::{
 };
 use tokio::{task::spawn, time::sleep};
 
+pub mod gulag_appeal_handler;
 pub mod gulag_handler;
+pub mod gulag_history_handler;
 pub mod gulag_list_handler;
 pub mod gulag_message_command;
 pub mod gulag_reaction;
@@ -35,6 +39,93 @@ pub mod gulag_vote;
 
 pub struct Gulag;
 
+/// How a gulag action was triggered. Stored in `moderation_events.source`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GulagSource {
+    SlashCommand,
+    ReactionVote,
+    AiSlop,
+    GokuPoll,
+    Elon,
+    SlowUser,
+    Rejoin,
+    Timer,
+    Appeal,
+}
+
+impl GulagSource {
+    pub const ALL: [GulagSource; 9] = [
+        GulagSource::SlashCommand,
+        GulagSource::ReactionVote,
+        GulagSource::AiSlop,
+        GulagSource::GokuPoll,
+        GulagSource::Elon,
+        GulagSource::SlowUser,
+        GulagSource::Rejoin,
+        GulagSource::Timer,
+        GulagSource::Appeal,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GulagSource::SlashCommand => "slash_command",
+            GulagSource::ReactionVote => "reaction_vote",
+            GulagSource::AiSlop => "ai_slop",
+            GulagSource::GokuPoll => "goku_poll",
+            GulagSource::Elon => "elon",
+            GulagSource::SlowUser => "slow_user",
+            GulagSource::Rejoin => "rejoin",
+            GulagSource::Timer => "timer",
+            GulagSource::Appeal => "appeal",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<GulagSource> {
+        GulagSource::ALL.into_iter().find(|s| s.as_str() == value)
+    }
+
+    /// Short human-readable label for history output.
+    pub fn label(&self) -> &'static str {
+        match self {
+            GulagSource::SlashCommand => "command",
+            GulagSource::ReactionVote => "reaction vote",
+            GulagSource::AiSlop => "AI Slop",
+            GulagSource::GokuPoll => "goku poll",
+            GulagSource::Elon => "elon",
+            GulagSource::SlowUser => "slow-user mention",
+            GulagSource::Rejoin => "rejoin",
+            GulagSource::Timer => "sentence served",
+            GulagSource::Appeal => "appeal",
+        }
+    }
+
+    /// The offense ledger entry this source escalates, if it counts as one.
+    pub fn offense_type(&self) -> Option<OffenseType> {
+        match self {
+            GulagSource::SlashCommand => Some(OffenseType::SlashCommand),
+            GulagSource::ReactionVote => Some(OffenseType::ReactionVote),
+            GulagSource::AiSlop => Some(OffenseType::AiSlop),
+            GulagSource::GokuPoll => Some(OffenseType::GokuPoll),
+            GulagSource::Elon => Some(OffenseType::Elon),
+            GulagSource::SlowUser => Some(OffenseType::SlowUser),
+            GulagSource::Rejoin | GulagSource::Timer | GulagSource::Appeal => None,
+        }
+    }
+}
+
+/// `moderation_events.action` values.
+pub const ACTION_GULAG: &str = "gulag";
+pub const ACTION_RELEASE: &str = "release";
+
+/// Audit log fields for `Gulag::log_event`, before ID conversion.
+struct EventDetails<'a> {
+    action: &'static str,
+    source: GulagSource,
+    moderator_id: Option<u64>,
+    reason: Option<&'a str>,
+    duration_seconds: Option<i32>,
+}
+
 pub struct GulagParams {
     pub guildid: u64,
     pub userid: u64,
@@ -42,22 +133,19 @@ pub struct GulagParams {
     pub gulaglength: u32,
     pub channelid: u64,
     pub messageid: u64,
+    pub source: GulagSource,
+    /// Member who issued the action, if a person did.
+    pub moderator_id: Option<u64>,
+    pub reason: Option<String>,
 }
 
 impl Gulag {
-    /// Calculate gulag duration based on offense count (exponential).
-    /// - Base: 30 minutes (1800 seconds)
-    /// - Formula: 30 * 2^offense_count minutes
-    pub fn get_gulag_duration_for_offense(count: u32) -> u64 {
-        let base_seconds: u64 = 1800; // 30 minutes
-
-        // Use checked operations to prevent overflow, capped at ~30 days
-        let multiplier = match count {
-            c if c < 32 => 2u64.checked_pow(c).unwrap_or(u64::MAX),
-            _ => u64::MAX,
-        };
-
-        base_seconds.saturating_mul(multiplier)
+    /// Announcement posted when a user is released.
+    pub fn release_message(member: &str, sent_for: Option<&str>) -> String {
+        match sent_for {
+            Some(why) => format!("Freeing {} from the gulag (sent for: {})", member, why),
+            None => format!("Freeing {} from the gulag", member),
+        }
     }
 
     /// Format duration in human-readable format (h/m/s).
@@ -74,35 +162,6 @@ impl Gulag {
             format!("{}s", secs)
         }
     }
-
-    /// Check if member has any of the specified roles.
-    pub async fn member_has_any_role(
-        http: &Arc<Http>,
-        guildid: u64,
-        member: &Member,
-        role_names: &[&str],
-    ) -> bool {
-        // Fetch all roles in a single API call instead of N calls
-        match http.get_guild_roles(guildid.into()).await {
-            Err(_) => false,
-            Ok(roles) => {
-                let guild_roles: Vec<Role> = roles;
-
-                for role_name in role_names {
-                    // Find the matching role by name
-                    if let Some(role) = guild_roles.iter().find(|r| r.name == *role_name) {
-                        // Check if member has this role
-                        for member_role_id in member.roles.iter().copied() {
-                            if member_role_id.get() == role.id.get() {
-                                return true;
-                            }
-                        }
-                    }
-                }
-                false
-            }
-        }
-    }
 }
 
 impl Gulag {
@@ -191,6 +250,47 @@ impl Gulag {
         Ok(None)
     }
 
+    /// Look up the guild's gulag announcement channel as configured in
+    /// `guild_settings`, falling back to #the-gulag.
+    pub async fn find_gulag_channel(
+        http: &Arc<Http>,
+        pool: &DbPool,
+        guildid: u64,
+    ) -> Result<Option<GuildChannel>, serenity::Error> {
+        GuildConfig::load(pool, guildid).gulag_channel(http).await
+    }
+
+    /// Append an event to the moderation audit log. Failures are logged, not
+    /// returned: the Discord side of the action has already happened.
+    fn log_event(pool: &DbPool, guildid: u64, userid: u64, details: EventDetails) {
+        let result = (|| -> Result<()> {
+            let event = NewModerationEvent {
+                guild_id: i64::try_from(guildid)
+                    .with_context(|| format!("Guild ID {} exceeds i64::MAX", guildid))?,
+                user_id: i64::try_from(userid)
+                    .with_context(|| format!("User ID {} exceeds i64::MAX", userid))?,
+                action: details.action,
+                source: details.source.as_str(),
+                moderator_id: details
+                    .moderator_id
+                    .map(i64::try_from)
+                    .transpose()
+                    .with_context(|| "Moderator ID exceeds i64::MAX")?,
+                reason: details.reason,
+                duration_seconds: details.duration_seconds,
+            };
+            insert_moderation_event(pool, &event)
+                .with_context(|| "Failed to insert moderation event")?;
+            Ok(())
+        })();
+        if let Err(e) = result {
+            eprintln!(
+                "[gulag] Failed to log {} of user {} in guild {}: {:#}",
+                details.action, userid, guildid, e
+            );
+        }
+    }
+
     pub async fn is_tugbot(http: &Arc<Http>, user: &User) -> Option<bool> {
         match http.get_current_user().await {
             Err(why) => {
@@ -220,63 +320,78 @@ impl Gulag {
             .try_into()
             .with_context(|| format!("Gulag length {} exceeds i32::MAX", params.gulaglength))?;
 
-        match Gulag::is_user_in_gulag(pool, params.userid) {
-            Some(gulag_db_user) => {
-                // Safely add to existing gulag time with overflow check
-                let new_length = gulag_db_user
-                    .gulag_length
-                    .checked_add(gulag_length_i32)
-                    .with_context(|| {
-                        format!(
-                            "Gulag length overflow: {} + {}",
-                            gulag_db_user.gulag_length, gulag_length_i32
-                        )
+        let gulag_user =
+            match Gulag::is_user_in_gulag(pool, params.userid) {
+                Some(gulag_db_user) => {
+                    // Safely add to existing gulag time with overflow check
+                    let new_length = gulag_db_user
+                        .gulag_length
+                        .checked_add(gulag_length_i32)
+                        .with_context(|| {
+                            format!(
+                                "Gulag length overflow: {} + {}",
+                                gulag_db_user.gulag_length, gulag_length_i32
+                            )
+                        })?;
+
+                    add_time_to_gulag(
+                        pool,
+                        gulag_db_user.id,
+                        new_length,
+                        gulag_length_i32,
+                        gulag_db_user.release_at,
+                        params.reason.as_deref(),
+                    )
+                    .with_context(|| "Failed to add time to gulag")?
+                }
+                None => {
+                    // Safe conversions for all Discord IDs
+                    let user_id_i64: i64 = params
+                        .userid
+                        .try_into()
+                        .with_context(|| format!("User ID {} exceeds i64::MAX", params.userid))?;
+                    let guild_id_i64: i64 = params
+                        .guildid
+                        .try_into()
+                        .with_context(|| format!("Guild ID {} exceeds i64::MAX", params.guildid))?;
+                    let role_id_i64: i64 = params.gulag_roleid.try_into().with_context(|| {
+                        format!("Role ID {} exceeds i64::MAX", params.gulag_roleid)
+                    })?;
+                    let channel_id_i64: i64 = params.channelid.try_into().with_context(|| {
+                        format!("Channel ID {} exceeds i64::MAX", params.channelid)
+                    })?;
+                    let message_id_i64: i64 = params.messageid.try_into().with_context(|| {
+                        format!("Message ID {} exceeds i64::MAX", params.messageid)
                     })?;
 
-                add_time_to_gulag(
-                    pool,
-                    gulag_db_user.id,
-                    new_length,
-                    gulag_length_i32,
-                    gulag_db_user.release_at,
-                )
-                .with_context(|| "Failed to add time to gulag")
-            }
-            None => {
-                // Safe conversions for all Discord IDs
-                let user_id_i64: i64 = params
-                    .userid
-                    .try_into()
-                    .with_context(|| format!("User ID {} exceeds i64::MAX", params.userid))?;
-                let guild_id_i64: i64 = params
-                    .guildid
-                    .try_into()
-                    .with_context(|| format!("Guild ID {} exceeds i64::MAX", params.guildid))?;
-                let role_id_i64: i64 = params
-                    .gulag_roleid
-                    .try_into()
-                    .with_context(|| format!("Role ID {} exceeds i64::MAX", params.gulag_roleid))?;
-                let channel_id_i64: i64 = params
-                    .channelid
-                    .try_into()
-                    .with_context(|| format!("Channel ID {} exceeds i64::MAX", params.channelid))?;
-                let message_id_i64: i64 = params
-                    .messageid
-                    .try_into()
-                    .with_context(|| format!("Message ID {} exceeds i64::MAX", params.messageid))?;
-
-                send_to_gulag(
-                    pool,
-                    user_id_i64,
-                    guild_id_i64,
-                    role_id_i64,
-                    gulag_length_i32,
-                    channel_id_i64,
-                    message_id_i64,
-                )
-                .with_context(|| "Failed to send user to gulag")
-            }
-        }
+                    send_to_gulag(
+                        pool,
+                        user_id_i64,
+                        guild_id_i64,
+                        role_id_i64,
+                        gulag_length_i32,
+                        channel_id_i64,
+                        message_id_i64,
+                        params.reason.as_deref(),
+                    )
+                    .with_context(|| "Failed to send user to gulag")?
+                }
+            };
+
+        Gulag::log_event(
+            pool,
+            params.guildid,
+            params.userid,
+            EventDetails {
+                action: ACTION_GULAG,
+                source: params.source,
+                moderator_id: params.moderator_id,
+                reason: params.reason.as_deref(),
+                duration_seconds: Some(gulag_length_i32),
+            },
+        );
+
+        Ok(gulag_user)
     }
 
     pub async fn send_to_gulag_and_message(
@@ -286,18 +401,27 @@ impl Gulag {
         userid: u64,
         channelid: u64,
         messageid: u64,
-        users: Option<Vec<User>>,
+        source: GulagSource,
     ) -> Result<()> {
         let gulag_role = Gulag::find_gulag_role(http, guildid)
             .await
             .with_context(|| "Couldn't find gulag role".to_string())?;
-        let gulaglength = 300;
-
-        let gulag_channel = Gulag::find_channel(http, guildid, "the-gulag".to_string())
+        let offense = source
+            .offense_type()
+            .with_context(|| format!("{} is not an offense", source.as_str()))?;
+        let gulag_channel = Gulag::find_gulag_channel(http, pool, guildid)
             .await
-            .with_context(|| "the-gulag channel lookup failed".to_string())?
-            .with_context(|| "the-gulag channel not found".to_string())?;
-        let gulag_user = Gulag::add_to_gulag(
+            .with_context(|| "gulag channel lookup failed".to_string())?
+            .with_context(|| "gulag channel not found".to_string())?;
+        let msg = http.get_message(channelid.into(), messageid.into()).await?;
+        let gulag_reason = match source {
+            GulagSource::Elon => format!("#1ElonMuskFan post {}", msg.link()),
+            _ => format!("Voted in for {}", msg.link()),
+        };
+
+        let outcome = Offenses::record(pool, guildid, userid, offense)?;
+        let gulaglength = u32::try_from(outcome.duration_seconds).unwrap_or(u32::MAX);
+        let added = Gulag::add_to_gulag(
             http,
             pool,
             GulagParams {
@@ -307,55 +431,129 @@ impl Gulag {
                 gulaglength,
                 channelid: gulag_channel.id.get(),
                 messageid,
+                source,
+                moderator_id: None,
+                reason: Some(gulag_reason.clone()),
             },
         )
-        .await
-        .with_context(|| "Failed to add user to gulag")?;
+        .await;
+        if added.is_err() {
+            Gulag::retract_offense(pool, guildid, userid, offense, &outcome);
+        }
+        let gulag_user = added.with_context(|| "Failed to add user to gulag")?;
 
-        let msg = http.get_message(channelid.into(), messageid.into()).await?;
         let member = http.get_member(guildid.into(), userid.into()).await?;
 
-        let mut user_string = "".to_string();
-        if let Some(user_list) = users {
-            user_string = "\nThese people voted them in".to_string();
-            for user in user_list {
-                user_string.push_str(format!(", {}", user).as_str());
-            }
-        }
-
         let content = format!(
-            "Sending {} to the Gulag for {} minutes because of {}, they have {} minutes remaining{}",
+            "Sending {} to the Gulag for {} (offense #{}). Reason: {}. They have {} minutes remaining",
             member.user,
-            gulaglength / 60,
-            msg.link(),
+            Gulag::format_duration(outcome.duration_seconds),
+            outcome.count,
+            gulag_reason,
             gulag_user.gulag_length / 60,
-            user_string,
         );
 
         gulag_channel.say(http, content).await?;
         Ok(())
     }
 
-    async fn remove_from_gulag(
-        http: Arc<Http>,
-        userid: u64,
+    /// Take back an offense whose sentence couldn't be applied.
+    pub fn retract_offense(
+        pool: &DbPool,
         guildid: u64,
-        gulag_roleid: RoleId,
+        userid: u64,
+        offense: OffenseType,
+        outcome: &Outcome,
+    ) {
+        if let Err(e) = Offenses::retract(pool, guildid, userid, offense, outcome) {
+            eprintln!("Failed to retract {} offense: {:#}", offense.as_str(), e);
+        }
+    }
+
+    /// Remove the gulag role, announce the release and log it. The caller is
+    /// responsible for deleting the `gulag_users` row.
+    pub async fn remove_from_gulag(
+        http: Arc<Http>,
+        pool: &DbPool,
+        gulag_user: &GulagUser,
+        source: GulagSource,
+        moderator_id: Option<u64>,
     ) -> Result<()> {
+        // Safe conversion for Discord IDs (i64 -> u64)
+        let userid = u64::try_from(gulag_user.user_id)
+            .with_context(|| format!("User ID {} is negative", gulag_user.user_id))?;
+        let guildid = u64::try_from(gulag_user.guild_id)
+            .with_context(|| format!("Guild ID {} is negative", gulag_user.guild_id))?;
+        let gulag_roleid = u64::try_from(gulag_user.gulag_role_id)
+            .with_context(|| format!("Role ID {} is negative", gulag_user.gulag_role_id))?;
+
         let mem = http.get_member(guildid.into(), userid.into()).await?;
-        mem.remove_role(&http, gulag_roleid).await?;
-        let channel_opt = Gulag::find_channel(&http, guildid, "the-gulag".to_string())
+        mem.remove_role(&http, RoleId::new(gulag_roleid)).await?;
+        let channel_opt = Gulag::find_gulag_channel(&http, pool, guildid)
             .await
-            .with_context(|| "the-gulag channel lookup failed".to_string())?;
-        let channel = channel_opt.ok_or_else(|| anyhow::anyhow!("the-gulag channel not found"))?;
-        let message = format!("Freeing {} from the gulag", mem);
+            .with_context(|| "gulag channel lookup failed".to_string())?;
+        let channel = channel_opt.ok_or_else(|| anyhow::anyhow!("gulag channel not found"))?;
+        let message = Gulag::release_message(&mem.to_string(), gulag_user.reason.as_deref());
         channel
             .send_message(&http, CreateMessage::new().content(message))
             .await?;
         eprintln!("Removed from gulag");
+
+        Gulag::log_event(
+            pool,
+            guildid,
+            userid,
+            EventDetails {
+                action: ACTION_RELEASE,
+                source,
+                moderator_id,
+                reason: None,
+                duration_seconds: None,
+            },
+        );
         Ok(())
     }
 
+    /// Release a user before their sentence is up and delete their row.
+    /// Shared by `/gulag-release` and approved appeals.
+    pub async fn release_early(
+        http: Arc<Http>,
+        pool: &DbPool,
+        gulag_user: &GulagUser,
+        source: GulagSource,
+        moderator_id: Option<u64>,
+    ) -> Result<()> {
+        Gulag::remove_from_gulag(http, pool, gulag_user, source, moderator_id).await?;
+
+        // The role is already gone, so a failed cleanup isn't the caller's problem.
+        match pool.get() {
+            Ok(mut conn) => {
+                match diesel::delete(gulag_users.filter(gulag_users::id.eq(gulag_user.id)))
+                    .execute(&mut conn)
+                {
+                    Ok(_) => {
+                        eprintln!("Removed from database");
+                        Gulag::close_appeals(pool, gulag_user);
+                    }
+                    Err(e) => eprintln!("Failed to delete gulag user from DB: {}", e),
+                }
+            }
+            Err(e) => eprintln!("Failed to get database connection for cleanup: {}", e),
+        }
+        Ok(())
+    }
+
+    /// Expire any appeal still pending against a sentence whose row is gone.
+    fn close_appeals(pool: &DbPool, gulag_user: &GulagUser) {
+        if let Err(e) = expire_pending_gulag_appeals(pool, gulag_user.guild_id, gulag_user.user_id)
+        {
+            eprintln!(
+                "Failed to expire appeals for gulag user {}: {}",
+                gulag_user.id, e
+            );
+        }
+    }
+
     pub fn run_gulag_check(http: &Arc<Http>, pool: DbPool) {
         let http = Arc::clone(http);
         spawn(async move {
@@ -408,37 +606,12 @@ impl Gulag {
                             continue;
                         }
 
-                        // Safe conversion for Discord IDs (i64 -> u64)
-                        let user_id_u64 = match u64::try_from(result.user_id) {
-                            Ok(uid) => uid,
-                            Err(e) => {
-                                eprintln!("User ID conversion error for user {}: {}", result.id, e);
-                                continue;
-                            }
-                        };
-                        let guild_id_u64 = match u64::try_from(result.guild_id) {
-                            Ok(gid) => gid,
-                            Err(e) => {
-                                eprintln!(
-                                    "Guild ID conversion error for user {}: {}",
-                                    result.id, e
-                                );
-                                continue;
-                            }
-                        };
-                        let role_id_u64 = match u64::try_from(result.gulag_role_id) {
-                            Ok(rid) => rid,
-                            Err(e) => {
-                                eprintln!("Role ID conversion error for user {}: {}", result.id, e);
-                                continue;
-                            }
-                        };
-
                         match Gulag::remove_from_gulag(
                             http.to_owned(),
-                            user_id_u64,
-                            guild_id_u64,
-                            RoleId::new(role_id_u64),
+                            &pool,
+                            &result,
+                            GulagSource::Timer,
+                            None,
                         )
                         .await
                         {
@@ -452,6 +625,7 @@ impl Gulag {
                                     continue;
                                 }
                                 eprintln!("Removed from database");
+                                Gulag::close_appeals(&pool, &result);
 
                                 if result.message_id != 0 {
                                     // Done the vote from the database
@@ -499,6 +673,7 @@ impl Gulag {
                                             "Removed stale gulag user {} from database ({} not found)",
                                             result.id, why
                                         );
+                                        Gulag::close_appeals(&pool, &result);
                                     }
                                 } else {
                                     eprintln!("Error run_gulag_check: {:?}", why);
@@ -642,7 +817,7 @@ impl Gulag {
                 user_id_u64,
                 channel_id_u64,
                 message_id_u64,
-                None,
+                GulagSource::ReactionVote,
             )
             .await
             {
@@ -726,6 +901,26 @@ mod tests {
         assert!(!Gulag::is_discord_not_found(&err));
     }
 
+    #[test]
+    fn release_message_includes_reason() {
+        assert_eq!(
+            Gulag::release_message("@bob", Some("spamming")),
+            "Freeing @bob from the gulag (sent for: spamming)"
+        );
+        assert_eq!(
+            Gulag::release_message("@bob", None),
+            "Freeing @bob from the gulag"
+        );
+    }
+
+    #[test]
+    fn gulag_source_round_trips() {
+        for source in GulagSource::ALL {
+            assert_eq!(GulagSource::parse(source.as_str()), Some(source));
+        }
+        assert_eq!(GulagSource::parse("unknown"), None);
+    }
+
     #[test]
     fn test_send_error_formats_correctly() {
         let error_msg = "Something went wrong";
@@ -0,0 +1,117 @@
+//! Routing for message component (button, select menu) and modal
+//! interactions.
+//!
+//! Handlers put a fixed prefix at the start of every custom_id they create,
+//! e.g. `gulag-appeal:approve:42`, and register a callback for that prefix
+//! in `interaction_router` in `handlers/mod.rs`. Callbacks are responsible
+//! for responding to the interaction themselves, since they may need to
+//! update the original message rather than send a new one.
+
+use std::{future::Future, pin::Pin};
+
+use serenity::{
+    all::{
+        ComponentInteraction, CreateInteractionResponse, CreateInteractionResponseMessage,
+        Interaction, ModalInteraction,
+    },
+    client::Context,
+};
+
+pub type BoxedFuture<'a> = Pin<Box<dyn Future<Output = ()> + Send + 'a>>;
+pub type ComponentCallback = for<'a> fn(&'a Context, &'a ComponentInteraction) -> BoxedFuture<'a>;
+pub type ModalCallback = for<'a> fn(&'a Context, &'a ModalInteraction) -> BoxedFuture<'a>;
+
+#[derive(Default)]
+pub struct InteractionRouter {
+    components: Vec<(&'static str, ComponentCallback)>,
+    modals: Vec<(&'static str, ModalCallback)>,
+}
+
+impl InteractionRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Route components whose custom_id starts with `prefix` to `callback`.
+    pub fn component(mut self, prefix: &'static str, callback: ComponentCallback) -> Self {
+        self.components.push((prefix, callback));
+        self
+    }
+
+    /// Route modal submissions whose custom_id starts with `prefix` to `callback`.
+    pub fn modal(mut self, prefix: &'static str, callback: ModalCallback) -> Self {
+        self.modals.push((prefix, callback));
+        self
+    }
+
+    /// Dispatch a component or modal interaction. Interactions with no
+    /// matching route get an ephemeral "no longer active" reply, so the
+    /// clicker isn't left with Discord's generic failure message.
+    pub async fn dispatch(&self, ctx: &Context, interaction: &Interaction) {
+        match interaction {
+            Interaction::Component(component) => {
+                match Self::find(&self.components, &component.data.custom_id) {
+                    Some(callback) => callback(ctx, component).await,
+                    None => {
+                        eprintln!(
+                            "[interactions] No route for component '{}'",
+                            component.data.custom_id
+                        );
+                        if let Err(why) = component
+                            .create_response(&ctx.http, Self::inactive_response())
+                            .await
+                        {
+                            eprintln!("Cannot respond to component: {}", why);
+                        }
+                    }
+                }
+            }
+            Interaction::Modal(modal) => match Self::find(&self.modals, &modal.data.custom_id) {
+                Some(callback) => callback(ctx, modal).await,
+                None => {
+                    eprintln!(
+                        "[interactions] No route for modal '{}'",
+                        modal.data.custom_id
+                    );
+                    if let Err(why) = modal
+                        .create_response(&ctx.http, Self::inactive_response())
+                        .await
+                    {
+                        eprintln!("Cannot respond to modal: {}", why);
+                    }
+                }
+            },
+            _ => {}
+        }
+    }
+
+    /// The route with the longest prefix matching `custom_id`.
+    fn find<'a, T>(routes: &'a [(&'static str, T)], custom_id: &str) -> Option<&'a T> {
+        routes
+            .iter()
+            .filter(|(prefix, _)| custom_id.starts_with(prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, callback)| callback)
+    }
+
+    fn inactive_response() -> CreateInteractionResponse {
+        CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content("This button is no longer active.")
+                .ephemeral(true),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_prefers_longest_prefix() {
+        let routes = [("cull:", 1), ("cull:confirm:", 2), ("gulag-appeal:", 3)];
+        assert_eq!(InteractionRouter::find(&routes, "cull:confirm:9"), Some(&2));
+        assert_eq!(InteractionRouter::find(&routes, "cull:cancel:9"), Some(&1));
+        assert_eq!(InteractionRouter::find(&routes, "feature:next:2"), None);
+    }
+}
@@ -1,18 +1,24 @@
 use base64::prelude::BASE64_STANDARD;
 use base64::Engine;
-use std::{path::Path, sync::Arc, time::Duration, time::SystemTime};
+use std::{path::Path, sync::Arc, time::Duration, time::Instant, time::SystemTime};
 
+use crate::ask::{AskBusy, AskEvent, AskStream};
 use crate::db::{
+    continue_conversation, get_conversation_for_answer, get_conversation_history,
     get_is_this_real_usage, get_or_create_is_this_real_usage, get_server_by_guild_id,
-    update_is_this_real_usage, DbPool,
+    get_thread_conversation,
+    models::{ConversationMessage, NewConversation, NewConversationMessage},
+    start_conversation, update_is_this_real_usage, DbPool,
 };
 use crate::features::Features;
 use crate::handlers::get_config;
 use crate::handlers::get_pool;
-use crate::handlers::gulag::{Gulag, GulagParams};
+use crate::handlers::gulag::{Gulag, GulagParams, GulagSource};
+use crate::offenses::{OffenseType, Offenses};
+use crate::tugbot::guild_config::GuildConfig;
 use serenity::{
-    all::{Http, Mentionable},
-    builder::CreateMessage,
+    all::{ChannelId, Http, Mentionable, MessageId},
+    builder::{CreateMessage, EditMessage},
     model::prelude::Message,
     prelude::Context,
 };
@@ -61,20 +67,111 @@ fn format_remaining(seconds: u64) -> String {
     }
 }
 
+/// A reply while the backend is still writing it: the text so far and what it is
+/// doing right now.
+#[derive(Default)]
+struct StreamingReply {
+    text: String,
+    status: Option<String>,
+}
+
+impl StreamingReply {
+    fn apply(&mut self, event: AskEvent) {
+        match event {
+            AskEvent::TextDelta(delta) => self.text.push_str(&delta),
+            AskEvent::ToolStart { name, args } => {
+                // Text before a tool call isn't part of the final answer
+                self.text.clear();
+                self.status = Some(tool_status(&name, &args));
+            }
+            AskEvent::ToolEnd { .. } => self.status = None,
+        }
+    }
+
+    fn render(&self) -> String {
+        let text = self.text.trim();
+        let mut content: String = text.chars().take(STREAM_PREVIEW_CHARS).collect();
+        if content.len() < text.len() {
+            content.push('…');
+        }
+        let status = self.status.as_deref().unwrap_or("thinking…");
+        if content.is_empty() || self.status.is_some() {
+            if !content.is_empty() {
+                content.push_str("\n\n");
+            }
+            content.push_str("-# ");
+            content.push_str(status);
+        }
+        content
+    }
+}
+
+/// Status line shown while the backend runs a tool.
+fn tool_status(name: &str, args: &serde_json::Value) -> String {
+    match name {
+        "web_search" => match args.get("query").and_then(|q| q.as_str()) {
+            Some(query) => {
+                let query: String = query.chars().take(100).collect();
+                format!("searching the web for \"{}\"…", query)
+            }
+            None => "searching the web…".to_string(),
+        },
+        "fetch_content" => "reading a page…".to_string(),
+        other => format!("running {}…", other),
+    }
+}
+
+/// The prompt for pi: earlier turns of the conversation, if any, then the
+/// new question.
+fn format_prompt(
+    author: &str,
+    question: &str,
+    replied_to: Option<&str>,
+    history: &[ConversationMessage],
+) -> String {
+    let ask = match replied_to {
+        Some(context) => format!(
+            "{} replied to: \"{}\" and asked: \"{}\"",
+            author, context, question
+        ),
+        None => format!("{} asked: \"{}\"", author, question),
+    };
+    if history.is_empty() {
+        return ask;
+    }
+
+    let mut prompt = String::from("Earlier in this conversation:\n");
+    for turn in history {
+        let line = match turn.role.as_str() {
+            "assistant" => format!("You answered: \"{}\"\n", turn.content),
+            _ => format!("{} asked: \"{}\"\n", turn.author_name, turn.content),
+        };
+        prompt.push_str(&line);
+    }
+    prompt.push_str("\nNow ");
+    prompt.push_str(&ask);
+    prompt
+}
+
 pub struct Mention;
 
 const COOLDOWN_SECS: u64 = 300; // 5m between uses
 const SLOW_COOLDOWN_SECS: u64 = 7_200; // 2h between uses
-const GULAG_DURATION_SECS: u32 = 300; // 5 minutes
 const SLOW_USER_AUTO_GULAG_FEATURE: &str = "slow_user_auto_gulag";
-const ASK_TUGBOT_CHANNEL_ID: u64 = 1515343076401479790; // #ask-tugbot
+/// Earlier turns (questions and answers) included in a follow-up prompt.
+const CONVERSATION_HISTORY_MESSAGES: i64 = 10;
+/// Minimum time between edits of a streamed reply.
+const STREAM_EDIT_INTERVAL: Duration = Duration::from_millis(1500);
+/// Streamed text shown before the answer is complete, leaving room for
+/// the status line under Discord's 2000-character limit.
+const STREAM_PREVIEW_CHARS: usize = 1800;
 
 impl Mention {
     pub async fn handler(ctx: &Context, msg: &Message) {
         // 1. Feature flag check
         // Note: DB key is still "is_this_real" for backward compat — rename via migration later
         let pool = get_pool(ctx).await;
-        if !Features::is_enabled(&pool, "is_this_real") {
+        if !Features::is_enabled(&pool, msg.guild_id.map(|g| g.get()), "is_this_real") {
             return;
         }
         eprintln!(
@@ -101,10 +198,18 @@ impl Mention {
             None => return,
         };
 
-        // 4. Channel restriction — only respond to mentions in #ask-tugbot
-        if msg.channel_id.get() != ASK_TUGBOT_CHANNEL_ID {
-            return;
-        }
+        // 4. Channel restriction — only respond to mentions in the guild's
+        //    ask channel (#ask-tugbot unless configured via /config) or in
+        //    threads started from it
+        let ask_channel_id = GuildConfig::load(&pool, guild_id.get()).ask_channel_id();
+        let thread_id = if msg.channel_id.get() == ask_channel_id {
+            None
+        } else {
+            match Self::ask_thread(ctx, msg, ask_channel_id).await {
+                Some(thread_id) => Some(thread_id),
+                None => return,
+            }
+        };
 
         // 5. Config — slow_user_ids only affects the per-user cooldown (longer
         //    cooldown for throttled users). The auto-gulag-on-mention behavior
@@ -117,7 +222,7 @@ impl Mention {
         let slow_user_ids = &config.slow_user_ids;
         let cooldown_exempt_user_ids = &config.cooldown_exempt_user_ids;
         if slow_user_ids.contains(&msg.author.id.get())
-            && Features::is_enabled(&pool, SLOW_USER_AUTO_GULAG_FEATURE)
+            && Features::is_enabled(&pool, Some(guild_id.get()), SLOW_USER_AUTO_GULAG_FEATURE)
         {
             Mention::handle_slow_user_auto_gulag(&ctx.http, &pool, guild_id.get(), msg).await;
             return;
@@ -307,52 +412,80 @@ impl Mention {
             }
         }
 
-        // 11. Get pi RPC
-        let pi_rpc = match (ctx.data.read().await).get::<crate::handlers::PiRpcKey>() {
+        // 11. Get the ask backend
+        let backend = match (ctx.data.read().await).get::<crate::handlers::AskBackendKey>() {
             Some(rpc) => rpc.clone(),
             None => {
-                eprintln!("[mention] pi RPC not available");
+                eprintln!("[mention] Ask backend not available");
                 return;
             }
         };
 
-        // 12. Build prompt — include referenced message context
-        let prompt = match &referenced_msg {
-            Some(ref_msg) => {
-                let context = match (!ref_msg.content.is_empty(), !images.is_empty()) {
+        // 12. Find the conversation this continues — the thread's, or the
+        //     one tugbot's replied-to answer belongs to
+        let conversation = match (thread_id, &referenced_msg) {
+            (Some(thread_id), _) => {
+                get_thread_conversation(&pool, guild_id_u64 as i64, thread_id.get() as i64)
+            }
+            (None, Some(ref_msg)) if ref_msg.author.id == bot_user.id => {
+                get_conversation_for_answer(&pool, guild_id_u64 as i64, ref_msg.id.get() as i64)
+            }
+            _ => Ok(None),
+        }
+        .unwrap_or_else(|e| {
+            eprintln!("[mention] Failed to look up conversation: {}", e);
+            None
+        });
+        let history = match &conversation {
+            Some(conversation) => {
+                get_conversation_history(&pool, conversation.id, CONVERSATION_HISTORY_MESSAGES)
+                    .unwrap_or_else(|e| {
+                        eprintln!("[mention] Failed to load conversation history: {}", e);
+                        Vec::new()
+                    })
+            }
+            None => Vec::new(),
+        };
+
+        // 13. Build prompt — include referenced message context unless the
+        //     history already has it
+        let replied_to = referenced_msg
+            .as_ref()
+            .filter(|ref_msg| {
+                !history
+                    .iter()
+                    .any(|turn| turn.message_id == ref_msg.id.get() as i64)
+            })
+            .map(
+                |ref_msg| match (!ref_msg.content.is_empty(), !images.is_empty()) {
                     (true, true) => format!("{} [also shared an image]", ref_msg.content),
                     (false, true) => format!("[shared an image ({})]", images.len()),
                     (true, false) => ref_msg.content.clone(),
                     (false, false) => String::from("[replied to an image]"),
-                };
-                format!(
-                    "{} replied to: \"{}\" and asked: \"{}\"",
-                    msg.author.name, context, question
-                )
-            }
-            None => {
-                format!("{} asked: \"{}\"", msg.author.name, question)
-            }
+                },
+            );
+        let prompt = format_prompt(&msg.author.name, &question, replied_to.as_deref(), &history);
+
+        // 14. Stream the answer into a placeholder reply
+        let (placeholder, answer) = match backend.ask_streaming(&prompt, &images) {
+            Ok(stream) => Self::stream_answer(ctx, msg, stream).await,
+            Err(e) => (None, Err(e)),
         };
+        let _ = msg
+            .channel_id
+            .delete_reaction(&ctx.http, msg.id, Some(bot_user.id), '\u{1F914}')
+            .await;
 
-        let final_text = match pi_rpc.ask_with_images(&prompt, &images).await {
+        let final_text = match answer {
             Ok(text) => text.trim().to_string(),
             Err(e) => {
-                eprintln!("[mention] pi RPC ask failed: {}", e);
-                let _ = msg
-                    .channel_id
-                    .delete_reaction(&ctx.http, msg.id, Some(bot_user.id), '\u{1F914}')
-                    .await;
-                if let Err(why) = msg
-                    .channel_id
-                    .send_message(
-                        &ctx.http,
-                        CreateMessage::new()
-                            .content("I'm having trouble thinking right now, try again later")
-                            .reference_message((msg.channel_id, msg.id)),
-                    )
-                    .await
-                {
+                eprintln!("[mention] Ask failed: {}", e);
+                let reply = if e.downcast_ref::<AskBusy>().is_some() {
+                    "I'm busy answering other questions right now, try again in a bit"
+                } else {
+                    "I'm having trouble thinking right now, try again later"
+                };
+                if let Err(why) = Self::post_or_edit(ctx, msg, placeholder, reply).await {
                     eprintln!("[mention] Failed to send error message: {}", why);
                 }
                 return;
@@ -361,42 +494,67 @@ impl Mention {
 
         // Don't post or update cooldown for empty responses
         if final_text.is_empty() {
-            eprintln!("[mention] pi returned empty response, skipping post and cooldown update");
-            let _ = msg
-                .channel_id
-                .delete_reaction(&ctx.http, msg.id, Some(bot_user.id), '\u{1F914}')
-                .await;
+            eprintln!(
+                "[mention] Backend returned empty response, skipping post and cooldown update"
+            );
+            if let Some(placeholder) = placeholder {
+                let _ = placeholder.delete(&ctx.http).await;
+            }
             return;
         }
 
-        // 14. Remove thinking emoji and post response
-        let _ = msg
-            .channel_id
-            .delete_reaction(&ctx.http, msg.id, Some(bot_user.id), '\u{1F914}')
-            .await;
+        // 15. Replace the placeholder with the full response
         eprintln!("[mention] Posting response...");
-        let posted = match msg
-            .channel_id
-            .send_message(
-                &ctx.http,
-                CreateMessage::new()
-                    .content(final_text.trim())
-                    .reference_message((msg.channel_id, msg.id)),
-            )
-            .await
-        {
-            Ok(_) => {
+        let posted = match Self::post_or_edit(ctx, msg, placeholder, &final_text).await {
+            Ok(answer_id) => {
                 eprintln!("[mention] Response posted");
-                true
+                Some(answer_id)
             }
             Err(why) => {
                 eprintln!("[mention] Failed to post response: {}", why);
-                false
+                None
             }
         };
 
-        // 15. Update cooldown only if response was delivered — skip exempt users
-        if posted && !cooldown_exempt_user_ids.contains(&user_id) {
+        // 16. Remember the exchange so follow-ups can pick it up
+        if let Some(answer_id) = posted {
+            let turns = [
+                NewConversationMessage {
+                    message_id: msg.id.get() as i64,
+                    asked_by: user_id as i64,
+                    role: "user",
+                    author_name: &msg.author.name,
+                    content: &question,
+                },
+                NewConversationMessage {
+                    message_id: answer_id.get() as i64,
+                    asked_by: user_id as i64,
+                    role: "assistant",
+                    author_name: &bot_user.name,
+                    content: &final_text,
+                },
+            ];
+            let saved = match &conversation {
+                Some(conversation) => continue_conversation(&pool, conversation.id, &turns),
+                None => start_conversation(
+                    &pool,
+                    &NewConversation {
+                        guild_id: guild_id_u64 as i64,
+                        channel_id: msg.channel_id.get() as i64,
+                        thread_id: thread_id.map(|id| id.get() as i64),
+                        started_by: user_id as i64,
+                    },
+                    &turns,
+                )
+                .map(|_| ()),
+            };
+            if let Err(e) = saved {
+                eprintln!("[mention] Failed to save conversation: {}", e);
+            }
+        }
+
+        // 17. Update cooldown only if response was delivered — skip exempt users
+        if posted.is_some() && !cooldown_exempt_user_ids.contains(&user_id) {
             let usage_result =
                 get_or_create_is_this_real_usage(&pool, user_id as i64, guild_id_u64 as i64);
             if let Ok(u) = usage_result {
@@ -407,9 +565,110 @@ impl Mention {
         }
     }
 
+    /// Post a placeholder reply and edit it as the backend streams its answer, at
+    /// most once per `STREAM_EDIT_INTERVAL`. Returns the placeholder (if it
+    /// could be posted) and the final answer.
+    async fn stream_answer(
+        ctx: &Context,
+        msg: &Message,
+        mut stream: AskStream,
+    ) -> (Option<Message>, anyhow::Result<String>) {
+        let mut reply = StreamingReply::default();
+        let mut shown = reply.render();
+        let mut placeholder = match msg
+            .channel_id
+            .send_message(
+                &ctx.http,
+                CreateMessage::new()
+                    .content(&shown)
+                    .reference_message((msg.channel_id, msg.id)),
+            )
+            .await
+        {
+            Ok(placeholder) => Some(placeholder),
+            Err(why) => {
+                eprintln!("[mention] Failed to post placeholder: {}", why);
+                None
+            }
+        };
+
+        let mut last_edit = Instant::now();
+        loop {
+            // Wake up at least once per interval so a pending edit isn't
+            // held back until the next event
+            match tokio::time::timeout(STREAM_EDIT_INTERVAL, stream.next_event()).await {
+                Ok(Some(event)) => reply.apply(event),
+                Ok(None) => break,
+                Err(_) => {}
+            }
+            let Some(placeholder) = placeholder.as_mut() else {
+                continue;
+            };
+            if last_edit.elapsed() < STREAM_EDIT_INTERVAL {
+                continue;
+            }
+            let content = reply.render();
+            if content == shown {
+                continue;
+            }
+            if let Err(why) = placeholder
+                .edit(&ctx.http, EditMessage::new().content(&content))
+                .await
+            {
+                eprintln!("[mention] Failed to update streamed reply: {}", why);
+            }
+            shown = content;
+            last_edit = Instant::now();
+        }
+
+        (placeholder, stream.finish().await)
+    }
+
+    /// The thread `msg` was posted in, if it was started from the ask channel.
+    async fn ask_thread(ctx: &Context, msg: &Message, ask_channel_id: u64) -> Option<ChannelId> {
+        let channel = match msg.channel_id.to_channel(&ctx.http).await {
+            Ok(channel) => channel.guild()?,
+            Err(e) => {
+                eprintln!("[mention] Failed to fetch channel: {}", e);
+                return None;
+            }
+        };
+        let from_ask_channel = channel.parent_id.map(|id| id.get()) == Some(ask_channel_id);
+        (channel.thread_metadata.is_some() && from_ask_channel).then_some(channel.id)
+    }
+
+    /// Edit the placeholder to `content`, or reply with it if there is none.
+    /// Returns the ID of the message holding the reply.
+    async fn post_or_edit(
+        ctx: &Context,
+        msg: &Message,
+        placeholder: Option<Message>,
+        content: &str,
+    ) -> serenity::Result<MessageId> {
+        match placeholder {
+            Some(mut placeholder) => {
+                placeholder
+                    .edit(&ctx.http, EditMessage::new().content(content))
+                    .await?;
+                Ok(placeholder.id)
+            }
+            None => msg
+                .channel_id
+                .send_message(
+                    &ctx.http,
+                    CreateMessage::new()
+                        .content(content)
+                        .reference_message((msg.channel_id, msg.id)),
+                )
+                .await
+                .map(|reply| reply.id),
+        }
+    }
+
     /// Slow-user auto-gulag handler — fires when the `slow_user_auto_gulag`
     /// feature flag is enabled and the message author is in SLOW_USER_IDS.
-    /// Any mention in #ask-tugbot gulags them for GULAG_DURATION_SECS.
+    /// Any mention in the ask channel gulags them under the `slow_user`
+    /// offense policy.
     async fn handle_slow_user_auto_gulag(
         http: &Arc<Http>,
         pool: &DbPool,
@@ -427,32 +686,49 @@ impl Mention {
             }
         };
 
-        let gulag_channel =
-            match Gulag::find_channel(http, guild_id_u64, "the-gulag".to_string()).await {
-                Ok(Some(c)) => c,
-                Ok(None) => {
-                    eprintln!("[mention] No gulag channel found");
-                    return;
-                }
-                Err(e) => {
-                    eprintln!("[mention] Error looking up gulag channel: {}", e);
-                    return;
-                }
-            };
+        let gulag_channel = match Gulag::find_gulag_channel(http, pool, guild_id_u64).await {
+            Ok(Some(c)) => c,
+            Ok(None) => {
+                eprintln!("[mention] No gulag channel found");
+                return;
+            }
+            Err(e) => {
+                eprintln!("[mention] Error looking up gulag channel: {}", e);
+                return;
+            }
+        };
+
+        let gulag_roleid = match u64::try_from(server.gulag_id) {
+            Ok(id) => id,
+            Err(_) => {
+                eprintln!("[mention] gulag role ID {} overflows u64", server.gulag_id);
+                return;
+            }
+        };
+
+        let outcome = match Offenses::record(
+            pool,
+            guild_id_u64,
+            msg.author.id.get(),
+            OffenseType::SlowUser,
+        ) {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                eprintln!("[mention] Failed to record slow-user offense: {:#}", e);
+                return;
+            }
+        };
 
         let params = GulagParams {
             guildid: guild_id_u64,
             userid: msg.author.id.get(),
-            gulag_roleid: match u64::try_from(server.gulag_id) {
-                Ok(id) => id,
-                Err(_) => {
-                    eprintln!("[mention] gulag role ID {} overflows u64", server.gulag_id);
-                    return;
-                }
-            },
-            gulaglength: GULAG_DURATION_SECS,
+            gulag_roleid,
+            gulaglength: outcome.duration_seconds.try_into().unwrap_or(u32::MAX),
             channelid: gulag_channel.id.get(),
             messageid: msg.id.get(),
+            source: GulagSource::SlowUser,
+            moderator_id: None,
+            reason: Some(format!("Asked tugbot if something was real {}", msg.link())),
         };
 
         match Gulag::add_to_gulag(http, pool, params).await {
@@ -462,8 +738,9 @@ impl Mention {
                     .send_message(
                         http,
                         CreateMessage::new().content(format!(
-                            "{} wanted to know if something was real... now they're in the gulag for 5m. Irony.",
-                            msg.author.mention()
+                            "{} wanted to know if something was real... now they're in the gulag for {}. Irony.",
+                            msg.author.mention(),
+                            Gulag::format_duration(outcome.duration_seconds)
                         )),
                     )
                     .await
@@ -473,6 +750,13 @@ impl Mention {
             }
             Err(e) => {
                 eprintln!("[mention] Failed to gulag slow user: {}", e);
+                Gulag::retract_offense(
+                    pool,
+                    guild_id_u64,
+                    msg.author.id.get(),
+                    OffenseType::SlowUser,
+                    &outcome,
+                );
             }
         }
     }
@@ -480,7 +764,11 @@ impl Mention {
 
 #[cfg(test)]
 mod tests {
-    use super::{format_remaining, mime_for_url};
+    use super::{
+        format_prompt, format_remaining, mime_for_url, AskEvent, ConversationMessage,
+        StreamingReply, STREAM_PREVIEW_CHARS,
+    };
+    use crate::ask::{AskBackend, FakeBackend, FakeReply};
 
     #[test]
     fn mime_for_url_png() {
@@ -550,4 +838,108 @@ mod tests {
         assert_eq!(format_remaining(7_200), "2h");
         assert_eq!(format_remaining(7_260), "2h 1m");
     }
+
+    #[test]
+    fn streaming_reply_shows_status_until_text_arrives() {
+        let mut reply = StreamingReply::default();
+        assert_eq!(reply.render(), "-# thinking…");
+
+        reply.apply(AskEvent::TextDelta("Let me check.".to_string()));
+        assert_eq!(reply.render(), "Let me check.");
+
+        reply.apply(AskEvent::ToolStart {
+            name: "web_search".to_string(),
+            args: serde_json::json!({"query": "moon landing"}),
+        });
+        assert_eq!(reply.render(), "-# searching the web for \"moon landing\"…");
+
+        reply.apply(AskEvent::ToolEnd {
+            name: "web_search".to_string(),
+        });
+        reply.apply(AskEvent::TextDelta("Yes, ".to_string()));
+        reply.apply(AskEvent::TextDelta("it's real".to_string()));
+        assert_eq!(reply.render(), "Yes, it's real");
+    }
+
+    #[test]
+    fn streaming_reply_truncates_long_previews() {
+        let mut reply = StreamingReply::default();
+        reply.apply(AskEvent::TextDelta("é".repeat(STREAM_PREVIEW_CHARS + 5)));
+        reply.apply(AskEvent::ToolStart {
+            name: "fetch_content".to_string(),
+            args: serde_json::Value::Null,
+        });
+        reply.apply(AskEvent::TextDelta("x".repeat(STREAM_PREVIEW_CHARS + 5)));
+        let content = reply.render();
+        assert!(content.starts_with(&format!("{}…\n\n", "x".repeat(STREAM_PREVIEW_CHARS))));
+        assert!(content.ends_with("-# reading a page…"));
+    }
+
+    #[tokio::test]
+    async fn streaming_reply_follows_a_backend_stream() {
+        let backend = FakeBackend::new([FakeReply::Answer {
+            events: vec![
+                AskEvent::TextDelta("Let me look.".to_string()),
+                AskEvent::ToolStart {
+                    name: "web_search".to_string(),
+                    args: serde_json::json!({"query": "moon"}),
+                },
+                AskEvent::ToolEnd {
+                    name: "web_search".to_string(),
+                },
+                AskEvent::TextDelta("Not cheese.".to_string()),
+            ],
+            text: "Not cheese.".to_string(),
+        }]);
+
+        let mut stream = backend.ask_streaming("is the moon cheese?", &[]).unwrap();
+        let mut reply = StreamingReply::default();
+        let mut shown = Vec::new();
+        while let Some(event) = stream.next_event().await {
+            reply.apply(event);
+            shown.push(reply.render());
+        }
+        assert_eq!(
+            shown,
+            vec![
+                "Let me look.",
+                "-# searching the web for \"moon\"…",
+                "-# thinking…",
+                "Not cheese.",
+            ]
+        );
+        assert_eq!(stream.finish().await.unwrap(), reply.text);
+        assert_eq!(backend.prompts(), vec!["is the moon cheese?"]);
+    }
+
+    #[test]
+    fn format_prompt_puts_history_before_the_question() {
+        assert_eq!(
+            format_prompt("alice", "is it real?", Some("the moon is cheese"), &[]),
+            "alice replied to: \"the moon is cheese\" and asked: \"is it real?\""
+        );
+
+        let turn = |role: &str, author_name: &str, content: &str| ConversationMessage {
+            id: 0,
+            conversation_id: 1,
+            message_id: 0,
+            asked_by: 0,
+            role: role.to_string(),
+            author_name: author_name.to_string(),
+            content: content.to_string(),
+            created_at: std::time::SystemTime::UNIX_EPOCH,
+        };
+        let history = [
+            turn("user", "alice", "is the moon cheese?"),
+            turn("assistant", "tugbot", "No."),
+        ];
+        assert_eq!(
+            format_prompt("bob", "what is it then?", None, &history),
+            "Earlier in this conversation:\n\
+             alice asked: \"is the moon cheese?\"\n\
+             You answered: \"No.\"\n\
+             \n\
+             Now bob asked: \"what is it then?\""
+        );
+    }
 }
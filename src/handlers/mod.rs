@@ -1,21 +1,28 @@
 // pub mod elkmen;
+pub mod activity;
 pub mod ai_slop;
+pub mod amnesty;
 pub mod bsky;
+pub mod config;
 pub mod cull;
 pub mod derpies;
 pub mod elon;
 pub mod feat;
+pub mod forget;
 pub mod goku_poll;
 pub mod gulag;
 pub mod instagram;
+pub mod interactions;
 pub mod mention;
+pub mod permissions;
 pub mod prefix_handler;
+pub mod stats;
 pub mod teh;
 pub mod tiktok;
 pub mod twitter;
 
+use crate::ask::{spawn_backend, AskBackend};
 use crate::db::DbPool;
-use crate::pi_rpc::PiRpc;
 use crate::tugbot::config::Config;
 use serenity::prelude::TypeMapKey;
 
@@ -49,60 +56,164 @@ pub async fn get_config(ctx: &serenity::client::Context) -> std::sync::Arc<Confi
         .clone()
 }
 
-// TypeMapKey for storing the pi RPC client in Serenity's context
-pub struct PiRpcKey;
+// TypeMapKey for storing the ask backend in Serenity's context
+pub struct AskBackendKey;
 
-impl TypeMapKey for PiRpcKey {
-    type Value = std::sync::Arc<PiRpc>;
+impl TypeMapKey for AskBackendKey {
+    type Value = std::sync::Arc<dyn AskBackend>;
 }
 
-// Helper function to get the pi RPC client from context
-pub async fn get_pi_rpc(ctx: &serenity::client::Context) -> std::sync::Arc<PiRpc> {
+// Helper function to get the ask backend from context
+pub async fn get_ask_backend(ctx: &serenity::client::Context) -> std::sync::Arc<dyn AskBackend> {
     let data = ctx.data.read().await;
-    data.get::<PiRpcKey>()
-        .expect("Expected PiRpc in TypeMap")
+    data.get::<AskBackendKey>()
+        .expect("Expected AskBackend in TypeMap")
+        .clone()
+}
+
+// Helper function to get the activity tracker from context
+pub async fn get_activity_tracker(
+    ctx: &serenity::client::Context,
+) -> std::sync::Arc<ActivityTracker> {
+    let data = ctx.data.read().await;
+    data.get::<ActivityTrackerKey>()
+        .expect("Expected ActivityTracker in TypeMap")
         .clone()
 }
 
 use crate::handlers::{
+    activity::ActivityHandler,
     ai_slop::AiSlopHandler,
+    amnesty::AmnestyHandler,
     bsky::Bsky,
-    cull::CullHandler,
+    config::ConfigHandler,
+    cull::{running::running_culls, CullHandler},
     feat::Feat,
+    forget::ForgetHandler,
     goku_poll::GokuPoll,
     gulag::{
-        gulag_handler::GulagHandler, gulag_list_handler::GulagListHandler,
+        gulag_appeal_handler::GulagAppealHandler, gulag_handler::GulagHandler,
+        gulag_history_handler::GulagHistoryHandler, gulag_list_handler::GulagListHandler,
         gulag_message_command::GulagMessageCommandHandler, gulag_reaction::GulagReaction,
         gulag_remove_handler::GulagRemoveHandler, Gulag,
     },
     mention::Mention,
+    permissions::PermissionsHandler,
     prefix_handler::PrefixHandler,
+    stats::StatsHandler,
     teh::Teh,
     twitter::Twitter,
 };
+use crate::tugbot::activity::{ActivitySource, ActivityTracker, ActivityTrackerKey};
 use crate::tugbot::servers::Servers;
 use instagram::Instagram;
+use interactions::InteractionRouter;
 use serenity::{
-    all::{Interaction, Member, Message, MessageUpdateEvent, Reaction, Ready},
+    all::{
+        CommandDataOption, CommandDataOptionValue, Interaction, Member, Message,
+        MessageUpdateEvent, Reaction, Ready, VoiceState,
+    },
     async_trait,
-    builder::{CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage},
+    builder::{
+        CreateInteractionResponse, CreateInteractionResponseMessage, CreateMessage,
+        EditInteractionResponse,
+    },
     client::{Context, EventHandler},
 };
+use std::{sync::OnceLock, time::SystemTime};
 
 #[derive(Default)]
 pub struct HandlerResponse {
     pub content: String,
     pub components: Option<Vec<serenity::all::CreateActionRow>>,
     pub ephemeral: bool,
-    /// If Some(true), defer the response instead of sending a message.
+    /// Set by handlers of commands in `DEFERRED_COMMANDS`. Those commands are
+    /// acknowledged before the handler runs, and `content`/`components` are
+    /// delivered by editing the deferred reply (which is always ephemeral).
     pub defer_response: Option<bool>,
 }
 
+impl HandlerResponse {
+    /// A plain reply only the invoking user can see.
+    pub fn ephemeral(content: impl Into<String>) -> Self {
+        HandlerResponse {
+            content: content.into(),
+            components: None,
+            ephemeral: true,
+            defer_response: None,
+        }
+    }
+
+    /// A reply for a command in `DEFERRED_COMMANDS`.
+    pub fn deferred(content: impl Into<String>) -> Self {
+        HandlerResponse {
+            defer_response: Some(true),
+            ..Self::ephemeral(content)
+        }
+    }
+}
+
+/// The options passed to a subcommand; empty for anything else.
+pub fn subcommand_options(subcommand: &CommandDataOption) -> &[CommandDataOption] {
+    match &subcommand.value {
+        CommandDataOptionValue::SubCommand(options) => options,
+        _ => &[],
+    }
+}
+
+/// The value of a subcommand's option called `name`, if it was given.
+pub fn subcommand_option<'a>(
+    subcommand: &'a CommandDataOption,
+    name: &str,
+) -> Option<&'a CommandDataOptionValue> {
+    subcommand_options(subcommand)
+        .iter()
+        .find(|o| o.name == name)
+        .map(|o| &o.value)
+}
+
 pub struct Handler;
 
+/// Commands that may take longer than Discord's 3 second response window.
+const DEFERRED_COMMANDS: &[&str] = &["cull"];
+
+/// Component and modal routes, keyed by custom_id prefix.
+fn interaction_router() -> &'static InteractionRouter {
+    static ROUTER: OnceLock<InteractionRouter> = OnceLock::new();
+    ROUTER.get_or_init(|| {
+        InteractionRouter::new()
+            .component(
+                gulag::gulag_appeal_handler::CUSTOM_ID_PREFIX,
+                |ctx, component| Box::pin(GulagAppealHandler::handle_component(ctx, component)),
+            )
+            .component(cull::CUSTOM_ID_PREFIX, |ctx, component| {
+                Box::pin(CullHandler::handle_component(ctx, component))
+            })
+    })
+}
+
 #[async_trait]
 impl EventHandler for Handler {
     async fn message(&self, ctx: Context, msg: Message) {
+        // Gateway messages carry their guild, threads included; DMs have none.
+        if let Some(guild_id) = msg.guild_id {
+            if !msg.author.bot && msg.webhook_id.is_none() {
+                let tracker = get_activity_tracker(&ctx).await;
+                let now = SystemTime::now();
+                tracker.record(
+                    guild_id.get(),
+                    msg.author.id.get(),
+                    ActivitySource::Message,
+                    now,
+                );
+                tracker.record_message(
+                    guild_id.get(),
+                    msg.channel_id.get(),
+                    msg.author.id.get(),
+                    now,
+                );
+            }
+        }
         Teh::handler(&ctx, &msg).await;
         Twitter::handler(&ctx, &msg).await;
         //TikTok::handler(&ctx, &msg).await;
@@ -112,6 +223,16 @@ impl EventHandler for Handler {
     }
 
     async fn reaction_add(&self, ctx: Context, add_reaction: Reaction) {
+        if let (Some(guild_id), Some(user_id)) = (add_reaction.guild_id, add_reaction.user_id) {
+            if !add_reaction.member.as_ref().is_some_and(|m| m.user.bot) {
+                get_activity_tracker(&ctx).await.record(
+                    guild_id.get(),
+                    user_id.get(),
+                    ActivitySource::Reaction,
+                    SystemTime::now(),
+                );
+            }
+        }
         GulagReaction::handler(&ctx, &add_reaction).await;
     }
 
@@ -119,6 +240,23 @@ impl EventHandler for Handler {
         GulagReaction::handler(&ctx, &add_reaction).await;
     }
 
+    async fn voice_state_update(&self, ctx: Context, old: Option<VoiceState>, new: VoiceState) {
+        let joined = match (&old, new.channel_id) {
+            (_, None) => false,
+            (Some(old), Some(channel)) => old.channel_id != Some(channel),
+            (None, Some(_)) => true,
+        };
+        let is_bot = new.member.as_ref().is_some_and(|m| m.user.bot);
+        if let (true, false, Some(guild_id)) = (joined, is_bot, new.guild_id) {
+            get_activity_tracker(&ctx).await.record(
+                guild_id.get(),
+                new.user_id.get(),
+                ActivitySource::Voice,
+                SystemTime::now(),
+            );
+        }
+    }
+
     async fn message_update(
         &self,
         ctx: Context,
@@ -191,6 +329,9 @@ impl EventHandler for Handler {
                     gulaglength: gulag_length,
                     channelid: channel_id,
                     messageid: 0,
+                    source: gulag::GulagSource::Rejoin,
+                    moderator_id: None,
+                    reason: user.reason.clone(),
                 },
             )
             .await
@@ -212,41 +353,78 @@ impl EventHandler for Handler {
     }
 
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        if matches!(
+            interaction,
+            Interaction::Component(_) | Interaction::Modal(_)
+        ) {
+            interaction_router().dispatch(&ctx, &interaction).await;
+            return;
+        }
         if let Interaction::Command(command) = interaction {
-            let handler_response = match command.data.name.as_str() {
-                "gulag" => GulagHandler::setup_interaction(&ctx, &command).await,
-                "gulag-release" => GulagRemoveHandler::setup_interaction(&ctx, &command).await,
-                "gulag-list" => GulagListHandler::setup_interaction(&ctx, &command).await,
-                "Add Gulag Vote" => {
-                    GulagMessageCommandHandler::setup_interaction(&ctx, &command).await
+            // Slow commands are acknowledged first; their response is
+            // delivered by editing the deferred (ephemeral) reply.
+            let deferred = DEFERRED_COMMANDS.contains(&command.data.name.as_str());
+            if deferred {
+                if let Err(why) = command.defer_ephemeral(&ctx.http).await {
+                    eprintln!("Cannot defer slash command: {}", why);
+                    return;
                 }
-                "AI Slop" => AiSlopHandler::setup_interaction(&ctx, &command).await,
-                "phony" => PrefixHandler::setup_interaction(&ctx, &command).await,
-                "horny" => PrefixHandler::setup_interaction(&ctx, &command).await,
-                "feature" => Feat::setup_interaction(&ctx, &command).await,
-                "cull" => CullHandler::setup_interaction(&ctx, &command).await,
-                _ => HandlerResponse {
-                    content: "Not Implemented".to_string(),
-                    components: None,
-                    ephemeral: true,
-                    defer_response: None,
-                },
-            };
+            }
 
-            let response = if handler_response.defer_response == Some(true) {
-                CreateInteractionResponse::Defer(
-                    CreateInteractionResponseMessage::new().ephemeral(true),
-                )
+            let pool = get_pool(&ctx).await;
+            let handler_response = if let Err(denied) =
+                crate::permissions::authorize(&ctx.http, &pool, &command).await
+            {
+                HandlerResponse::ephemeral(denied)
             } else {
-                let mut message = CreateInteractionResponseMessage::new()
-                    .content(handler_response.content)
-                    .ephemeral(handler_response.ephemeral);
-                if let Some(components) = handler_response.components {
-                    message = message.components(components);
+                match command.data.name.as_str() {
+                    "gulag" => GulagHandler::setup_interaction(&ctx, &command).await,
+                    "gulag-release" => GulagRemoveHandler::setup_interaction(&ctx, &command).await,
+                    "gulag-list" => GulagListHandler::setup_interaction(&ctx, &command).await,
+                    "gulag-history" => GulagHistoryHandler::setup_interaction(&ctx, &command).await,
+                    "gulag-appeal" => GulagAppealHandler::setup_interaction(&ctx, &command).await,
+                    "amnesty" => AmnestyHandler::setup_interaction(&ctx, &command).await,
+                    "Add Gulag Vote" => {
+                        GulagMessageCommandHandler::setup_interaction(&ctx, &command).await
+                    }
+                    "AI Slop" => AiSlopHandler::setup_interaction(&ctx, &command).await,
+                    "phony" => PrefixHandler::setup_interaction(&ctx, &command).await,
+                    "horny" => PrefixHandler::setup_interaction(&ctx, &command).await,
+                    "feature" => Feat::setup_interaction(&ctx, &command).await,
+                    "cull" => CullHandler::setup_interaction(&ctx, &command).await,
+                    "activity" => ActivityHandler::setup_interaction(&ctx, &command).await,
+                    "stats" => StatsHandler::setup_interaction(&ctx, &command).await,
+                    "forget" => ForgetHandler::setup_interaction(&ctx, &command).await,
+                    "config" => ConfigHandler::setup_interaction(&ctx, &command).await,
+                    "permissions" => PermissionsHandler::setup_interaction(&ctx, &command).await,
+                    _ => HandlerResponse {
+                        content: "Not Implemented".to_string(),
+                        components: None,
+                        ephemeral: true,
+                        defer_response: None,
+                    },
                 }
-                CreateInteractionResponse::Message(message)
             };
 
+            if deferred {
+                let mut edit = EditInteractionResponse::new().content(handler_response.content);
+                if let Some(components) = handler_response.components {
+                    edit = edit.components(components);
+                }
+                if let Err(why) = command.edit_response(&ctx.http, edit).await {
+                    eprintln!("Cannot edit deferred slash command response: {}", why);
+                }
+                return;
+            }
+
+            let mut message = CreateInteractionResponseMessage::new()
+                .content(handler_response.content)
+                .ephemeral(handler_response.ephemeral);
+            if let Some(components) = handler_response.components {
+                message = message.components(components);
+            }
+            let response = CreateInteractionResponse::Message(message);
+
             match command.create_response(&ctx.http, response).await {
                 Ok(()) => {}
                 Err(why) => eprintln!("Cannot respond to slash command: {}", why),
@@ -262,17 +440,18 @@ impl EventHandler for Handler {
         let servers = Servers::get_servers(&ctx, &pool).await;
         Gulag::run_gulag_check(&ctx.http, pool.clone());
         Gulag::run_gulag_vote_check(&ctx.http, pool.clone());
+        CullHandler::run_schedule_check(&ctx.http, pool.clone(), running_culls(&ctx).await);
 
-        // Start pi RPC subprocess
-        match PiRpc::spawn().await {
-            Ok(pi_rpc) => {
+        // Start the ask backend (pi RPC unless configured otherwise)
+        match spawn_backend().await {
+            Ok(backend) => {
                 let mut data = ctx.data.write().await;
-                data.insert::<PiRpcKey>(pi_rpc);
-                eprintln!("pi RPC subprocess started");
+                data.insert::<AskBackendKey>(backend);
+                eprintln!("Ask backend started");
             }
             Err(e) => {
                 eprintln!(
-                    "Failed to start pi RPC subprocess: {} — mention feature will not work",
+                    "Failed to start ask backend: {} — mention feature will not work",
                     e
                 );
             }
@@ -287,12 +466,20 @@ impl EventHandler for Handler {
                         GulagHandler::setup_command(),
                         GulagRemoveHandler::setup_command(),
                         GulagListHandler::setup_command(),
+                        GulagHistoryHandler::setup_command(),
+                        GulagAppealHandler::setup_command(),
+                        AmnestyHandler::setup_command(),
                         GulagMessageCommandHandler::setup_command(),
                         AiSlopHandler::setup_command(),
                         PrefixHandler::setup_command("horny", "Mark yourself as horny/lfg"),
                         PrefixHandler::setup_command("phony", "Mark yourself as phony/watching"),
                         Feat::setup_command(),
                         CullHandler::setup_command(),
+                        ActivityHandler::setup_command(),
+                        StatsHandler::setup_command(),
+                        ForgetHandler::setup_command(),
+                        ConfigHandler::setup_command(),
+                        PermissionsHandler::setup_command(),
                     ],
                 )
                 .await;
@@ -0,0 +1,191 @@
+use serenity::{
+    all::{CommandDataOption, CommandDataOptionValue, CommandInteraction, CommandOptionType},
+    builder::{CreateCommand, CreateCommandOption},
+    client::Context,
+};
+
+use crate::permissions::{self, Capability, Grantee};
+
+use super::{get_pool, HandlerResponse};
+
+pub struct PermissionsHandler;
+
+impl PermissionsHandler {
+    pub fn setup_command() -> CreateCommand {
+        let capability = || {
+            let mut option = CreateCommandOption::new(
+                CommandOptionType::String,
+                "capability",
+                "The permission to change",
+            )
+            .required(true);
+            for cap in Capability::ALL {
+                option = option.add_string_choice(cap.as_str(), cap.as_str());
+            }
+            option
+        };
+        let targets = |sub: CreateCommandOption| {
+            sub.add_sub_option(capability())
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::Role, "role", "A role")
+                        .required(false),
+                )
+                .add_sub_option(
+                    CreateCommandOption::new(CommandOptionType::User, "user", "A single user")
+                        .required(false),
+                )
+        };
+
+        CreateCommand::new("permissions")
+            .description("Manage who can use moderator commands")
+            .add_option(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "list",
+                "Show all permission grants",
+            ))
+            .add_option(targets(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "grant",
+                "Grant a permission to a role or user",
+            )))
+            .add_option(targets(CreateCommandOption::new(
+                CommandOptionType::SubCommand,
+                "revoke",
+                "Revoke a permission from a role or user",
+            )))
+    }
+
+    pub async fn setup_interaction(ctx: &Context, command: &CommandInteraction) -> HandlerResponse {
+        let guild_id = match command.guild_id {
+            Some(id) => id.get(),
+            None => return HandlerResponse::ephemeral("This command can only be used in a server"),
+        };
+        let pool = get_pool(ctx).await;
+
+        let subcommand = match command.data.options.first() {
+            Some(sub) => sub,
+            None => return HandlerResponse::ephemeral("Expected a subcommand"),
+        };
+
+        if subcommand.name == "list" {
+            return match permissions::list(&pool, guild_id) {
+                Ok(grants) => HandlerResponse::ephemeral(Self::format_list(&grants)),
+                Err(e) => {
+                    eprintln!("[permissions] {:#}", e);
+                    HandlerResponse::ephemeral(
+                        "Failed to load permissions. Please try again later.",
+                    )
+                }
+            };
+        }
+
+        let (capability, grantee) = match Self::parse_target(subcommand) {
+            Ok(target) => target,
+            Err(e) => return HandlerResponse::ephemeral(e),
+        };
+
+        let result = match subcommand.name.as_str() {
+            "grant" => permissions::grant(&pool, guild_id, capability, grantee).map(|added| {
+                if added {
+                    format!("Granted `{}` to {}", capability.as_str(), grantee.mention())
+                } else {
+                    format!(
+                        "{} already has `{}`",
+                        grantee.mention(),
+                        capability.as_str()
+                    )
+                }
+            }),
+            "revoke" => permissions::revoke(&pool, guild_id, capability, grantee).map(|removed| {
+                if removed {
+                    format!(
+                        "Revoked `{}` from {}",
+                        capability.as_str(),
+                        grantee.mention()
+                    )
+                } else {
+                    format!(
+                        "{} doesn't have an explicit `{}` grant",
+                        grantee.mention(),
+                        capability.as_str()
+                    )
+                }
+            }),
+            other => return HandlerResponse::ephemeral(format!("Unknown subcommand `{}`", other)),
+        };
+
+        match result {
+            Ok(content) => HandlerResponse::ephemeral(content),
+            Err(e) => {
+                eprintln!("[permissions] {:#}", e);
+                HandlerResponse::ephemeral("Failed to update permissions. Please try again later.")
+            }
+        }
+    }
+
+    fn parse_target(subcommand: &CommandDataOption) -> Result<(Capability, Grantee), String> {
+        let options = match &subcommand.value {
+            CommandDataOptionValue::SubCommand(options) => options,
+            _ => return Err("Invalid subcommand".to_string()),
+        };
+
+        let mut capability = None;
+        let mut grantees = Vec::new();
+        for option in options {
+            match (option.name.as_str(), &option.value) {
+                ("capability", CommandDataOptionValue::String(name)) => {
+                    capability = Some(
+                        Capability::parse(name)
+                            .ok_or_else(|| format!("Unknown permission `{}`", name))?,
+                    );
+                }
+                ("role", CommandDataOptionValue::Role(id)) => {
+                    grantees.push(Grantee::Role(id.get()))
+                }
+                ("user", CommandDataOptionValue::User(id)) => {
+                    grantees.push(Grantee::User(id.get()))
+                }
+                _ => {}
+            }
+        }
+
+        let capability = capability.ok_or("Missing required option `capability`")?;
+        match grantees.as_slice() {
+            [grantee] => Ok((capability, *grantee)),
+            _ => Err("Provide exactly one of `role` or `user`".to_string()),
+        }
+    }
+
+    fn format_list(grants: &[(Capability, Vec<Grantee>)]) -> String {
+        let mut content = "**Permissions on this server**".to_string();
+        for (capability, grantees) in grants {
+            let who = if grantees.is_empty() {
+                "moderator roles (default)".to_string()
+            } else {
+                grantees
+                    .iter()
+                    .map(|g| g.mention())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            content = format!("{}\n`{}`: {}", content, capability.as_str(), who);
+        }
+        content
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_list_shows_default_for_ungranted() {
+        let grants = vec![
+            (Capability::Gulag, vec![Grantee::Role(1), Grantee::User(2)]),
+            (Capability::Cull, vec![]),
+        ];
+        let text = PermissionsHandler::format_list(&grants);
+        assert!(text.contains("`gulag`: <@&1>, <@2>"));
+        assert!(text.contains("`cull`: moderator roles (default)"));
+    }
+}
@@ -39,7 +39,7 @@ impl PrefixHandler {
         let prefix = command.data.name.clone();
 
         // Check feature flag
-        match Features::check_enabled(&pool, &prefix) {
+        match Features::check_enabled(&pool, command.guild_id.map(|g| g.get()), &prefix) {
             Ok(true) => {}
             Ok(false) => {
                 return HandlerResponse {
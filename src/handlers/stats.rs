@@ -0,0 +1,267 @@
+//! `/stats`: leaderboards and an hourly heatmap from the message rollups
+//! that `ActivityTracker` maintains. Counts start when live tracking did;
+//! `/cull scan` does not backfill them.
+
+use std::time::SystemTime;
+
+use serenity::{
+    all::{CommandDataOptionValue, CommandInteraction, CommandOptionType},
+    builder::{CreateCommand, CreateCommandOption},
+    client::Context,
+};
+
+use crate::db::{
+    models::MessageHourCount, query_busiest_channels, query_message_hour_counts, query_top_posters,
+};
+use crate::tugbot::activity::{epoch_day, MESSAGE_COUNT_RETENTION_DAYS};
+
+use super::{get_pool, subcommand_option, HandlerResponse};
+
+/// Entries in each leaderboard.
+const LEADERBOARD_SIZE: i64 = 10;
+/// Window used when `days` isn't given.
+const DEFAULT_DAYS: i64 = 30;
+
+const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+/// Heatmap cells from quietest to busiest.
+const SHADES: [char; 5] = [' ', '░', '▒', '▓', '█'];
+
+pub struct StatsHandler;
+
+impl StatsHandler {
+    pub fn setup_command() -> CreateCommand {
+        let days = || {
+            CreateCommandOption::new(
+                CommandOptionType::Integer,
+                "days",
+                "How many days back to count (default: 30)",
+            )
+            .required(false)
+            .min_int_value(1)
+            .max_int_value(MESSAGE_COUNT_RETENTION_DAYS as u64)
+        };
+
+        CreateCommand::new("stats")
+            .description("Message statistics for this server")
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "top-posters",
+                    "Members who sent the most messages",
+                )
+                .add_sub_option(days())
+                .add_sub_option(
+                    CreateCommandOption::new(
+                        CommandOptionType::Channel,
+                        "channel",
+                        "Only count messages in this channel",
+                    )
+                    .required(false),
+                ),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "channels",
+                    "Channels with the most messages",
+                )
+                .add_sub_option(days()),
+            )
+            .add_option(
+                CreateCommandOption::new(
+                    CommandOptionType::SubCommand,
+                    "heatmap",
+                    "Messages by weekday and hour (UTC)",
+                )
+                .add_sub_option(days()),
+            )
+    }
+
+    pub async fn setup_interaction(ctx: &Context, command: &CommandInteraction) -> HandlerResponse {
+        let guild_id = match command.guild_id {
+            Some(id) => id.get() as i64,
+            None => return HandlerResponse::ephemeral("This command can only be used in a server"),
+        };
+        let pool = get_pool(ctx).await;
+
+        let subcommand = match command.data.options.first() {
+            Some(sub) => sub,
+            None => return HandlerResponse::ephemeral("Expected a subcommand"),
+        };
+        let days = match subcommand_option(subcommand, "days") {
+            Some(CommandDataOptionValue::Integer(days)) => *days,
+            _ => DEFAULT_DAYS,
+        };
+        // Today counts as the first of the `days`
+        let since_day = epoch_day(SystemTime::now()) - (days as i32 - 1);
+
+        let result = match subcommand.name.as_str() {
+            "top-posters" => {
+                let channel_id = match subcommand_option(subcommand, "channel") {
+                    Some(CommandDataOptionValue::Channel(id)) => Some(id.get() as i64),
+                    _ => None,
+                };
+                query_top_posters(&pool, guild_id, since_day, channel_id, LEADERBOARD_SIZE).map(
+                    |rows| {
+                        let title = match channel_id {
+                            Some(id) => format!("Top posters in <#{}>", id),
+                            None => "Top posters".to_string(),
+                        };
+                        Self::format_leaderboard(&title, days, &rows, |id| format!("<@{}>", id))
+                    },
+                )
+            }
+            "channels" => {
+                query_busiest_channels(&pool, guild_id, since_day, LEADERBOARD_SIZE).map(|rows| {
+                    Self::format_leaderboard("Busiest channels", days, &rows, |id| {
+                        format!("<#{}>", id)
+                    })
+                })
+            }
+            "heatmap" => query_message_hour_counts(&pool, guild_id, since_day)
+                .map(|rows| Self::format_heatmap(days, &rows)),
+            other => return HandlerResponse::ephemeral(format!("Unknown subcommand `{}`", other)),
+        };
+
+        match result {
+            Ok(content) => HandlerResponse::ephemeral(content),
+            Err(e) => {
+                eprintln!("[stats] Failed to query message counts: {}", e);
+                HandlerResponse::ephemeral(
+                    "Failed to load message statistics. Please try again later.",
+                )
+            }
+        }
+    }
+
+    fn format_leaderboard(
+        title: &str,
+        days: i64,
+        rows: &[(i64, i64)],
+        mention: impl Fn(i64) -> String,
+    ) -> String {
+        if rows.is_empty() {
+            return format!("No messages counted in the last {} days.", days);
+        }
+        let mut content = format!("**{}** (last {} days)", title, days);
+        for (rank, (id, count)) in rows.iter().enumerate() {
+            content.push_str(&format!(
+                "\n{}. {} — {} message{}",
+                rank + 1,
+                mention(*id),
+                count,
+                if *count == 1 { "" } else { "s" }
+            ));
+        }
+        content
+    }
+
+    /// Weekday of an epoch day, Monday = 0. 1970-01-01 was a Thursday.
+    fn weekday(day: i32) -> usize {
+        (day + 3).rem_euclid(7) as usize
+    }
+
+    fn format_heatmap(days: i64, rows: &[MessageHourCount]) -> String {
+        let mut grid = [[0i64; 24]; 7];
+        for row in rows {
+            if let Some(cell) = grid[Self::weekday(row.day)].get_mut(row.hour as usize) {
+                *cell += row.message_count as i64;
+            }
+        }
+        let total: i64 = grid.iter().flatten().sum();
+        if total == 0 {
+            return format!("No messages counted in the last {} days.", days);
+        }
+        let max = grid.iter().flatten().copied().max().unwrap_or(0);
+
+        let mut content = format!(
+            "**Messages by hour (UTC)**, last {} days: {} messages\n```\n    0     6     12    18\n",
+            days, total
+        );
+        // (weekday, hour, count) of the busiest cell
+        let mut busiest = (0, 0, 0);
+        for (weekday, hours) in grid.iter().enumerate() {
+            let cells: String = hours.iter().map(|&count| Self::shade(count, max)).collect();
+            content.push_str(&format!("{} {}\n", WEEKDAYS[weekday], cells));
+            for (hour, &count) in hours.iter().enumerate() {
+                if count > busiest.2 {
+                    busiest = (weekday, hour, count);
+                }
+            }
+        }
+        content.push_str(&format!(
+            "```\nBusiest hour: {} {:02}:00 ({} messages)",
+            WEEKDAYS[busiest.0], busiest.1, busiest.2
+        ));
+        content
+    }
+
+    /// Heatmap cell for `count` relative to the busiest cell. Any activity
+    /// at all gets at least the lightest shade.
+    fn shade(count: i64, max: i64) -> char {
+        if count <= 0 || max <= 0 {
+            return SHADES[0];
+        }
+        let steps = (SHADES.len() - 1) as i64;
+        let level = (count * steps + max - 1) / max;
+        SHADES[level.clamp(1, steps) as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_leaderboard_ranks_rows() {
+        let content =
+            StatsHandler::format_leaderboard("Top posters", 30, &[(5, 12), (6, 1)], |id| {
+                format!("<@{}>", id)
+            });
+        assert_eq!(
+            content,
+            "**Top posters** (last 30 days)\n1. <@5> — 12 messages\n2. <@6> — 1 message"
+        );
+        assert_eq!(
+            StatsHandler::format_leaderboard("Top posters", 7, &[], |id| id.to_string()),
+            "No messages counted in the last 7 days."
+        );
+    }
+
+    #[test]
+    fn heatmap_places_counts_by_weekday_and_hour() {
+        // 2024-01-15 was a Monday
+        assert_eq!(StatsHandler::weekday(19737), 0);
+        assert_eq!(StatsHandler::weekday(0), 3);
+
+        let row = |day, hour, message_count| MessageHourCount {
+            guild_id: 1,
+            day,
+            hour,
+            message_count,
+        };
+        let content = StatsHandler::format_heatmap(
+            30,
+            &[row(19737, 0, 1), row(19737, 23, 8), row(19738, 12, 4)],
+        );
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(
+            lines[0],
+            "**Messages by hour (UTC)**, last 30 days: 13 messages"
+        );
+        assert_eq!(lines[3], format!("Mon ░{}█", " ".repeat(22)));
+        assert_eq!(
+            lines[4],
+            format!("Tue {}▒{}", " ".repeat(12), " ".repeat(11))
+        );
+        assert!(content.ends_with("Busiest hour: Mon 23:00 (8 messages)"));
+    }
+
+    #[test]
+    fn shade_scales_to_busiest_cell() {
+        assert_eq!(StatsHandler::shade(0, 10), ' ');
+        assert_eq!(StatsHandler::shade(1, 100), '░');
+        assert_eq!(StatsHandler::shade(50, 100), '▒');
+        assert_eq!(StatsHandler::shade(100, 100), '█');
+    }
+}
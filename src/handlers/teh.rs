@@ -9,7 +9,9 @@ pub struct Teh;
 impl Teh {
     pub async fn handler(ctx: &Context, msg: &Message) {
         let pool = get_pool(ctx).await;
-        if Features::is_enabled(&pool, "teh") && msg.content.to_lowercase().contains("teh") {
+        if Features::is_enabled(&pool, msg.guild_id.map(|g| g.get()), "teh")
+            && msg.content.to_lowercase().contains("teh")
+        {
             // React with "🇹"
             if let Err(why) = msg.react(ctx, ReactionType::Unicode("🇹".to_string())).await {
                 eprintln!("Error reacting with emoji T: {:?}", why);
@@ -9,7 +9,7 @@ pub struct TikTok;
 impl TikTok {
     pub async fn handler(ctx: &Context, msg: &Message) {
         let pool = get_pool(ctx).await;
-        if Features::is_enabled(&pool, "tiktok") {
+        if Features::is_enabled(&pool, msg.guild_id.map(|g| g.get()), "tiktok") {
             if let Some(fixed_message) = Self::fx_rewriter(&msg.content.to_owned()).await {
                 if let Err(why) = msg
                     .clone()
@@ -1,5 +1,8 @@
+pub mod ask;
 pub mod db;
 pub mod features;
 pub mod handlers;
+pub mod offenses;
+pub mod permissions;
 pub mod pi_rpc;
 pub mod tugbot;
@@ -3,7 +3,10 @@ use std::sync::Arc;
 use tugbot::{
     db::establish_pool,
     handlers::{ConfigKey, DbPoolKey, Handler},
-    tugbot::config::Config,
+    tugbot::{
+        activity::{ActivityTracker, ActivityTrackerKey},
+        config::Config,
+    },
 };
 
 #[tokio::main]
@@ -24,17 +27,58 @@ async fn main() {
         .await
         .expect("Error creating client");
 
-    // Insert the database pool and config into the client's data
+    // Buffer user activity in memory and flush it to the database periodically
+    let activity_tracker = Arc::new(ActivityTracker::new());
+    activity_tracker.run_flush_loop(pool.clone());
+
+    // Insert the database pool, config and activity tracker into the client's data
     {
         let mut data = client.data.write().await;
-        data.insert::<DbPoolKey>(pool);
+        data.insert::<DbPoolKey>(pool.clone());
         data.insert::<ConfigKey>(tugbot_config);
+        data.insert::<ActivityTrackerKey>(activity_tracker.clone());
     }
 
+    // Stop the shards on Ctrl-C or SIGTERM so `client.start()` returns
+    let shard_manager = client.shard_manager.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        eprintln!("Shutting down...");
+        shard_manager.shutdown_all().await;
+    });
+
     // Finally, start a single shard, and start listening to events.
     // Shards will automatically attempt to reconnect, and will perform
     // exponential backoff until it reconnects.
     if let Err(why) = client.start().await {
         eprintln!("Client error: {:?}", why);
     }
+
+    // Write out activity buffered since the last periodic flush
+    match tokio::task::spawn_blocking(move || activity_tracker.flush(&pool)).await {
+        Ok(rows) => eprintln!("[activity] Flushed {} activity rows on shutdown", rows),
+        Err(e) => eprintln!("[activity] Shutdown flush failed: {}", e),
+    }
+}
+
+/// Resolves on Ctrl-C, or on SIGTERM (what `docker stop` sends).
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = terminate.recv() => {}
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to listen for SIGTERM: {}", e);
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
 }
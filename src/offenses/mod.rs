@@ -0,0 +1,429 @@
+//! Escalating offense ledger shared by every gulag trigger.
+//!
+//! Each trigger has an [`OffenseType`] with a per-guild [`Policy`]: the Nth
+//! offense costs `base * multiplier^(N-1)` seconds, capped at `cap`. When the
+//! policy has a decay window, the count halves for every full window that
+//! passes without an offense. Moderators can also forgive offenses outright
+//! with `/amnesty`.
+
+use crate::db::{
+    models::{Offense, OffensePolicy},
+    schema::{offense_policies, offenses},
+    DbPool,
+};
+use anyhow::{Context, Result};
+use diesel::prelude::*;
+use std::time::{Duration, SystemTime};
+
+/// `guild_id` of the global default policy rows.
+pub const GLOBAL_GUILD_ID: i64 = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OffenseType {
+    SlashCommand,
+    ReactionVote,
+    AiSlop,
+    GokuPoll,
+    Elon,
+    SlowUser,
+}
+
+impl OffenseType {
+    pub const ALL: [OffenseType; 6] = [
+        OffenseType::SlashCommand,
+        OffenseType::ReactionVote,
+        OffenseType::AiSlop,
+        OffenseType::GokuPoll,
+        OffenseType::Elon,
+        OffenseType::SlowUser,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OffenseType::SlashCommand => "slash_command",
+            OffenseType::ReactionVote => "reaction_vote",
+            OffenseType::AiSlop => "ai_slop",
+            OffenseType::GokuPoll => "goku_poll",
+            OffenseType::Elon => "elon",
+            OffenseType::SlowUser => "slow_user",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<OffenseType> {
+        OffenseType::ALL.into_iter().find(|t| t.as_str() == value)
+    }
+
+    /// Human-readable name for command choices and output.
+    pub fn label(&self) -> &'static str {
+        match self {
+            OffenseType::SlashCommand => "/gulag",
+            OffenseType::ReactionVote => "Reaction vote",
+            OffenseType::AiSlop => "AI Slop",
+            OffenseType::GokuPoll => "Goku poll",
+            OffenseType::Elon => "Elon",
+            OffenseType::SlowUser => "Slow user",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Policy {
+    pub base_seconds: u64,
+    pub multiplier: f64,
+    pub cap_seconds: u64,
+    pub decay_window: Option<Duration>,
+}
+
+impl Policy {
+    /// Used when an offense type has no policy row at all.
+    const FALLBACK: Policy = Policy {
+        base_seconds: 1800,
+        multiplier: 2.0,
+        cap_seconds: 604_800,
+        decay_window: None,
+    };
+
+    fn from_row(row: &OffensePolicy) -> Policy {
+        Policy {
+            base_seconds: row.base_seconds.max(1) as u64,
+            multiplier: row.multiplier.max(1.0),
+            cap_seconds: row.cap_seconds.max(1) as u64,
+            decay_window: row
+                .decay_window_seconds
+                .filter(|secs| *secs > 0)
+                .map(|secs| Duration::from_secs(secs as u64)),
+        }
+    }
+
+    /// Sentence length in seconds for the `count`th offense (1-based).
+    pub fn duration_for(&self, count: i32) -> u64 {
+        let exponent = count.saturating_sub(1).max(0);
+        let seconds = self.base_seconds as f64 * self.multiplier.powi(exponent);
+        if !seconds.is_finite() || seconds >= self.cap_seconds as f64 {
+            self.cap_seconds
+        } else {
+            seconds as u64
+        }
+    }
+
+    /// Apply decay: halve `count` once per full window since
+    /// `last_offense_at`. Returns the decayed count and the start of the
+    /// current, partially elapsed window, so storing both loses nothing.
+    fn decay(&self, count: i32, last_offense_at: SystemTime, now: SystemTime) -> (i32, SystemTime) {
+        let Some(window) = self.decay_window.filter(|w| !w.is_zero()) else {
+            return (count, last_offense_at);
+        };
+        let Ok(elapsed) = now.duration_since(last_offense_at) else {
+            return (count, last_offense_at);
+        };
+        let periods = elapsed.as_secs() / window.as_secs().max(1);
+        if periods == 0 {
+            return (count, last_offense_at);
+        }
+        let decayed = if periods >= 31 { 0 } else { count >> periods };
+        let anchor = last_offense_at + window * u32::try_from(periods).unwrap_or(u32::MAX);
+        (decayed, anchor)
+    }
+}
+
+/// Result of recording an offense.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    /// This offense's number, after decay.
+    pub count: i32,
+    pub duration_seconds: u64,
+    /// What the following offense would cost if it happened now.
+    pub next_duration_seconds: u64,
+    /// The stored count and timestamp this offense replaced, for [`Offenses::retract`].
+    pub previous: Option<(i32, SystemTime)>,
+}
+
+/// One offense type's count before and after `/amnesty`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Amnesty {
+    pub offense: OffenseType,
+    pub before: i32,
+    pub after: i32,
+}
+
+pub struct Offenses;
+
+impl Offenses {
+    /// The policy that applies to `offense` in `guild`.
+    pub fn policy(pool: &DbPool, guild: u64, offense: OffenseType) -> Result<Policy> {
+        let guild = Self::key(guild, "Guild")?;
+        let mut conn = pool
+            .get()
+            .with_context(|| "Failed to get database connection from pool")?;
+        Self::load_policy(&mut conn, guild, offense)
+    }
+
+    /// Replace the policy for `offense` in `guild` with a guild-specific row.
+    pub fn set_policy(
+        pool: &DbPool,
+        guild: u64,
+        offense: OffenseType,
+        policy: &Policy,
+    ) -> Result<()> {
+        let clamp = |secs: u64| i32::try_from(secs).unwrap_or(i32::MAX);
+        let row = OffensePolicy {
+            guild_id: Self::key(guild, "Guild")?,
+            offense_type: offense.as_str().to_string(),
+            base_seconds: clamp(policy.base_seconds),
+            multiplier: policy.multiplier,
+            cap_seconds: clamp(policy.cap_seconds),
+            decay_window_seconds: policy.decay_window.map(|d| clamp(d.as_secs())),
+        };
+        let mut conn = pool
+            .get()
+            .with_context(|| "Failed to get database connection from pool")?;
+        diesel::insert_into(offense_policies::table)
+            .values(&row)
+            .on_conflict((offense_policies::guild_id, offense_policies::offense_type))
+            .do_update()
+            .set(&row)
+            .execute(&mut conn)
+            .with_context(|| format!("Failed to save policy for '{}'", offense.as_str()))?;
+        Ok(())
+    }
+
+    /// Record an offense and return the sentence it earns.
+    pub fn record(pool: &DbPool, guild: u64, user: u64, offense: OffenseType) -> Result<Outcome> {
+        let guild = Self::key(guild, "Guild")?;
+        let user = Self::key(user, "User")?;
+        let mut conn = pool
+            .get()
+            .with_context(|| "Failed to get database connection from pool")?;
+
+        conn.transaction(|conn| {
+            let policy = Self::load_policy(conn, guild, offense)?;
+            let now = SystemTime::now();
+
+            let existing = offenses::table
+                .find((user, guild, offense.as_str()))
+                .select(Offense::as_select())
+                .for_update()
+                .first(conn)
+                .optional()
+                .with_context(|| "Failed to load offense count")?;
+            let previous = existing.map(|row| (row.count, row.last_offense_at));
+            let prior = previous
+                .map(|(count, at)| policy.decay(count, at, now).0)
+                .unwrap_or(0);
+            let count = prior.saturating_add(1);
+
+            diesel::insert_into(offenses::table)
+                .values(&Offense {
+                    user_id: user,
+                    guild_id: guild,
+                    offense_type: offense.as_str().to_string(),
+                    count,
+                    last_offense_at: now,
+                    created_at: now,
+                })
+                .on_conflict((
+                    offenses::user_id,
+                    offenses::guild_id,
+                    offenses::offense_type,
+                ))
+                .do_update()
+                .set((offenses::count.eq(count), offenses::last_offense_at.eq(now)))
+                .execute(conn)
+                .with_context(|| "Failed to record offense")?;
+
+            Ok(Outcome {
+                count,
+                duration_seconds: policy.duration_for(count),
+                next_duration_seconds: policy.duration_for(count.saturating_add(1)),
+                previous,
+            })
+        })
+    }
+
+    /// Undo a [`record`](Self::record) whose sentence never happened, restoring
+    /// the row it replaced. Does nothing if another offense was recorded since.
+    pub fn retract(
+        pool: &DbPool,
+        guild: u64,
+        user: u64,
+        offense: OffenseType,
+        outcome: &Outcome,
+    ) -> Result<()> {
+        let guild = Self::key(guild, "Guild")?;
+        let user = Self::key(user, "User")?;
+        let mut conn = pool
+            .get()
+            .with_context(|| "Failed to get database connection from pool")?;
+
+        let target = offenses::table
+            .find((user, guild, offense.as_str()))
+            .filter(offenses::count.eq(outcome.count));
+        match outcome.previous {
+            Some((count, last_offense_at)) => diesel::update(target)
+                .set((
+                    offenses::count.eq(count),
+                    offenses::last_offense_at.eq(last_offense_at),
+                ))
+                .execute(&mut conn),
+            None => diesel::delete(target).execute(&mut conn),
+        }
+        .with_context(|| format!("Failed to retract '{}' offense", offense.as_str()))?;
+        Ok(())
+    }
+
+    /// Forgive offenses: reset the decayed count to zero, or reduce it by
+    /// `reduce_by`. `None` for `offense` covers every type. Returns one entry
+    /// per offense type the user had on record.
+    pub fn amnesty(
+        pool: &DbPool,
+        guild: u64,
+        user: u64,
+        offense: Option<OffenseType>,
+        reduce_by: Option<i32>,
+    ) -> Result<Vec<Amnesty>> {
+        let guild = Self::key(guild, "Guild")?;
+        let user = Self::key(user, "User")?;
+        let mut conn = pool
+            .get()
+            .with_context(|| "Failed to get database connection from pool")?;
+
+        conn.transaction(|conn| {
+            let types: Vec<&str> = match offense {
+                Some(offense) => vec![offense.as_str()],
+                None => OffenseType::ALL.iter().map(|t| t.as_str()).collect(),
+            };
+            let rows = offenses::table
+                .filter(offenses::user_id.eq(user))
+                .filter(offenses::guild_id.eq(guild))
+                .filter(offenses::offense_type.eq_any(types))
+                .select(Offense::as_select())
+                .for_update()
+                .load(conn)
+                .with_context(|| "Failed to load offense counts")?;
+
+            let now = SystemTime::now();
+            let mut results = Vec::with_capacity(rows.len());
+            for row in rows {
+                let Some(offense) = OffenseType::parse(&row.offense_type) else {
+                    continue;
+                };
+                let policy = Self::load_policy(conn, guild, offense)?;
+                let (before, anchor) = policy.decay(row.count, row.last_offense_at, now);
+                let after = reduce_by.map_or(0, |n| before.saturating_sub(n.max(0)).max(0));
+
+                let target = offenses::table.find((user, guild, offense.as_str()));
+                if after == 0 {
+                    diesel::delete(target).execute(conn)
+                } else {
+                    diesel::update(target)
+                        .set((
+                            offenses::count.eq(after),
+                            offenses::last_offense_at.eq(anchor),
+                        ))
+                        .execute(conn)
+                }
+                .with_context(|| format!("Failed to update '{}' offenses", offense.as_str()))?;
+
+                results.push(Amnesty {
+                    offense,
+                    before,
+                    after,
+                });
+            }
+            Ok(results)
+        })
+    }
+
+    fn load_policy(conn: &mut PgConnection, guild: i64, offense: OffenseType) -> Result<Policy> {
+        // Discord IDs are always > 0, so the guild override sorts first.
+        let row = offense_policies::table
+            .filter(offense_policies::offense_type.eq(offense.as_str()))
+            .filter(offense_policies::guild_id.eq_any([GLOBAL_GUILD_ID, guild]))
+            .order(offense_policies::guild_id.desc())
+            .select(OffensePolicy::as_select())
+            .first(conn)
+            .optional()
+            .with_context(|| format!("Failed to load policy for '{}'", offense.as_str()))?;
+        Ok(match row {
+            Some(row) => Policy::from_row(&row),
+            None => {
+                eprintln!(
+                    "[offenses] No policy for '{}', using fallback",
+                    offense.as_str()
+                );
+                Policy::FALLBACK
+            }
+        })
+    }
+
+    fn key(id: u64, what: &str) -> Result<i64> {
+        i64::try_from(id).with_context(|| format!("{} ID {} exceeds i64::MAX", what, id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doubling() -> Policy {
+        Policy {
+            base_seconds: 1800,
+            multiplier: 2.0,
+            cap_seconds: 2_592_000,
+            decay_window: Some(Duration::from_secs(3600)),
+        }
+    }
+
+    #[test]
+    fn duration_escalates_and_caps() {
+        let policy = doubling();
+        assert_eq!(policy.duration_for(1), 1800);
+        assert_eq!(policy.duration_for(2), 3600);
+        assert_eq!(policy.duration_for(5), 1800 * 16);
+        assert_eq!(policy.duration_for(40), 2_592_000);
+        assert_eq!(policy.duration_for(i32::MAX), 2_592_000);
+    }
+
+    #[test]
+    fn flat_policy_never_escalates() {
+        let policy = Policy {
+            multiplier: 1.0,
+            cap_seconds: 300,
+            base_seconds: 300,
+            decay_window: None,
+        };
+        assert_eq!(policy.duration_for(1), 300);
+        assert_eq!(policy.duration_for(10), 300);
+    }
+
+    #[test]
+    fn count_halves_per_decay_window() {
+        let policy = doubling();
+        let now = SystemTime::now();
+        let ago = |secs| now - Duration::from_secs(secs);
+
+        assert_eq!(policy.decay(6, ago(60), now), (6, ago(60)));
+        assert_eq!(policy.decay(6, ago(3600 + 60), now), (3, ago(60)));
+        assert_eq!(policy.decay(6, ago(2 * 3600 + 60), now), (1, ago(60)));
+        assert_eq!(policy.decay(6, ago(100 * 3600), now).0, 0);
+    }
+
+    #[test]
+    fn no_decay_window_keeps_count() {
+        let policy = Policy {
+            decay_window: None,
+            ..doubling()
+        };
+        let now = SystemTime::now();
+        let long_ago = now - Duration::from_secs(365 * 86_400);
+        assert_eq!(policy.decay(6, long_ago, now), (6, long_ago));
+    }
+
+    #[test]
+    fn offense_types_round_trip() {
+        for offense in OffenseType::ALL {
+            assert_eq!(OffenseType::parse(offense.as_str()), Some(offense));
+        }
+        assert_eq!(OffenseType::parse("rejoin"), None);
+    }
+}
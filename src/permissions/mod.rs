@@ -0,0 +1,299 @@
+//! Capability-based permissions for moderator commands.
+//!
+//! Each guarded slash command requires a [`Capability`]. Guilds grant
+//! capabilities to roles or individual users with `/permissions`; a
+//! capability with no grants in a guild falls back to the guild's moderator
+//! roles from `/config`. Members with Administrator or Manage Server always
+//! pass, so a bad grant can't lock a server out of its own bot.
+
+use anyhow::{Context as _, Result};
+use serenity::all::{CommandInteraction, Http, Member, Permissions};
+
+use crate::db::{
+    delete_permission_grant, get_permission_grants, insert_permission_grant,
+    models::{NewPermissionGrant, PermissionGrant},
+    DbPool,
+};
+use crate::tugbot::guild_config::GuildConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    Gulag,
+    GulagRelease,
+    GulagHistory,
+    Amnesty,
+    AiSlop,
+    Cull,
+    Activity,
+    ManageFeatures,
+    ManageConfig,
+    ManagePermissions,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 10] = [
+        Capability::Gulag,
+        Capability::GulagRelease,
+        Capability::GulagHistory,
+        Capability::Amnesty,
+        Capability::AiSlop,
+        Capability::Cull,
+        Capability::Activity,
+        Capability::ManageFeatures,
+        Capability::ManageConfig,
+        Capability::ManagePermissions,
+    ];
+
+    /// Name stored in the database and shown in `/permissions`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Capability::Gulag => "gulag",
+            Capability::GulagRelease => "gulag-release",
+            Capability::GulagHistory => "gulag-history",
+            Capability::Amnesty => "amnesty",
+            Capability::AiSlop => "ai-slop",
+            Capability::Cull => "cull",
+            Capability::Activity => "activity",
+            Capability::ManageFeatures => "feature",
+            Capability::ManageConfig => "config",
+            Capability::ManagePermissions => "permissions",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Capability> {
+        Capability::ALL.into_iter().find(|c| c.as_str() == name)
+    }
+
+    /// The capability required to run an application command, if any.
+    pub fn for_command(command_name: &str) -> Option<Capability> {
+        match command_name {
+            "gulag" => Some(Capability::Gulag),
+            "gulag-release" => Some(Capability::GulagRelease),
+            "gulag-history" => Some(Capability::GulagHistory),
+            "amnesty" => Some(Capability::Amnesty),
+            "AI Slop" => Some(Capability::AiSlop),
+            "cull" => Some(Capability::Cull),
+            "activity" => Some(Capability::Activity),
+            "feature" => Some(Capability::ManageFeatures),
+            "config" => Some(Capability::ManageConfig),
+            "permissions" => Some(Capability::ManagePermissions),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Grantee {
+    Role(u64),
+    User(u64),
+}
+
+impl Grantee {
+    fn kind(&self) -> &'static str {
+        match self {
+            Grantee::Role(_) => "role",
+            Grantee::User(_) => "user",
+        }
+    }
+
+    fn id(&self) -> u64 {
+        match self {
+            Grantee::Role(id) | Grantee::User(id) => *id,
+        }
+    }
+
+    fn from_row(grant: &PermissionGrant) -> Option<Grantee> {
+        let id = u64::try_from(grant.grantee_id).ok()?;
+        match grant.grantee_type.as_str() {
+            "role" => Some(Grantee::Role(id)),
+            "user" => Some(Grantee::User(id)),
+            _ => None,
+        }
+    }
+
+    /// Discord mention for display.
+    pub fn mention(&self) -> String {
+        match self {
+            Grantee::Role(id) => format!("<@&{}>", id),
+            Grantee::User(id) => format!("<@{}>", id),
+        }
+    }
+}
+
+/// Check that the invoking member may run `command`.
+///
+/// Returns `Err` with a user-facing message when access is denied. Commands
+/// without a capability mapping are always allowed.
+pub async fn authorize(
+    http: &Http,
+    pool: &DbPool,
+    command: &CommandInteraction,
+) -> Result<(), String> {
+    let Some(capability) = Capability::for_command(&command.data.name) else {
+        return Ok(());
+    };
+    let (Some(guild_id), Some(member)) = (command.guild_id, command.member.as_deref()) else {
+        return Err("Error: This command can only be used in a server".to_string());
+    };
+    authorize_member(http, pool, guild_id.get(), member, capability).await
+}
+
+/// Check that `member` may use `capability`, for interactions that aren't
+/// slash commands (e.g. buttons). Same rules and messages as [`authorize`].
+pub async fn authorize_member(
+    http: &Http,
+    pool: &DbPool,
+    guild_id: u64,
+    member: &Member,
+    capability: Capability,
+) -> Result<(), String> {
+    if member
+        .permissions
+        .is_some_and(|p| p.contains(Permissions::ADMINISTRATOR) || p.manage_guild())
+    {
+        return Ok(());
+    }
+
+    let allowed = match has_capability(http, pool, guild_id, member, capability).await {
+        Ok(allowed) => allowed,
+        Err(e) => {
+            eprintln!("[permissions] {:#}", e);
+            return Err("Error: Could not verify your permissions".to_string());
+        }
+    };
+
+    if allowed {
+        Ok(())
+    } else {
+        Err(format!(
+            "Error: You don't have the `{}` permission on this server",
+            capability.as_str()
+        ))
+    }
+}
+
+/// Whether `member` holds `capability` through a grant, or through the
+/// guild's moderator roles when the capability has no grants.
+pub async fn has_capability(
+    http: &Http,
+    pool: &DbPool,
+    guild_id: u64,
+    member: &Member,
+    capability: Capability,
+) -> Result<bool> {
+    let grants = grants_for(pool, guild_id, Some(capability))?;
+    let roles: Vec<u64> = member.roles.iter().map(|r| r.get()).collect();
+    match decide(&grants, member.user.id.get(), &roles) {
+        Some(allowed) => Ok(allowed),
+        None => Ok(GuildConfig::load(pool, guild_id)
+            .is_moderator(http, member)
+            .await),
+    }
+}
+
+/// Decide from explicit grants. `None` means there are no grants and the
+/// caller should fall back to the moderator roles.
+fn decide(grants: &[Grantee], user_id: u64, role_ids: &[u64]) -> Option<bool> {
+    if grants.is_empty() {
+        return None;
+    }
+    Some(grants.iter().any(|grant| match grant {
+        Grantee::User(id) => *id == user_id,
+        Grantee::Role(id) => role_ids.contains(id),
+    }))
+}
+
+fn guild_key(guild_id: u64) -> Result<i64> {
+    i64::try_from(guild_id).with_context(|| format!("Guild ID {} exceeds i64::MAX", guild_id))
+}
+
+/// Grants in a guild, optionally for a single capability.
+pub fn grants_for(
+    pool: &DbPool,
+    guild_id: u64,
+    capability: Option<Capability>,
+) -> Result<Vec<Grantee>> {
+    let rows = get_permission_grants(pool, guild_key(guild_id)?, capability.map(|c| c.as_str()))
+        .with_context(|| format!("Failed to load permission grants for guild {}", guild_id))?;
+    Ok(rows.iter().filter_map(Grantee::from_row).collect())
+}
+
+/// Every grant in a guild, grouped by capability in `Capability::ALL` order.
+pub fn list(pool: &DbPool, guild_id: u64) -> Result<Vec<(Capability, Vec<Grantee>)>> {
+    let rows = get_permission_grants(pool, guild_key(guild_id)?, None)
+        .with_context(|| format!("Failed to load permission grants for guild {}", guild_id))?;
+    Ok(Capability::ALL
+        .into_iter()
+        .map(|cap| {
+            let grantees = rows
+                .iter()
+                .filter(|row| row.capability == cap.as_str())
+                .filter_map(Grantee::from_row)
+                .collect();
+            (cap, grantees)
+        })
+        .collect())
+}
+
+/// Grant a capability. Returns false if it was already granted.
+pub fn grant(pool: &DbPool, guild_id: u64, capability: Capability, to: Grantee) -> Result<bool> {
+    let row = new_row(guild_id, capability, &to)?;
+    insert_permission_grant(pool, &row).with_context(|| "Failed to save permission grant")
+}
+
+/// Revoke a capability. Returns false if there was no such grant.
+pub fn revoke(pool: &DbPool, guild_id: u64, capability: Capability, from: Grantee) -> Result<bool> {
+    let row = new_row(guild_id, capability, &from)?;
+    delete_permission_grant(pool, &row).with_context(|| "Failed to remove permission grant")
+}
+
+fn new_row(
+    guild_id: u64,
+    capability: Capability,
+    grantee: &Grantee,
+) -> Result<NewPermissionGrant<'static>> {
+    Ok(NewPermissionGrant {
+        guild_id: guild_key(guild_id)?,
+        capability: capability.as_str(),
+        grantee_type: grantee.kind(),
+        grantee_id: i64::try_from(grantee.id())
+            .with_context(|| format!("ID {} exceeds i64::MAX", grantee.id()))?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capability_names_round_trip() {
+        for cap in Capability::ALL {
+            assert_eq!(Capability::parse(cap.as_str()), Some(cap));
+        }
+        assert_eq!(Capability::parse("nope"), None);
+    }
+
+    #[test]
+    fn commands_map_to_capabilities() {
+        assert_eq!(Capability::for_command("AI Slop"), Some(Capability::AiSlop));
+        assert_eq!(
+            Capability::for_command("feature"),
+            Some(Capability::ManageFeatures)
+        );
+        assert_eq!(Capability::for_command("gulag-list"), None);
+        assert_eq!(Capability::for_command("horny"), None);
+    }
+
+    #[test]
+    fn decide_falls_back_without_grants() {
+        assert_eq!(decide(&[], 1, &[2]), None);
+    }
+
+    #[test]
+    fn decide_matches_user_or_role() {
+        let grants = [Grantee::Role(10), Grantee::User(20)];
+        assert_eq!(decide(&grants, 20, &[]), Some(true));
+        assert_eq!(decide(&grants, 1, &[10]), Some(true));
+        assert_eq!(decide(&grants, 1, &[11]), Some(false));
+    }
+}
@@ -0,0 +1,350 @@
+//! Live activity tracking for `user_activity`.
+//!
+//! Gateway events record activity into an in-memory buffer, and a background
+//! task flushes it with `bulk_upsert_activity_at` every [`FLUSH_INTERVAL`],
+//! so Postgres sees one upsert per interval instead of one per message.
+//! Messages always count. Reactions and voice joins only count in guilds
+//! with the `activity_reactions` / `activity_voice` feature enabled; that is
+//! checked once per guild per flush rather than once per event.
+//!
+//! Messages are also counted per channel, user and hour, and flushed into
+//! the `message_counts` / `message_hour_counts` rollups behind `/stats`.
+
+use crate::db::{
+    add_message_counts, bulk_upsert_activity_at, delete_conversations_before,
+    delete_message_counts_before,
+    models::{MessageCount, MessageHourCount},
+    DbPool, INSERT_CHUNK,
+};
+use crate::features::Features;
+use serenity::prelude::TypeMapKey;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How often buffered activity is written to the database.
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Days of message counts kept; older rollups are pruned once a day.
+pub const MESSAGE_COUNT_RETENTION_DAYS: i32 = 180;
+
+/// Days a conversation with tugbot is kept after its last turn; older ones
+/// are pruned along with the message counts.
+pub const CONVERSATION_RETENTION_DAYS: u64 = 30;
+
+const DAY: u64 = 86_400;
+
+/// Days since the Unix epoch (UTC), the `day` column of the message rollups.
+pub fn epoch_day(at: SystemTime) -> i32 {
+    at.duration_since(UNIX_EPOCH)
+        .map(|d| (d.as_secs() / DAY) as i32)
+        .unwrap_or(0)
+}
+
+fn hour_of_day(at: SystemTime) -> i16 {
+    at.duration_since(UNIX_EPOCH)
+        .map(|d| (d.as_secs() % DAY / 3600) as i16)
+        .unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ActivitySource {
+    Message,
+    Reaction,
+    Voice,
+}
+
+impl ActivitySource {
+    /// Feature flag that must be enabled for this source to count, if any.
+    fn feature(&self) -> Option<&'static str> {
+        match self {
+            ActivitySource::Message => None,
+            ActivitySource::Reaction => Some("activity_reactions"),
+            ActivitySource::Voice => Some("activity_voice"),
+        }
+    }
+}
+
+type Buffer = HashMap<(u64, u64, ActivitySource), SystemTime>;
+
+/// Message counts keyed by (guild, channel, user, day, hour).
+type MessageBuffer = HashMap<(u64, u64, u64, i32, i16), i32>;
+
+#[derive(Default)]
+pub struct ActivityTracker {
+    pending: Mutex<Buffer>,
+    messages: Mutex<MessageBuffer>,
+}
+
+pub struct ActivityTrackerKey;
+
+impl TypeMapKey for ActivityTrackerKey {
+    type Value = Arc<ActivityTracker>;
+}
+
+impl ActivityTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Note that `user` was active in `guild` at `at`.
+    pub fn record(&self, guild: u64, user: u64, source: ActivitySource, at: SystemTime) {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        pending
+            .entry((guild, user, source))
+            .and_modify(|seen| *seen = (*seen).max(at))
+            .or_insert(at);
+    }
+
+    /// Count a message by `user` in `channel` of `guild`, sent at `at`.
+    pub fn record_message(&self, guild: u64, channel: u64, user: u64, at: SystemTime) {
+        let mut messages = self.messages.lock().unwrap_or_else(|e| e.into_inner());
+        *messages
+            .entry((guild, channel, user, epoch_day(at), hour_of_day(at)))
+            .or_insert(0) += 1;
+    }
+
+    fn drain(&self) -> Buffer {
+        std::mem::take(&mut *self.pending.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Put records back after a failed flush, keeping the newest timestamps.
+    fn requeue(&self, records: Vec<(i64, i64, SystemTime)>) {
+        for (user, guild, at) in records {
+            if let (Ok(guild), Ok(user)) = (u64::try_from(guild), u64::try_from(user)) {
+                self.record(guild, user, ActivitySource::Message, at);
+            }
+        }
+    }
+
+    /// Write everything buffered so far. Returns the number of rows upserted.
+    pub fn flush(&self, pool: &DbPool) -> usize {
+        self.flush_activity(pool) + self.flush_message_counts(pool)
+    }
+
+    fn flush_activity(&self, pool: &DbPool) -> usize {
+        let drained = self.drain();
+        if drained.is_empty() {
+            return 0;
+        }
+
+        let mut enabled: HashMap<(u64, &'static str), bool> = HashMap::new();
+        let records = Self::collapse(drained, |guild, feature| {
+            *enabled
+                .entry((guild, feature))
+                .or_insert_with(|| Features::is_enabled(pool, Some(guild), feature))
+        });
+
+        let mut written = 0;
+        let mut chunks = records.chunks(INSERT_CHUNK);
+        while let Some(chunk) = chunks.next() {
+            match bulk_upsert_activity_at(pool, chunk.to_vec()) {
+                Ok(rows) => written += rows,
+                Err(e) => {
+                    eprintln!("[activity] Failed to flush activity: {}", e);
+                    self.requeue(chunk.to_vec());
+                    chunks.for_each(|rest| self.requeue(rest.to_vec()));
+                    break;
+                }
+            }
+        }
+        written
+    }
+
+    fn flush_message_counts(&self, pool: &DbPool) -> usize {
+        let drained = std::mem::take(&mut *self.messages.lock().unwrap_or_else(|e| e.into_inner()));
+        if drained.is_empty() {
+            return 0;
+        }
+
+        let (counts, hour_counts) = Self::roll_up(&drained);
+        match add_message_counts(pool, &counts, &hour_counts) {
+            Ok(rows) => rows,
+            Err(e) => {
+                eprintln!("[activity] Failed to flush message counts: {}", e);
+                // Nothing was written, so the counts can be added back as-is
+                let mut messages = self.messages.lock().unwrap_or_else(|e| e.into_inner());
+                for (key, count) in drained {
+                    *messages.entry(key).or_insert(0) += count;
+                }
+                0
+            }
+        }
+    }
+
+    /// Sum buffered message counts into daily per-channel-and-user rows and
+    /// hourly per-guild rows.
+    fn roll_up(drained: &MessageBuffer) -> (Vec<MessageCount>, Vec<MessageHourCount>) {
+        let mut daily: HashMap<(u64, u64, u64, i32), i32> = HashMap::new();
+        let mut hourly: HashMap<(u64, i32, i16), i32> = HashMap::new();
+        for (&(guild, channel, user, day, hour), &count) in drained {
+            *daily.entry((guild, channel, user, day)).or_insert(0) += count;
+            *hourly.entry((guild, day, hour)).or_insert(0) += count;
+        }
+
+        let counts = daily
+            .into_iter()
+            .map(|((guild, channel, user, day), count)| MessageCount {
+                guild_id: guild as i64,
+                channel_id: channel as i64,
+                user_id: user as i64,
+                day,
+                message_count: count,
+            })
+            .collect();
+        let hour_counts = hourly
+            .into_iter()
+            .map(|((guild, day, hour), count)| MessageHourCount {
+                guild_id: guild as i64,
+                day,
+                hour,
+                message_count: count,
+            })
+            .collect();
+        (counts, hour_counts)
+    }
+
+    /// Drop sources whose feature is off, then keep the newest timestamp per
+    /// (user, guild).
+    fn collapse(
+        drained: Buffer,
+        mut is_enabled: impl FnMut(u64, &'static str) -> bool,
+    ) -> Vec<(i64, i64, SystemTime)> {
+        let mut latest: HashMap<(i64, i64), SystemTime> = HashMap::new();
+        let mut skipped: HashSet<(u64, ActivitySource)> = HashSet::new();
+        for ((guild, user, source), at) in drained {
+            if skipped.contains(&(guild, source)) {
+                continue;
+            }
+            if let Some(feature) = source.feature() {
+                if !is_enabled(guild, feature) {
+                    skipped.insert((guild, source));
+                    continue;
+                }
+            }
+            let (Ok(guild), Ok(user)) = (i64::try_from(guild), i64::try_from(user)) else {
+                continue;
+            };
+            latest
+                .entry((user, guild))
+                .and_modify(|seen| *seen = (*seen).max(at))
+                .or_insert(at);
+        }
+        latest
+            .into_iter()
+            .map(|((user, guild), at)| (user, guild, at))
+            .collect()
+    }
+
+    /// Flush on an interval for the life of the process, and once a day prune
+    /// message counts older than [`MESSAGE_COUNT_RETENTION_DAYS`] and
+    /// conversations idle for [`CONVERSATION_RETENTION_DAYS`].
+    pub fn run_flush_loop(self: &Arc<Self>, pool: DbPool) {
+        let tracker = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+            let mut pruned_day = 0;
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let tracker = Arc::clone(&tracker);
+                let pool = pool.clone();
+                let today = epoch_day(SystemTime::now());
+                let prune = today != pruned_day;
+                pruned_day = today;
+                // Diesel is blocking; keep it off the async workers.
+                let flushed = tokio::task::spawn_blocking(move || {
+                    if prune {
+                        let cutoff = today - MESSAGE_COUNT_RETENTION_DAYS;
+                        if let Err(e) = delete_message_counts_before(&pool, cutoff) {
+                            eprintln!("[activity] Failed to prune message counts: {}", e);
+                        }
+                        let idle = Duration::from_secs(CONVERSATION_RETENTION_DAYS * DAY);
+                        let cutoff = SystemTime::now() - idle;
+                        if let Err(e) = delete_conversations_before(&pool, cutoff) {
+                            eprintln!("[activity] Failed to prune conversations: {}", e);
+                        }
+                    }
+                    tracker.flush(&pool)
+                })
+                .await;
+                match flushed {
+                    Ok(0) => {}
+                    Ok(rows) => eprintln!("[activity] Flushed {} activity rows", rows),
+                    Err(e) => eprintln!("[activity] Flush task failed: {}", e),
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_keeps_newest_timestamp() {
+        let tracker = ActivityTracker::new();
+        let t0 = SystemTime::UNIX_EPOCH + Duration::from_secs(100);
+        let t1 = t0 + Duration::from_secs(50);
+        tracker.record(1, 2, ActivitySource::Message, t1);
+        tracker.record(1, 2, ActivitySource::Message, t0);
+
+        let drained = tracker.drain();
+        assert_eq!(drained.len(), 1);
+        assert_eq!(drained[&(1, 2, ActivitySource::Message)], t1);
+        assert!(tracker.drain().is_empty());
+    }
+
+    #[test]
+    fn collapse_filters_disabled_sources_and_merges() {
+        let t0 = SystemTime::UNIX_EPOCH + Duration::from_secs(100);
+        let t1 = t0 + Duration::from_secs(50);
+        let mut buffer = Buffer::new();
+        buffer.insert((1, 2, ActivitySource::Message), t0);
+        buffer.insert((1, 2, ActivitySource::Voice), t1);
+        buffer.insert((1, 3, ActivitySource::Reaction), t1);
+        buffer.insert((9, 3, ActivitySource::Reaction), t1);
+
+        let mut checks = 0;
+        let mut records = ActivityTracker::collapse(buffer, |guild, feature| {
+            checks += 1;
+            guild == 1 && feature == "activity_voice"
+        });
+        records.sort();
+
+        assert_eq!(records, vec![(2, 1, t1)]);
+        assert_eq!(checks, 3);
+    }
+
+    #[test]
+    fn message_counts_roll_up_by_day_and_hour() {
+        let tracker = ActivityTracker::new();
+        // 2024-01-15 10:30 and 11:15 UTC, and 2024-01-16 10:00 UTC
+        let day = 19737;
+        let at = |day: u64, secs: u64| UNIX_EPOCH + Duration::from_secs(day * DAY + secs);
+        tracker.record_message(1, 10, 5, at(day, 10 * 3600 + 1800));
+        tracker.record_message(1, 10, 5, at(day, 11 * 3600 + 900));
+        tracker.record_message(1, 11, 6, at(day, 10 * 3600 + 1800));
+        tracker.record_message(1, 10, 5, at(day + 1, 10 * 3600));
+
+        let drained = std::mem::take(&mut *tracker.messages.lock().unwrap());
+        let (mut counts, mut hour_counts) = ActivityTracker::roll_up(&drained);
+        counts.sort_by_key(|c| (c.day, c.channel_id));
+        hour_counts.sort_by_key(|c| (c.day, c.hour));
+
+        let daily: Vec<(i64, i64, i32, i32)> = counts
+            .iter()
+            .map(|c| (c.channel_id, c.user_id, c.day, c.message_count))
+            .collect();
+        assert_eq!(
+            daily,
+            vec![(10, 5, 19737, 2), (11, 6, 19737, 1), (10, 5, 19738, 1)]
+        );
+        let hourly: Vec<(i32, i16, i32)> = hour_counts
+            .iter()
+            .map(|c| (c.day, c.hour, c.message_count))
+            .collect();
+        assert_eq!(hourly, vec![(19737, 10, 2), (19737, 11, 1), (19738, 10, 1)]);
+    }
+}
@@ -67,7 +67,8 @@ impl Config {
             .union(GatewayIntents::MESSAGE_CONTENT)
             .union(GatewayIntents::GUILD_MESSAGES)
             .union(GatewayIntents::GUILD_MESSAGE_REACTIONS)
-            .union(GatewayIntents::GUILD_MESSAGE_POLLS);
+            .union(GatewayIntents::GUILD_MESSAGE_POLLS)
+            .union(GatewayIntents::GUILD_VOICE_STATES);
         Config {
             db_url,
             token,
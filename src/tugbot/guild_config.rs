@@ -0,0 +1,315 @@
+//! Per-guild configuration resolved from the `guild_settings` table.
+//!
+//! Every value is optional in the database. When a guild hasn't configured
+//! something, the legacy defaults below apply, so the original server keeps
+//! behaving exactly as it did before `/config` existed.
+
+use std::{
+    collections::HashSet,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::Context as _;
+use serenity::all::{Channel, ChannelId, GuildChannel, Http, Member};
+
+use crate::db::{get_guild_settings, models::GuildSettings, upsert_guild_settings, DbPool};
+
+/// #ask-tugbot — the only channel where mentions are answered by default.
+pub const DEFAULT_ASK_CHANNEL_ID: u64 = 1515343076401479790;
+/// #cat-herding — moderator-only output channel for cull reports.
+pub const DEFAULT_MOD_LOG_CHANNEL_ID: u64 = 1224402885786472659;
+/// Gulag announcements go to the channel with this name by default.
+pub const DEFAULT_GULAG_CHANNEL_NAME: &str = "the-gulag";
+/// Members with any of these roles can use moderator commands by default.
+pub const DEFAULT_MODERATOR_ROLES: &[&str] = &["Highly Regarded", "admin"];
+/// Members with any of these roles are never culled by default.
+pub const DEFAULT_CULL_WHITELIST_ROLES: &[&str] = &["Highly Regarded", "admin"];
+/// Members who joined less than this many days ago are never culled by default.
+pub const DEFAULT_CULL_GRACE_DAYS: u32 = 14;
+
+#[derive(Debug, Clone, Default)]
+pub struct GuildConfig {
+    pub guild_id: u64,
+    pub ask_channel_id: Option<u64>,
+    pub mod_log_channel_id: Option<u64>,
+    pub gulag_channel_id: Option<u64>,
+    pub moderator_role_ids: Vec<u64>,
+    pub cull_whitelist_role_ids: Vec<u64>,
+    pub cull_grace_days: Option<u32>,
+}
+
+impl GuildConfig {
+    /// Load the configuration for a guild, returning an error if the database
+    /// is unreachable. A guild without a row gets the defaults.
+    pub fn try_load(pool: &DbPool, guild_id: u64) -> anyhow::Result<GuildConfig> {
+        let guild_id_i64 = i64::try_from(guild_id)
+            .with_context(|| format!("Guild ID {} exceeds i64::MAX", guild_id))?;
+        let row = get_guild_settings(pool, guild_id_i64)
+            .with_context(|| format!("Failed to load settings for guild {}", guild_id))?;
+        Ok(match row {
+            Some(row) => GuildConfig::from_row(guild_id, &row),
+            None => GuildConfig {
+                guild_id,
+                ..Default::default()
+            },
+        })
+    }
+
+    /// Load the configuration for a guild, falling back to the defaults on any
+    /// error. Use this on message/event paths that shouldn't fail loudly.
+    pub fn load(pool: &DbPool, guild_id: u64) -> GuildConfig {
+        GuildConfig::try_load(pool, guild_id).unwrap_or_else(|e| {
+            eprintln!("[guild_config] {:#}, using defaults", e);
+            GuildConfig {
+                guild_id,
+                ..Default::default()
+            }
+        })
+    }
+
+    /// Persist this configuration, replacing the guild's existing row.
+    pub fn save(&self, pool: &DbPool) -> anyhow::Result<()> {
+        let row = self.to_row()?;
+        upsert_guild_settings(pool, &row)
+            .with_context(|| format!("Failed to save settings for guild {}", self.guild_id))?;
+        Ok(())
+    }
+
+    fn from_row(guild_id: u64, row: &GuildSettings) -> GuildConfig {
+        let ids = |v: &[Option<i64>]| -> Vec<u64> {
+            v.iter()
+                .flatten()
+                .filter_map(|id| u64::try_from(*id).ok())
+                .collect()
+        };
+        GuildConfig {
+            guild_id,
+            ask_channel_id: row.ask_channel_id.and_then(|id| u64::try_from(id).ok()),
+            mod_log_channel_id: row.mod_log_channel_id.and_then(|id| u64::try_from(id).ok()),
+            gulag_channel_id: row.gulag_channel_id.and_then(|id| u64::try_from(id).ok()),
+            moderator_role_ids: ids(&row.moderator_role_ids),
+            cull_whitelist_role_ids: ids(&row.cull_whitelist_role_ids),
+            cull_grace_days: row.cull_grace_days.and_then(|d| u32::try_from(d).ok()),
+        }
+    }
+
+    fn to_row(&self) -> anyhow::Result<GuildSettings> {
+        let id = |v: u64| i64::try_from(v).with_context(|| format!("ID {} exceeds i64::MAX", v));
+        let opt = |v: Option<u64>| v.map(id).transpose();
+        let ids = |v: &[u64]| -> anyhow::Result<Vec<Option<i64>>> {
+            v.iter().map(|r| id(*r).map(Some)).collect()
+        };
+        Ok(GuildSettings {
+            guild_id: id(self.guild_id)?,
+            ask_channel_id: opt(self.ask_channel_id)?,
+            mod_log_channel_id: opt(self.mod_log_channel_id)?,
+            gulag_channel_id: opt(self.gulag_channel_id)?,
+            moderator_role_ids: ids(&self.moderator_role_ids)?,
+            cull_whitelist_role_ids: ids(&self.cull_whitelist_role_ids)?,
+            updated_at: SystemTime::now(),
+            cull_grace_days: self
+                .cull_grace_days
+                .map(|d| i32::try_from(d).context("Cull grace period is too long"))
+                .transpose()?,
+        })
+    }
+
+    /// How long new members are protected from culls.
+    pub fn cull_grace_period(&self) -> Duration {
+        let days = self.cull_grace_days.unwrap_or(DEFAULT_CULL_GRACE_DAYS);
+        Duration::from_secs(days as u64 * 86_400)
+    }
+
+    /// Channel where bot mentions are answered.
+    pub fn ask_channel_id(&self) -> u64 {
+        self.ask_channel_id.unwrap_or(DEFAULT_ASK_CHANNEL_ID)
+    }
+
+    /// Channel for moderation reports, or `None` if it doesn't belong to this
+    /// guild. The default only exists on the original server, so the check
+    /// stops other guilds' reports from leaking into it.
+    pub async fn mod_log_channel(&self, http: &Http) -> Option<ChannelId> {
+        let channel_id = ChannelId::new(
+            self.mod_log_channel_id
+                .unwrap_or(DEFAULT_MOD_LOG_CHANNEL_ID),
+        );
+        match http.get_channel(channel_id).await {
+            Ok(Channel::Guild(channel)) if channel.guild_id.get() == self.guild_id => {
+                Some(channel_id)
+            }
+            Ok(_) => None,
+            Err(e) => {
+                eprintln!(
+                    "[guild_config] Failed to look up mod log channel {}: {}",
+                    channel_id, e
+                );
+                None
+            }
+        }
+    }
+
+    /// Look up the gulag announcement channel.
+    ///
+    /// Same contract as `Gulag::find_channel`: `Ok(None)` means the channel
+    /// doesn't exist, `Err` means Discord returned an error.
+    pub async fn gulag_channel(
+        &self,
+        http: &Arc<Http>,
+    ) -> Result<Option<GuildChannel>, serenity::Error> {
+        let channels = http.get_channels(self.guild_id.into()).await?;
+        Ok(channels
+            .into_iter()
+            .find(|channel| match self.gulag_channel_id {
+                Some(id) => channel.id.get() == id,
+                None => channel.name == DEFAULT_GULAG_CHANNEL_NAME,
+            }))
+    }
+
+    /// Role IDs that grant access to moderator commands.
+    pub async fn moderator_role_ids(&self, http: &Http) -> Result<HashSet<u64>, serenity::Error> {
+        self.resolve_roles(http, &self.moderator_role_ids, DEFAULT_MODERATOR_ROLES)
+            .await
+    }
+
+    /// Role IDs whose members are never culled.
+    pub async fn cull_whitelist_role_ids(
+        &self,
+        http: &Http,
+    ) -> Result<HashSet<u64>, serenity::Error> {
+        self.resolve_roles(
+            http,
+            &self.cull_whitelist_role_ids,
+            DEFAULT_CULL_WHITELIST_ROLES,
+        )
+        .await
+    }
+
+    /// Check whether a member holds any moderator role. Fails closed.
+    pub async fn is_moderator(&self, http: &Http, member: &Member) -> bool {
+        match self.moderator_role_ids(http).await {
+            Ok(role_ids) => member.roles.iter().any(|r| role_ids.contains(&r.get())),
+            Err(e) => {
+                eprintln!("[guild_config] Failed to resolve moderator roles: {}", e);
+                false
+            }
+        }
+    }
+
+    async fn resolve_roles(
+        &self,
+        http: &Http,
+        configured: &[u64],
+        default_names: &[&str],
+    ) -> Result<HashSet<u64>, serenity::Error> {
+        if !configured.is_empty() {
+            return Ok(configured.iter().copied().collect());
+        }
+        let roles = http.get_guild_roles(self.guild_id.into()).await?;
+        Ok(roles
+            .into_iter()
+            .filter(|role| default_names.contains(&role.name.as_str()))
+            .map(|role| role.id.get())
+            .collect())
+    }
+
+    /// Human-readable summary used by `/config view`.
+    pub fn describe(&self) -> String {
+        let channel = |id: Option<u64>, default: String| match id {
+            Some(id) => format!("<#{}>", id),
+            None => format!("{} (default)", default),
+        };
+        let roles = |ids: &[u64], default: &[&str]| {
+            if ids.is_empty() {
+                format!("{} (default)", default.join(", "))
+            } else {
+                ids.iter()
+                    .map(|id| format!("<@&{}>", id))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+        };
+
+        format!(
+            "**Server configuration**\nAsk channel: {}\nModeration log channel: {}\nGulag channel: {}\nModerator roles: {}\nCull whitelist roles: {}\nCull grace period for new members: {}",
+            channel(self.ask_channel_id, format!("<#{}>", DEFAULT_ASK_CHANNEL_ID)),
+            channel(
+                self.mod_log_channel_id,
+                format!("<#{}>", DEFAULT_MOD_LOG_CHANNEL_ID)
+            ),
+            channel(
+                self.gulag_channel_id,
+                format!("#{}", DEFAULT_GULAG_CHANNEL_NAME)
+            ),
+            roles(&self.moderator_role_ids, DEFAULT_MODERATOR_ROLES),
+            roles(&self.cull_whitelist_role_ids, DEFAULT_CULL_WHITELIST_ROLES),
+            match self.cull_grace_days {
+                Some(days) => format!("{} days", days),
+                None => format!("{} days (default)", DEFAULT_CULL_GRACE_DAYS),
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_apply_when_unset() {
+        let config = GuildConfig {
+            guild_id: 1,
+            ..Default::default()
+        };
+        assert_eq!(config.ask_channel_id(), DEFAULT_ASK_CHANNEL_ID);
+        let text = config.describe();
+        assert!(text.contains("#the-gulag (default)"));
+        assert!(text.contains("Highly Regarded, admin (default)"));
+        assert!(text.contains("Cull grace period for new members: 14 days (default)"));
+    }
+
+    #[test]
+    fn configured_values_override_defaults() {
+        let config = GuildConfig {
+            guild_id: 1,
+            ask_channel_id: Some(10),
+            gulag_channel_id: Some(11),
+            moderator_role_ids: vec![12, 13],
+            ..Default::default()
+        };
+        assert_eq!(config.ask_channel_id(), 10);
+        let text = config.describe();
+        assert!(text.contains("Gulag channel: <#11>"));
+        assert!(text.contains("Moderator roles: <@&12>, <@&13>"));
+    }
+
+    #[test]
+    fn row_round_trip() {
+        let config = GuildConfig {
+            guild_id: 5,
+            ask_channel_id: Some(6),
+            mod_log_channel_id: None,
+            gulag_channel_id: Some(7),
+            moderator_role_ids: vec![8],
+            cull_whitelist_role_ids: vec![9, 10],
+            cull_grace_days: Some(3),
+        };
+        let row = config.to_row().unwrap();
+        assert_eq!(row.moderator_role_ids, vec![Some(8)]);
+        let back = GuildConfig::from_row(5, &row);
+        assert_eq!(back.ask_channel_id, Some(6));
+        assert_eq!(back.mod_log_channel_id, None);
+        assert_eq!(back.gulag_channel_id, Some(7));
+        assert_eq!(back.cull_whitelist_role_ids, vec![9, 10]);
+        assert_eq!(back.cull_grace_period(), Duration::from_secs(3 * 86_400));
+    }
+
+    #[test]
+    fn to_row_rejects_overflowing_ids() {
+        let config = GuildConfig {
+            guild_id: u64::MAX,
+            ..Default::default()
+        };
+        assert!(config.to_row().is_err());
+    }
+}
@@ -1,2 +1,4 @@
+pub mod activity;
 pub mod config;
+pub mod guild_config;
 pub mod servers;
@@ -0,0 +1,191 @@
+//! End-to-end tests of the pi RPC worker pool against a fake pi
+//! (tests/support/fake_pi.rs), which speaks pi's JSONL protocol. The fake is
+//! this test binary re-run with `FAKE_PI_ENV` set, so nothing extra ships in
+//! the package.
+
+mod support;
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tugbot::ask::{AskBackend, AskBusy, AskEvent};
+use tugbot::pi_rpc::{PiRpc, PiRpcOptions};
+
+/// Set for the copies of this binary that stand in for pi.
+const FAKE_PI_ENV: &str = "TUGBOT_FAKE_PI";
+
+/// Not a real test: in a copy started by `spawn` it serves pi's protocol on
+/// stdin and stdout until the worker closes stdin.
+#[test]
+fn fake_pi() {
+    if std::env::var_os(FAKE_PI_ENV).is_some() {
+        support::fake_pi::run();
+    }
+}
+
+async fn spawn(workers: usize, timeout: Duration) -> Arc<PiRpc> {
+    let test_binary = std::env::current_exe().expect("No path to the test binary");
+    // `env` sets the variable for the child without touching this process's
+    // environment, which the other tests share. `--quiet` keeps the harness
+    // from starting a "test fake_pi ..." line that the first response would
+    // land on; its other output is skipped like any non-JSON line.
+    PiRpc::spawn_with(PiRpcOptions {
+        binary: "env".to_string(),
+        args: vec![
+            format!("{}=1", FAKE_PI_ENV),
+            test_binary.to_string_lossy().into_owned(),
+            "fake_pi".to_string(),
+            "--exact".to_string(),
+            "--nocapture".to_string(),
+            "--quiet".to_string(),
+        ],
+        workers,
+        timeout,
+    })
+    .await
+    .expect("Failed to spawn fake pi")
+}
+
+/// The process ID fake pi put at the end of an answer.
+fn pid(answer: &str) -> u32 {
+    answer
+        .rsplit_once("[pid ")
+        .and_then(|(_, pid)| pid.trim_end_matches(']').parse().ok())
+        .unwrap_or_else(|| panic!("no pid in answer {:?}", answer))
+}
+
+#[tokio::test]
+async fn answers_and_streams_events() {
+    let pi_rpc = spawn(1, Duration::from_secs(10)).await;
+
+    let mut stream = pi_rpc.ask_streaming("tool is it real?", &[]).unwrap();
+    let mut events = Vec::new();
+    while let Some(event) = stream.next_event().await {
+        events.push(event);
+    }
+    let answer = stream.finish().await.unwrap();
+    assert!(answer.starts_with("tool is it real? [pid "));
+
+    assert!(matches!(&events[0], AskEvent::ToolStart { name, .. } if name == "web_search"));
+    assert_eq!(
+        events[1],
+        AskEvent::ToolEnd {
+            name: "web_search".to_string()
+        }
+    );
+    let streamed: String = events[2..]
+        .iter()
+        .map(|event| match event {
+            AskEvent::TextDelta(delta) => delta.as_str(),
+            other => panic!("unexpected event {:?}", other),
+        })
+        .collect();
+    assert_eq!(streamed, answer);
+}
+
+#[tokio::test]
+async fn skips_malformed_lines() {
+    let pi_rpc = spawn(1, Duration::from_secs(10)).await;
+    let answer = pi_rpc.ask("malformed but fine").await.unwrap();
+    assert!(answer.starts_with("malformed but fine [pid "));
+}
+
+#[tokio::test]
+async fn reports_rejections_and_agent_errors() {
+    let pi_rpc = spawn(1, Duration::from_secs(10)).await;
+
+    let rejected = pi_rpc.ask("reject this").await.unwrap_err();
+    assert!(rejected.to_string().contains("rejected by fake pi"));
+    let failed = pi_rpc.ask("fail this").await.unwrap_err();
+    assert!(failed
+        .to_string()
+        .contains("agent_end error: fake pi failed"));
+
+    // Neither takes the subprocess down
+    let first = pi_rpc.ask("hello").await.unwrap();
+    let second = pi_rpc.ask("hello again").await.unwrap();
+    assert_eq!(pid(&first), pid(&second));
+}
+
+#[tokio::test]
+async fn restarts_after_a_crash() {
+    let pi_rpc = spawn(1, Duration::from_secs(10)).await;
+    let before = pid(&pi_rpc.ask("hello").await.unwrap());
+
+    let crashed = pi_rpc.ask("crash now").await.unwrap_err();
+    assert!(crashed.to_string().contains("EOF on stdout"));
+
+    let after = pid(&pi_rpc.ask("hello").await.unwrap());
+    assert_ne!(before, after);
+}
+
+#[tokio::test]
+async fn times_out_and_restarts_a_hung_subprocess() {
+    let pi_rpc = spawn(1, Duration::from_secs(1)).await;
+    let before = pid(&pi_rpc.ask("hello").await.unwrap());
+
+    let started = Instant::now();
+    let hung = pi_rpc.ask("hang forever").await.unwrap_err();
+    assert!(hung.to_string().contains("1s"), "{}", hung);
+    assert!(started.elapsed() < Duration::from_secs(3));
+
+    let after = pid(&pi_rpc.ask("hello").await.unwrap());
+    assert_ne!(before, after);
+}
+
+#[tokio::test]
+async fn abandoned_requests_free_the_worker() {
+    let pi_rpc = spawn(1, Duration::from_secs(30)).await;
+    let before = pid(&pi_rpc.ask("hello").await.unwrap());
+
+    let stream = pi_rpc.ask_streaming("hang forever", &[]).unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    drop(stream);
+
+    // Answered by a fresh subprocess long before the 30s timeout
+    let started = Instant::now();
+    let after = pid(&pi_rpc.ask("hello").await.unwrap());
+    assert!(started.elapsed() < Duration::from_secs(5));
+    assert_ne!(before, after);
+}
+
+#[tokio::test]
+async fn queued_requests_keep_their_enqueue_deadline() {
+    let pi_rpc = spawn(1, Duration::from_millis(1500)).await;
+
+    // The hang waits ~1s behind the delay, so only ~0.5s of its deadline is left
+    let first = pi_rpc.ask_streaming("delay 1000 first", &[]).unwrap();
+    let second = pi_rpc.ask_streaming("hang second", &[]).unwrap();
+    first.finish().await.unwrap();
+    let started = Instant::now();
+    assert!(second.finish().await.is_err());
+    let answered = pi_rpc.ask("hello").await;
+    assert!(answered.is_ok(), "{:?}", answered);
+    assert!(started.elapsed() < Duration::from_millis(1400));
+}
+
+#[tokio::test]
+async fn workers_answer_concurrently() {
+    let pi_rpc = spawn(2, Duration::from_secs(10)).await;
+
+    let started = Instant::now();
+    let (first, second) = tokio::join!(pi_rpc.ask("delay 800 one"), pi_rpc.ask("delay 800 two"));
+    assert!(started.elapsed() < Duration::from_millis(1500));
+    assert_ne!(pid(&first.unwrap()), pid(&second.unwrap()));
+}
+
+#[tokio::test]
+async fn rejects_requests_when_the_queue_is_full() {
+    let pi_rpc = spawn(1, Duration::from_secs(10)).await;
+
+    // One in progress plus a full queue; the slow answers are never awaited
+    let mut streams = Vec::new();
+    let busy = loop {
+        match pi_rpc.ask_streaming("delay 5000 slow", &[]) {
+            Ok(stream) => streams.push(stream),
+            Err(e) => break e,
+        }
+        assert!(streams.len() <= 3, "queue never filled up");
+    };
+    assert!(busy.downcast_ref::<AskBusy>().is_some());
+    assert!(streams.len() >= 2);
+}
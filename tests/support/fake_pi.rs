@@ -0,0 +1,125 @@
+//! A stand-in for `pi --mode rpc` used by the pi RPC integration tests, which
+//! re-run their own test binary in this mode. It speaks the same JSONL protocol and picks its behaviour from the first
+//! word of each prompt:
+//!
+//! - `delay <ms> ...` — wait before answering
+//! - `tool ...` — run a pretend `web_search` first
+//! - `malformed ...` — write a line of invalid JSON before answering
+//! - `reject ...` — refuse the prompt
+//! - `fail ...` — end the run with an `agent_end` error
+//! - `crash ...` — exit partway through the answer
+//! - `hang ...` — accept the prompt and never answer
+//!
+//! Anything else is echoed back. Answers end with `[pid <pid>]` so tests
+//! can tell a restarted subprocess from the one before it.
+
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+use std::time::Duration;
+
+pub fn run() {
+    let stdin = std::io::stdin();
+    let mut out = std::io::stdout().lock();
+
+    for line in stdin.lock().lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        let Ok(command) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        let id = command["id"].as_str().unwrap_or_default();
+        let message = command["message"].as_str().unwrap_or_default();
+        let mut words = message.split_whitespace();
+        let behaviour = words.next().unwrap_or_default();
+
+        if behaviour == "reject" {
+            send(
+                &mut out,
+                json!({
+                    "id": id, "type": "response", "command": "prompt",
+                    "success": false, "error": "rejected by fake pi"
+                }),
+            );
+            continue;
+        }
+        send(
+            &mut out,
+            json!({ "id": id, "type": "response", "command": "prompt", "success": true }),
+        );
+        send(&mut out, json!({ "type": "agent_start" }));
+
+        match behaviour {
+            "delay" => {
+                let ms = words.next().and_then(|ms| ms.parse().ok()).unwrap_or(0);
+                std::thread::sleep(Duration::from_millis(ms));
+            }
+            "tool" => {
+                send(
+                    &mut out,
+                    json!({
+                        "type": "tool_execution_start", "toolCallId": "call_1",
+                        "toolName": "web_search", "args": { "query": message }
+                    }),
+                );
+                send(
+                    &mut out,
+                    json!({
+                        "type": "tool_execution_end", "toolCallId": "call_1",
+                        "toolName": "web_search", "result": {}
+                    }),
+                );
+            }
+            "malformed" => {
+                writeln!(out, "{{this is not json").unwrap();
+                out.flush().unwrap();
+            }
+            "fail" => {
+                send(
+                    &mut out,
+                    json!({ "type": "agent_end", "messages": [], "error": "fake pi failed" }),
+                );
+                continue;
+            }
+            "hang" => loop {
+                std::thread::sleep(Duration::from_secs(3600));
+            },
+            _ => {}
+        }
+
+        let answer = format!("{} [pid {}]", message, std::process::id());
+        let middle = answer
+            .char_indices()
+            .nth(answer.chars().count() / 2)
+            .map_or(0, |(i, _)| i);
+        let (first, rest) = answer.split_at(middle);
+        send(&mut out, text_delta(first));
+        if behaviour == "crash" {
+            std::process::exit(1);
+        }
+        send(&mut out, text_delta(rest));
+        send(
+            &mut out,
+            json!({
+                "type": "agent_end",
+                "messages": [
+                    { "role": "user", "content": message },
+                    { "role": "assistant", "content": [{ "type": "text", "text": answer }] }
+                ]
+            }),
+        );
+    }
+}
+
+fn text_delta(delta: &str) -> Value {
+    json!({
+        "type": "message_update",
+        "message": {},
+        "assistantMessageEvent": { "type": "text_delta", "delta": delta }
+    })
+}
+
+fn send(out: &mut impl Write, event: Value) {
+    writeln!(out, "{}", event).unwrap();
+    out.flush().unwrap();
+}